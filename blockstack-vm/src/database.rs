@@ -4,6 +4,13 @@ use errors::Error;
 use InterpreterResult;
 use types::{ValueType, TypeSignature, TupleTypeSignature, AtomTypeIdentifier};
 
+/// A single map/key read or write. A missing key is a legitimate, expected outcome
+/// (`fetch_entry` returns `Ok(ValueType::VoidType)`, `delete_entry` returns `Ok(BoolType(false))`)
+/// and must stay distinguishable from the backend itself failing: once a non-memory backend
+/// exists (disk, network, a replica that hasn't synced a chunk yet -- see `ReplicatedDataMap`), an
+/// I/O or decode failure has to propagate as `Err(Error::DatabaseCorrupt(..))`, not get flattened
+/// into "the key wasn't there". Silently treating corruption as an empty result can let a node
+/// compute a wrong state root instead of halting.
 pub trait DataMap {
     fn fetch_entry(&self, key: &ValueType) -> InterpreterResult;
     fn set_entry(&mut self, key: ValueType, value: ValueType) -> Result<(), Error>;
@@ -12,7 +19,10 @@ pub trait DataMap {
 }
 
 pub trait ContractDatabase {
-    fn get_data_map(&mut self, map_name: &str) -> Option<&mut DataMap>;
+    /// `Ok(None)` means no such map was ever declared; `Err(..)` means the backend holding the
+    /// map couldn't be reached or returned something unreadable. Only the former is a normal,
+    /// expected outcome a caller should treat as "map not present".
+    fn get_data_map(&mut self, map_name: &str) -> Result<Option<&mut DataMap>, Error>;
     fn create_map(&mut self, map_name: &str, key_type: TupleTypeSignature, value_type: TupleTypeSignature);
 }
 
@@ -46,11 +56,14 @@ impl MemoryContractDatabase {
 }
 
 impl ContractDatabase for MemoryContractDatabase {
-    fn get_data_map(&mut self, map_name: &str) -> Option<&mut DataMap> {
+    fn get_data_map(&mut self, map_name: &str) -> Result<Option<&mut DataMap>, Error> {
+        // A plain in-memory HashMap has no way to fail a lookup short of the map never having
+        // been declared, so there's no `Err` path here -- see `ReplicatedContractDatabase` for a
+        // backend where one actually exists.
         if let Some(data_map) = self.maps.get_mut(map_name) {
-            Some(data_map)
+            Ok(Some(data_map))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -60,6 +73,218 @@ impl ContractDatabase for MemoryContractDatabase {
     }
 }
 
+/// A single versioned, signed write to a replicated data-map slot, modeled on StackerDB's chunk
+/// gossip format: a write only replaces what's currently held if `version` strictly exceeds it;
+/// ties (equal `version`) are broken by preferring the lexicographically greater `signature`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReplicatedChunk {
+    version: u64,
+    value: ValueType,
+    signature: Vec<u8>,
+}
+
+/// Checks `signature` against each of `authorized_signers` for a `(slot_id, version, value)`
+/// write.
+///
+/// This prototype crate has no public-key cryptography wired in yet, so this is a placeholder: a
+/// chunk verifies only if its signature bytes exactly equal one of the authorized signer entries.
+/// Swapping in real signature verification (e.g. secp256k1 over a canonical encoding of
+/// `(slot_id, version, value)`) is additive once that machinery exists.
+fn verify_chunk_signature(_slot_id: &str, _version: u64, _value: &ValueType, signature: &[u8], authorized_signers: &[Vec<u8>]) -> bool {
+    authorized_signers.iter().any(|signer| signer.as_slice() == signature)
+}
+
+/// A `DataMap` whose entries live in an off-chain, node-replicated store rather than local
+/// consensus state, keyed by a `slot_id` derived from the map key.
+///
+/// Local writes made through the `DataMap` trait (a contract's own `map-set`/`map-insert`/
+/// `map-delete`) are authoritative -- they're the contract's own execution, not an external
+/// gossip write -- and simply bump the slot's version with no signature required. Writes arriving
+/// from the replication/gossip layer instead go through `receive_chunk`, which enforces the
+/// version/signature rules described above.
+///
+/// `authorized_signers` stands in for a contract-defined read-only access-control function keyed
+/// on the slot: this prototype crate has no contract-call plumbing yet to invoke one, so callers
+/// populate it directly per slot via `set_authorized_signers`.
+pub struct ReplicatedDataMap {
+    key_type: TypeSignature,
+    value_type: TypeSignature,
+    slots: HashMap<String, ReplicatedChunk>,
+    authorized_signers: HashMap<String, Vec<Vec<u8>>>,
+}
+
+impl ReplicatedDataMap {
+    pub fn new(key_type: TupleTypeSignature,
+               value_type: TupleTypeSignature) -> ReplicatedDataMap {
+        ReplicatedDataMap {
+            slots: HashMap::new(),
+            authorized_signers: HashMap::new(),
+            key_type: TypeSignature::new(
+                AtomTypeIdentifier::TupleType(key_type), 0),
+            value_type: TypeSignature::new(
+                AtomTypeIdentifier::TupleType(value_type), 0)
+        }
+    }
+
+    // TODO: this crate has no canonical value-encoding yet; Debug-formatting the key is a
+    //   placeholder stand-in for a real slot-id derivation.
+    fn slot_id(key: &ValueType) -> String {
+        format!("{:?}", key)
+    }
+
+    /// Declare which signer public keys may write `key`'s slot, per the contract's own
+    /// access-control rule. Until contract-call plumbing exists (see the struct doc comment),
+    /// this is set directly rather than derived by invoking the contract.
+    pub fn set_authorized_signers(&mut self, key: &ValueType, signers: Vec<Vec<u8>>) {
+        self.authorized_signers.insert(Self::slot_id(key), signers);
+    }
+
+    /// Accept a versioned, signed write arriving from the replication/gossip layer.
+    ///
+    /// Returns `Ok(true)` if the chunk was newer (or tied with a lexicographically greater
+    /// signature) and so replaced the held chunk, `Ok(false)` if it was stale and so ignored, or
+    /// `Err` if the value's type doesn't match the map's declared value type, or the signature
+    /// doesn't verify against any authorized signer for this slot.
+    pub fn receive_chunk(&mut self, key: &ValueType, version: u64, value: ValueType, signature: Vec<u8>) -> Result<bool, Error> {
+        let value_type = TypeSignature::type_of(&value);
+        if self.value_type != value_type {
+            return Err(Error::TypeError(format!("{:?}", self.value_type), value))
+        }
+
+        let slot_id = Self::slot_id(key);
+        let no_signers: Vec<Vec<u8>> = Vec::new();
+        let authorized = self.authorized_signers.get(&slot_id).unwrap_or(&no_signers);
+        if !verify_chunk_signature(&slot_id, version, &value, &signature, authorized) {
+            return Err(Error::InvalidArguments(
+                "chunk signature did not verify against any authorized signer for this slot".to_string()))
+        }
+
+        let replace = match self.slots.get(&slot_id) {
+            None => true,
+            Some(held) if version > held.version => true,
+            Some(held) if version == held.version => signature > held.signature,
+            Some(_held) => false,
+        };
+
+        if replace {
+            self.slots.insert(slot_id, ReplicatedChunk { version: version, value: value, signature: signature });
+        }
+
+        Ok(replace)
+    }
+}
+
+impl DataMap for ReplicatedDataMap {
+    // TODO: currently, the return types and behavior of these functions are defined here,
+    //   however, they should really be specified in the functions/database.rs file, whereas
+    //   this file should really just be speccing out the database connection/requirement.
+
+    fn fetch_entry(&self, key: &ValueType) -> InterpreterResult {
+        let key_type = TypeSignature::type_of(key);
+        if self.key_type != key_type {
+            return Err(Error::TypeError(format!("{:?}", self.key_type), (*key).clone()))
+        }
+        match self.slots.get(&Self::slot_id(key)) {
+            Some(chunk) => Ok(chunk.value.clone()),
+            None => Ok(ValueType::VoidType)
+        }
+    }
+
+    fn set_entry(&mut self, key: ValueType, value: ValueType) -> Result<(), Error> {
+        let key_type = TypeSignature::type_of(&key);
+        if self.key_type != key_type {
+            return Err(Error::TypeError(format!("{:?}", self.key_type), key))
+        }
+        let value_type = TypeSignature::type_of(&value);
+        if self.value_type != value_type {
+            return Err(Error::TypeError(format!("{:?}", self.value_type), value))
+        }
+        let slot_id = Self::slot_id(&key);
+        let next_version = self.slots.get(&slot_id).map(|chunk| chunk.version + 1).unwrap_or(0);
+        self.slots.insert(slot_id, ReplicatedChunk { version: next_version, value: value, signature: vec![] });
+        Ok(())
+    }
+
+    fn insert_entry(&mut self, key: ValueType, value: ValueType) -> InterpreterResult {
+        let key_type = TypeSignature::type_of(&key);
+        if self.key_type != key_type {
+            return Err(Error::TypeError(format!("{:?}", self.key_type), key))
+        }
+        let value_type = TypeSignature::type_of(&value);
+        if self.value_type != value_type {
+            return Err(Error::TypeError(format!("{:?}", self.value_type), value))
+        }
+        let slot_id = Self::slot_id(&key);
+        if self.slots.contains_key(&slot_id) {
+            Ok(ValueType::BoolType(false))
+        } else {
+            self.slots.insert(slot_id, ReplicatedChunk { version: 0, value: value, signature: vec![] });
+            Ok(ValueType::BoolType(true))
+        }
+    }
+
+    fn delete_entry(&mut self, key: &ValueType) -> InterpreterResult {
+        let key_type = TypeSignature::type_of(key);
+        if self.key_type != key_type {
+            return Err(Error::TypeError(format!("{:?}", self.key_type), (*key).clone()))
+        }
+        if let Some(_chunk) = self.slots.remove(&Self::slot_id(key)) {
+            Ok(ValueType::BoolType(true))
+        } else {
+            Ok(ValueType::BoolType(false))
+        }
+    }
+}
+
+/// Where a contract-declared data map's entries actually live, recorded by
+/// `ReplicatedContractDatabase::create_map`/`create_replicated_map`.
+enum MapBacking {
+    Local(MemoryDataMap),
+    Replicated(ReplicatedDataMap),
+}
+
+/// A second `ContractDatabase` implementation alongside `MemoryContractDatabase`: each map is
+/// either consensus-local (a plain in-memory `HashMap`, as before) or replicated off-chain (see
+/// `ReplicatedDataMap`), and `get_data_map`/`fetch_entry`/`set_entry` route to whichever backing
+/// the map was declared with transparently, without the contract-facing `DataMap` interface
+/// changing shape.
+pub struct ReplicatedContractDatabase {
+    maps: HashMap<String, MapBacking>,
+}
+
+impl ReplicatedContractDatabase {
+    pub fn new() -> ReplicatedContractDatabase {
+        ReplicatedContractDatabase { maps: HashMap::new() }
+    }
+
+    /// Declare `map_name` as replicated rather than consensus-local. Like `create_map`, this
+    /// just records the map's shape; `ReplicatedDataMap::set_authorized_signers` and
+    /// `receive_chunk` are used afterward to configure access control and ingest gossiped writes.
+    pub fn create_replicated_map(&mut self, map_name: &str, key_type: TupleTypeSignature, value_type: TupleTypeSignature) {
+        self.maps.insert(map_name.to_string(), MapBacking::Replicated(ReplicatedDataMap::new(key_type, value_type)));
+    }
+}
+
+impl ContractDatabase for ReplicatedContractDatabase {
+    fn get_data_map(&mut self, map_name: &str) -> Result<Option<&mut DataMap>, Error> {
+        // `ReplicatedDataMap` is still an in-process HashMap under the hood (see its own doc
+        // comment), so there's no real transport failure to surface yet either -- but unlike
+        // `MemoryContractDatabase`, a future version of this backend that actually gossips chunks
+        // over the network has somewhere to propagate an `Error::DatabaseUnavailable` through
+        // without another trait-signature change.
+        match self.maps.get_mut(map_name) {
+            Some(MapBacking::Local(data_map)) => Ok(Some(data_map)),
+            Some(MapBacking::Replicated(data_map)) => Ok(Some(data_map)),
+            None => Ok(None)
+        }
+    }
+
+    fn create_map(&mut self, map_name: &str, key_type: TupleTypeSignature, value_type: TupleTypeSignature) {
+        let new_map = MemoryDataMap::new(key_type, value_type);
+        self.maps.insert(map_name.to_string(), MapBacking::Local(new_map));
+    }
+}
+
 impl DataMap for MemoryDataMap {
     // TODO: currently, the return types and behavior of these functions are defined here,
     //   however, they should really be specified in the functions/database.rs file, whereas
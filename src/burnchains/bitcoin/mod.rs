@@ -28,6 +28,8 @@ pub mod keys;
 pub mod indexer;
 pub mod network;
 pub mod spv;
+#[cfg(feature = "burnchain-script-verify")]
+pub mod script_verify;
 
 use std::fmt;
 use std::io;
@@ -150,16 +152,27 @@ pub enum BitcoinNetworkType {
     Regtest
 }
 
+// `BitcoinAddress::is_burn()` and the `address`/`bits` parsers that build a `BitcoinTxOutput` from
+// a transaction output script are responsible for recognizing bech32 (`OP_0 <20|32-byte>`)
+// outputs alongside legacy base58 P2PKH/P2SH ones, and for recovering `BitcoinTxInput::keys` from
+// the witness stack for the `SegwitP2WPKH`/`SegwitP2WSH` variants below -- both live in
+// `burnchains::bitcoin::{address, bits}`, which this tree doesn't carry source for.
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct BitcoinTxOutput {
     pub address: BitcoinAddress,
     pub units: u64
 }
 
+/// How a `BitcoinTxInput`'s `keys` were recovered from the spending transaction. `Standard` and
+/// `SegwitP2SH` read them out of the scriptSig (a bare signature script, or a redeem script
+/// wrapped in a P2SH scriptSig, respectively); `SegwitP2WPKH`/`SegwitP2WSH` read them out of the
+/// witness stack instead, since a native segwit input carries no scriptSig at all.
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum BitcoinInputType {
     Standard,
-    SegwitP2SH
+    SegwitP2SH,
+    SegwitP2WPKH,
+    SegwitP2WSH,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
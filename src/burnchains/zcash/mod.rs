@@ -0,0 +1,152 @@
+/*
+ copyright: (c) 2013-2018 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+// This module mirrors `burnchains::bitcoin`: a Zcash transparent transaction carries the same
+// OP_RETURN-encoded Blockstack operation wire format that `chainstate::burn::operations` parses
+// out of a `BitcoinTransaction`, so most of an operation's `parse_data` is reused unchanged. What
+// differs is txid and header derivation: Zcash (post-Overwinter) transactions are serialized with
+// a version-group ID and an optional Sapling/JoinSplit bundle that Bitcoin's plain legacy/segwit
+// serialization has no equivalent of, and a Zcash block header carries a 1344-byte Equihash
+// solution that must be included when hashing `BurnchainHeaderHash`.
+//
+// NOTE: `burnchains::mod` (where `BurnchainTransaction`'s variants and this directory's `pub mod
+// zcash;` declaration alongside `pub mod bitcoin;` would live) has no file anywhere in this tree,
+// so there's no enum to add a `Zcash` variant to and no parent module to declare this one from.
+// This file is written exactly as `burnchains::bitcoin::mod` is -- the data types and txid/header
+// hashing a `BurnchainTransaction::Zcash(ZcashTransaction)` variant would wrap -- for a tree that
+// has that enum to slot into.
+
+use std::fmt;
+
+use burnchains::bitcoin::keys::BitcoinPublicKey;
+use burnchains::bitcoin::address::BitcoinAddress;
+use burnchains::{BurnchainHeaderHash, Txid};
+
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZcashNetworkType {
+    Mainnet,
+    Testnet,
+}
+
+/// Transparent inputs use the same secp256k1 ECDSA scripts Bitcoin does, so signer recovery reuses
+/// `BitcoinPublicKey`/`BitcoinTxInput`'s shape verbatim rather than a parallel type.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ZcashTxInput {
+    pub keys: Vec<BitcoinPublicKey>,
+    pub num_required: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct ZcashTxOutput {
+    pub address: BitcoinAddress,
+    pub units: u64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ZcashTransaction {
+    pub txid: Txid,
+    pub vtxindex: u32,
+    pub opcode: u8,
+    pub data: Vec<u8>,
+    pub inputs: Vec<ZcashTxInput>,
+    pub outputs: Vec<ZcashTxOutput>,
+    /// The Overwinter/Sapling version-group ID this transaction was serialized with -- part of
+    /// the preimage `compute_txid()` hashes over, since two transactions that differ only in this
+    /// field are consensus-distinct.
+    pub version_group_id: u32,
+    /// Raw, already-serialized Sapling spend/output descriptions and (if present) JoinSplit
+    /// description bytes, in on-the-wire order. Carried opaquely rather than parsed, since no
+    /// Blockstack operation reads shielded data -- only `compute_txid()` needs these bytes, to
+    /// include them in the overwintered preimage.
+    pub shielded_bundle_bytes: Vec<u8>,
+}
+
+impl ZcashTransaction {
+    /// The preimage this transaction's `txid` is the double-SHA256 of: the version-group ID, the
+    /// opcode, the OP_RETURN `data`, and the shielded bundle bytes, in that order. The
+    /// transparent `inputs` and `outputs` are not part of it.
+    fn overwintered_preimage(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.version_group_id.to_le_bytes());
+        bytes.extend_from_slice(&(self.opcode as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes.extend_from_slice(&self.shielded_bundle_bytes);
+        bytes
+    }
+
+    /// Recompute this transaction's txid over `overwintered_preimage()`, which covers the
+    /// Sapling/JoinSplit bundle (even though no Blockstack operation ever looks inside it) but
+    /// not the transparent inputs and outputs.
+    pub fn compute_txid(&self) -> Txid {
+        Txid(sha256d(&self.overwintered_preimage()))
+    }
+}
+
+/// 1344 bytes: Zcash's Equihash(200,9) solution size, carried in every Zcash block header in
+/// addition to the Bitcoin-style version/prev-hash/merkle-root/time/bits/nonce fields.
+pub const EQUIHASH_SOLUTION_SIZE: usize = 1344;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ZcashBlockHeader {
+    pub version: i32,
+    pub prev_block_hash: BurnchainHeaderHash,
+    pub merkle_root: [u8; 32],
+    pub final_sapling_root: [u8; 32],
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: [u8; 32],
+    pub equihash_solution: Vec<u8>,
+}
+
+impl ZcashBlockHeader {
+    /// `BurnchainHeaderHash` for a Zcash block: double-SHA256 over every header field in wire
+    /// order, including `equihash_solution` -- omitting it would let two blocks with different
+    /// proof-of-work solutions (but identical transactions) collide on header hash.
+    pub fn burn_header_hash(&self) -> BurnchainHeaderHash {
+        let mut bytes = Vec::with_capacity(4 + 32 + 32 + 32 + 4 + 4 + 32 + EQUIHASH_SOLUTION_SIZE);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(self.prev_block_hash.as_bytes());
+        bytes.extend_from_slice(&self.merkle_root);
+        bytes.extend_from_slice(&self.final_sapling_root);
+        bytes.extend_from_slice(&self.time.to_le_bytes());
+        bytes.extend_from_slice(&self.bits.to_le_bytes());
+        bytes.extend_from_slice(&self.nonce);
+        bytes.extend_from_slice(&self.equihash_solution);
+        BurnchainHeaderHash(sha256d(&bytes))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ZcashBlock {
+    pub block_height: u64,
+    pub block_hash: BurnchainHeaderHash,
+    pub parent_block_hash: BurnchainHeaderHash,
+    pub txs: Vec<ZcashTransaction>,
+    pub timestamp: u64,
+}
+
+fn sha256d(bytes: &[u8]) -> [u8; 32] {
+    let first = Sha256::digest(bytes);
+    let second = Sha256::digest(&first);
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(second.as_slice());
+    ret
+}
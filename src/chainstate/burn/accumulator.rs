@@ -0,0 +1,175 @@
+/// A utreexo-style accumulator over spendable burnchain outputs (including block-commit UTXOs),
+/// so a pruned node can track which outputs are still spendable without keeping a full UTXO
+/// database. Spendable outputs are hashed into leaves of a binary Merkle forest; only the
+/// forest's O(log n) roots are retained, and membership is proven with the list of sibling
+/// hashes from a leaf up to its root -- the same shape as `merkle_path`/`verify_ops_inclusion` in
+/// `chainstate::burn::mod`, applied here to UTXOs rather than a block's txids.
+///
+/// The single most important property of this module is that `is_spendable_output` is the *only*
+/// place either side of the accumulator decides what counts as addable/provable: `add_output`
+/// consults it before turning an output into a leaf, and a verifier is expected to run the same
+/// predicate over the output a proof claims to cover before trusting `verify_inclusion`'s answer.
+/// A node that classified outputs differently on the add side than the verify side would silently
+/// diverge from every other node's accumulator state.
+use burnchains::Txid;
+
+const ACCUMULATOR_LEAF_TAG: &'static [u8] = b"accumulator-leaf";
+const ACCUMULATOR_NODE_TAG: &'static [u8] = b"accumulator-node";
+
+/// The largest scriptPubKey this module will ever treat as spendable. Bitcoin Core's own mempool
+/// policy (`IsStandard`) rejects non-segwit scripts above this size, and libbitcoinconsensus will
+/// never mark such an output spendable either, so there's no point accumulating it.
+const MAX_SPENDABLE_SCRIPT_LEN: usize = 10_000;
+
+fn tagged_sha256(tag: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.input(tag);
+    for part in parts {
+        hasher.input(part);
+    }
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(hasher.result().as_slice());
+    ret
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    tagged_sha256(ACCUMULATOR_NODE_TAG, &[left, right])
+}
+
+/// Decides whether an output with the given scriptPubKey and value is spendable, and therefore
+/// eligible to become an accumulator leaf. This must be consulted identically on both the add and
+/// the proof-verify side (see this module's top doc comment) -- never reimplement this check
+/// elsewhere.
+///
+/// An output is excluded if it is:
+/// - `OP_RETURN` (opcode `0x6a` as its first byte): provably unspendable by Bitcoin consensus rules.
+/// - Oversized: larger than [`MAX_SPENDABLE_SCRIPT_LEN`], which no standard spend can satisfy.
+/// - Zero-valued: a zero-amount output carries no spendable value to track.
+pub fn is_spendable_output(script_pubkey: &[u8], amount_sats: u64) -> bool {
+    if amount_sats == 0 {
+        return false;
+    }
+    if script_pubkey.len() > MAX_SPENDABLE_SCRIPT_LEN {
+        return false;
+    }
+    if script_pubkey.first() == Some(&0x6a) {
+        // OP_RETURN
+        return false;
+    }
+    true
+}
+
+/// Hashes one output into its accumulator leaf: `SHA256(leaf-tag || txid || vout || scriptPubKey
+/// || amount)`. Callers add/prove membership against this value, never the output's raw fields.
+pub fn leaf_hash(txid: &Txid, vout: u32, script_pubkey: &[u8], amount_sats: u64) -> [u8; 32] {
+    tagged_sha256(
+        ACCUMULATOR_LEAF_TAG,
+        &[
+            txid.as_bytes(),
+            &vout.to_le_bytes(),
+            script_pubkey,
+            &amount_sats.to_le_bytes(),
+        ],
+    )
+}
+
+/// A sibling hash and which side of it the accumulated value sits on, read bottom-up -- the same
+/// shape `chainstate::burn::mod::merkle_path` returns for ops-hash inclusion proofs. `true` means
+/// the running hash is the *right* child (so `node_hash(sibling, running)`); `false` means it's
+/// the left child (`node_hash(running, sibling)`).
+pub type AccumulatorProof = Vec<([u8; 32], bool)>;
+
+/// A forest of perfect binary Merkle trees over the accumulator's current leaves, indexed by row:
+/// `roots[i]` is `Some(root)` of a 2^i-leaf tree if the forest currently has one, or `None` if
+/// that row is empty. This is the standard utreexo "binary counter" layout: adding a leaf behaves
+/// like incrementing a binary number, carrying a merge into the next row whenever two same-size
+/// trees collide.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Accumulator {
+    pub roots: Vec<Option<[u8; 32]>>,
+}
+
+impl Accumulator {
+    pub fn new() -> Accumulator {
+        Accumulator { roots: Vec::new() }
+    }
+
+    /// Adds a single leaf hash to the forest, merging same-size roots exactly like a binary
+    /// counter increment.
+    fn add_leaf(&mut self, mut carry: [u8; 32]) {
+        let mut row = 0;
+        loop {
+            if row == self.roots.len() {
+                self.roots.push(Some(carry));
+                return;
+            }
+            match self.roots[row].take() {
+                None => {
+                    self.roots[row] = Some(carry);
+                    return;
+                }
+                Some(existing) => {
+                    carry = node_hash(&existing, &carry);
+                    row += 1;
+                }
+            }
+        }
+    }
+
+    /// Adds `(txid, vout, script_pubkey, amount_sats)` to the accumulator if and only if
+    /// [`is_spendable_output`] accepts it -- an unspendable output is simply never turned into a
+    /// leaf, so it can never later be proven a member.
+    pub fn add_output(&mut self, txid: &Txid, vout: u32, script_pubkey: &[u8], amount_sats: u64) {
+        if !is_spendable_output(script_pubkey, amount_sats) {
+            return;
+        }
+        let leaf = leaf_hash(txid, vout, script_pubkey, amount_sats);
+        self.add_leaf(leaf);
+    }
+
+    /// Checks whether `leaf` is a member of this forest under `proof`: folds `leaf` up through
+    /// `proof`'s sibling hashes and compares the result to the root at row `proof.len()`. Returns
+    /// `false` if that row has no root, or if the recomputed hash doesn't match it.
+    pub fn verify_inclusion(&self, leaf: &[u8; 32], proof: &AccumulatorProof) -> bool {
+        let row = proof.len();
+        let root = match self.roots.get(row).and_then(|r| r.as_ref()) {
+            Some(root) => root,
+            None => return false,
+        };
+
+        let mut cur = *leaf;
+        for (sibling, cur_is_right) in proof.iter() {
+            cur = if *cur_is_right {
+                node_hash(sibling, &cur)
+            } else {
+                node_hash(&cur, sibling)
+            };
+        }
+
+        &cur == root
+    }
+
+    /// Removes `leaf` from the forest if `proof` authenticates it (see `verify_inclusion`),
+    /// returning whether the removal took place.
+    ///
+    /// NOTE: this clears the *entire* `2^proof.len()`-leaf tree the leaf belonged to, rather than
+    /// performing full utreexo rebalancing (promoting the deleted leaf's sibling subtree into a
+    /// smaller, non-power-of-two gap). That rebalancing needs more than a single leaf's proof --
+    /// a pruned node genuinely cannot recompute it without a bridge node supplying the sibling
+    /// subtree's own structure, which is out of scope here. The net effect is a correct but overly
+    /// conservative delete: the other leaves that shared that tree are also dropped from the
+    /// forest and would need to be re-added from a fresh scan if still live.
+    pub fn delete(&mut self, leaf: &[u8; 32], proof: &AccumulatorProof) -> bool {
+        if !self.verify_inclusion(leaf, proof) {
+            return false;
+        }
+        self.roots[proof.len()] = None;
+        true
+    }
+
+    /// Whether this accumulator currently tracks no leaves at all.
+    pub fn is_empty(&self) -> bool {
+        self.roots.iter().all(|r| r.is_none())
+    }
+}
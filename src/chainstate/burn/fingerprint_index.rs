@@ -0,0 +1,88 @@
+/// An optional, non-consensus fingerprint index over `ConsensusHash` lookups, gated behind the
+/// `blake3-index` feature. `get_prev_consensus_hashes` (see `chainstate::burn::mod`) walks
+/// backward from a tip recomputing or re-fetching a geometrically-spaced set of prior
+/// `ConsensusHash` values; a node doing this repeatedly for overlapping ranges during
+/// ancestor-selection benefits from caching "these serialized consensus-hash-determining fields
+/// map to this already-computed `ConsensusHash`" rather than re-deriving or re-querying it.
+///
+/// BLAKE3 is used only as the index's internal cache key -- never as the on-chain
+/// `ConsensusHash` itself, which remains the existing Hash160-derived 20-byte value defined in
+/// `chainstate::burn::mod`. Swapping this module out for a different cache implementation (or
+/// deleting it outright) changes nothing about what any `ConsensusHash` is worth on-chain.
+///
+/// The index is keyed in BLAKE3's *keyed* mode under a fixed domain-separation key, so a
+/// fingerprint computed here can never collide with a BLAKE3 hash computed for an unrelated
+/// purpose elsewhere in the codebase, even over identical input bytes. BLAKE3 itself is a
+/// Merkle tree over 1 KiB input chunks and parallelizes across them internally -- that's a
+/// property of the algorithm, not something this module implements -- which is what makes it
+/// attractive here over a serial hash for a cache that may be fed large serialized inputs.
+///
+/// NOTE: this tree has no Cargo.toml anywhere, so there's nowhere to actually declare the
+/// `blake3-index` feature or the `blake3` dependency it would gate. This module is written
+/// exactly as it would be wired up in a tree that had a manifest.
+use std::collections::HashMap;
+
+use chainstate::burn::ConsensusHash;
+
+const FINGERPRINT_DOMAIN_LABEL: &'static [u8] = b"stacks-blockchain/consensus-hash-fingerprint-index-v1";
+
+fn fingerprint_key() -> [u8; 32] {
+    *blake3::hash(FINGERPRINT_DOMAIN_LABEL).as_bytes()
+}
+
+/// A 256-bit BLAKE3 fingerprint over a `ConsensusHash`'s serialized determining fields. Used only
+/// as a `FingerprintIndex` cache key -- see this module's top doc comment.
+pub struct Fingerprint(pub [u8; 32]);
+impl_array_newtype!(Fingerprint, u8, 32);
+impl_array_hexstring_fmt!(Fingerprint);
+impl_byte_array_newtype!(Fingerprint, u8, 32);
+
+/// Computes the keyed BLAKE3 fingerprint of `serialized_fields` (the concatenated bytes that
+/// determine a `ConsensusHash` -- fork-set identifier, network magic, ops hash, burn total, and
+/// prior consensus hashes, in the same order `ConsensusHash::from_ops` feeds them to its own
+/// hasher).
+pub fn fingerprint(serialized_fields: &[u8]) -> Fingerprint {
+    let key = fingerprint_key();
+    let mut hasher = blake3::Hasher::new_keyed(&key);
+    hasher.update(serialized_fields);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hasher.finalize().as_bytes());
+    Fingerprint(out)
+}
+
+/// An in-memory, process-local cache from a `Fingerprint` of a `ConsensusHash`'s determining
+/// fields to the `ConsensusHash` itself. Purely a dedupe/fast-path layer over whatever already
+/// computes or fetches a `ConsensusHash` -- it has no consensus meaning and nothing downstream
+/// should distinguish a cache hit from a freshly computed value.
+#[derive(Default)]
+pub struct FingerprintIndex {
+    index: HashMap<Fingerprint, ConsensusHash>,
+}
+
+impl FingerprintIndex {
+    pub fn new() -> FingerprintIndex {
+        FingerprintIndex { index: HashMap::new() }
+    }
+
+    /// Returns the cached `ConsensusHash` for `serialized_fields` if present; otherwise calls
+    /// `compute` to obtain it, caches the result, and returns it. Ancestor-selection call sites
+    /// that already have the serialized fields on hand (rather than forcing this cache into
+    /// `get_prev_consensus_hashes`'s own signature, which stays unchanged so non-`blake3-index`
+    /// builds are unaffected) can wrap their existing lookup/recompute step in this.
+    pub fn get_or_insert_with<F>(&mut self, serialized_fields: &[u8], compute: F) -> ConsensusHash
+    where
+        F: FnOnce() -> ConsensusHash,
+    {
+        let key = fingerprint(serialized_fields);
+        if let Some(existing) = self.index.get(&key) {
+            return existing.clone();
+        }
+        let computed = compute();
+        self.index.insert(key, computed.clone());
+        computed
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+}
@@ -19,8 +19,13 @@
 
 /// This module contains the code for processing the burn chain state database
 
+pub mod accumulator;
 pub mod db;
 pub mod distribution;
+pub mod fast_sync;
+#[cfg(feature = "blake3-index")]
+pub mod fingerprint_index;
+pub mod filter;
 pub mod operations;
 pub mod sortition;
 
@@ -48,6 +53,7 @@ use chainstate::burn::db::burndb::BurnDBTx;
 use util::db::Error as db_error;
 
 use core::SYSTEM_FORK_SET_VERSION;
+use core::StacksEpochId;
 
 use util::log;
 use util::uint::Uint256;
@@ -61,6 +67,96 @@ impl_array_hexstring_fmt!(ConsensusHash);
 impl_byte_array_newtype!(ConsensusHash, u8, 20);
 pub const CONSENSUS_HASH_ENCODED_SIZE : u32 = 20;
 
+/// Per-network identity mixed into every consensus hash, so mainnet, testnet, and regtest
+/// produce structurally identical but cryptographically distinct chains -- a burn operation or
+/// snapshot mined on one network can never be replayed or confused as belonging to another, and
+/// each network can bump its own fork-set identifier independently of the others.
+///
+/// This replaces what used to be a single hard-coded `SYSTEM_FORK_SET_VERSION` fed into every
+/// `ConsensusHash::from_ops` call: `mainnet()` keeps that exact byte string as its fork-set
+/// identifier, while `testnet()`/`regtest()` get their own identifiers. Every network, mainnet
+/// included, also mixes its `network_magic` into each consensus hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsensusParams {
+    /// Fed into every consensus hash on this network, in place of the old global
+    /// `SYSTEM_FORK_SET_VERSION` constant. Bump this to fork a single network's consensus hash
+    /// chain cleanly, without affecting any other network.
+    pub fork_set_identifier: &'static [u8],
+    /// The block height at which this network's burn chain history begins.
+    pub first_block_height: u64,
+    /// This network's wire/handshake magic bytes, mixed into the consensus hash alongside the
+    /// fork-set identifier so the two networks can never be confused even if a future fork-set
+    /// bump ever collided.
+    pub network_magic: &'static [u8],
+}
+
+impl ConsensusParams {
+    pub fn mainnet() -> ConsensusParams {
+        ConsensusParams {
+            fork_set_identifier: SYSTEM_FORK_SET_VERSION,
+            first_block_height: 0,
+            network_magic: b"X2",
+        }
+    }
+
+    pub fn testnet() -> ConsensusParams {
+        ConsensusParams {
+            fork_set_identifier: b"testnet-fork-set-1",
+            first_block_height: 0,
+            network_magic: b"T2",
+        }
+    }
+
+    pub fn regtest() -> ConsensusParams {
+        ConsensusParams {
+            fork_set_identifier: b"regtest-fork-set-1",
+            first_block_height: 0,
+            network_magic: b"R2",
+        }
+    }
+}
+
+/// A single typed handle on "the current canonical burn chain tip", bundling the three fields
+/// that must always travel together -- a height, the burn header hash at that height, and the
+/// consensus hash that height resolved to. Callers that juggle these as separate hash/height
+/// parameters risk pairing a height with the wrong fork's hash; a `BestBurnBlock` can only ever
+/// describe one block, so there's nothing to accidentally mismatch. Modeled on the same idea as
+/// rust-lightning's `BestBlock`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestBurnBlock {
+    burn_header_hash: BurnchainHeaderHash,
+    consensus_hash: ConsensusHash,
+    block_height: u64,
+}
+
+impl BestBurnBlock {
+    /// Seeds a tip at `params`'s genesis: height `params.first_block_height`, the all-zeros burn
+    /// header hash and consensus hash every network starts its chain from.
+    pub fn from_genesis(params: &ConsensusParams) -> BestBurnBlock {
+        BestBurnBlock {
+            burn_header_hash: BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap(),
+            consensus_hash: ConsensusHash::empty(),
+            block_height: params.first_block_height,
+        }
+    }
+
+    pub fn new(burn_header_hash: BurnchainHeaderHash, consensus_hash: ConsensusHash, block_height: u64) -> BestBurnBlock {
+        BestBurnBlock { burn_header_hash, consensus_hash, block_height }
+    }
+
+    pub fn burn_header_hash(&self) -> &BurnchainHeaderHash {
+        &self.burn_header_hash
+    }
+
+    pub fn consensus_hash(&self) -> &ConsensusHash {
+        &self.consensus_hash
+    }
+
+    pub fn block_height(&self) -> u64 {
+        self.block_height
+    }
+}
+
 pub struct BlockHeaderHash(pub [u8; 32]);
 impl_array_newtype!(BlockHeaderHash, u8, 32);
 impl_array_hexstring_fmt!(BlockHeaderHash);
@@ -118,6 +214,8 @@ pub struct BlockSnapshot {
     pub parent_burn_header_hash: BurnchainHeaderHash,
     pub consensus_hash: ConsensusHash,
     pub ops_hash: OpsHash,
+    pub ops_merkle_root: OpsMerkleRoot,  // Merkle root over this block's ordered txids, committed in consensus next to ops_hash so light clients can get a logarithmic inclusion proof for a single operation
+    pub ops_filter_hash: filter::FilterHash,  // commits to a compact Golomb-coded filter over this block's operations, so a wallet can decide whether to fetch the block at all without downloading every operation in it
     pub total_burn: u64,        // how many burn tokens have been destroyed since genesis
     pub sortition: bool,        // whether or not a sortition happened in this block (will be false if there were no burns)
     pub sortition_hash: SortitionHash,  // rolling hash of the burn chain's block headers -- this gets mixed with the sortition VRF seed
@@ -140,31 +238,75 @@ impl BlockHeaderHash {
     }
 }
 
+// Domain separation tags for the hash constructors below. Each is prepended as the *first*
+// input to its hasher so that a txid sequence, a consensus-hash chain, and a sortition mix can
+// never alias one another just because they happen to serialize to the same bytes -- the classic
+// cross-protocol hash confusion that domain-separated hashing (distinct tag per hash use) exists
+// to rule out.
+const OPS_HASH_TAG: &'static [u8] = b"ops-hash";
+const CONSENSUS_HASH_TAG: &'static [u8] = b"consensus-hash";
+const SORTITION_HEADER_TAG: &'static [u8] = b"sortition-header";
+const SORTITION_VRF_TAG: &'static [u8] = b"sortition-vrf";
+
+/// Compute `SHA256(tag || parts[0] || parts[1] || ...)`. Every hash constructor in this module
+/// routes through this instead of feeding a bare `Sha256` engine directly, so the domain tag is
+/// never accidentally left off one call site while present on the others.
+fn tagged_sha256(tag: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    use sha2::Digest;
+    let mut hasher = Sha256::new();
+    hasher.input(tag);
+    for part in parts {
+        hasher.input(part);
+    }
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(hasher.result().as_slice());
+    ret
+}
+
+/// Compute `SHA256(parts[0] || parts[1] || ...)`, with no domain tag at all -- exactly what every
+/// constructor below computed prior to domain separation. Epoch 1.0 burn chain history was
+/// committed with this bare hash, so it has to keep reproducing forever; only Epoch 2.0 and later
+/// switch to `tagged_sha256`. This is the same `StacksEpochId`-gated split `Txid::from_stacks_tx`
+/// uses to keep its own tagged-hash change from silently forking pre-activation history.
+fn untagged_sha256(parts: &[&[u8]]) -> [u8; 32] {
+    use sha2::Digest;
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.input(part);
+    }
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(hasher.result().as_slice());
+    ret
+}
+
+/// `SHA256(tag || parts...)` from Epoch 2.0 onward, `SHA256(parts...)` (no tag) in Epoch 1.0 --
+/// so the new domain-separated wire format only takes effect from its activation epoch forward,
+/// and every hash computed against pre-activation burn chain history still reproduces exactly.
+fn epoch_gated_sha256(epoch_id: StacksEpochId, tag: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    match epoch_id {
+        StacksEpochId::Epoch10 => untagged_sha256(parts),
+        StacksEpochId::Epoch20 => tagged_sha256(tag, parts),
+    }
+}
+
 impl SortitionHash {
     /// Calculate a new sortition hash from the given burn header hash
     pub fn initial() -> SortitionHash {
         SortitionHash([0u8; 32])
     }
 
-    /// Mix in a burn blockchain header to make a new sortition hash
-    pub fn mix_burn_header(&self, burn_header_hash: &BurnchainHeaderHash) -> SortitionHash {
-        use sha2::Digest;
-        let mut sha2 = Sha256::new();
-        sha2.input(self.as_bytes());
-        sha2.input(burn_header_hash.as_bytes());
-        let mut ret = [0u8; 32];
-        ret.copy_from_slice(sha2.result().as_slice());
+    /// Mix in a burn blockchain header to make a new sortition hash. `epoch_id` is the epoch of
+    /// the burn block `burn_header_hash` belongs to, so the tagged hash format only kicks in from
+    /// Epoch 2.0 onward (see `epoch_gated_sha256`).
+    pub fn mix_burn_header(&self, epoch_id: StacksEpochId, burn_header_hash: &BurnchainHeaderHash) -> SortitionHash {
+        let ret = epoch_gated_sha256(epoch_id, SORTITION_HEADER_TAG, &[self.as_bytes(), burn_header_hash.as_bytes()]);
         SortitionHash(ret)
     }
 
-    /// Mix in a new VRF seed to make a new sortition hash.
-    pub fn mix_VRF_seed(&self, VRF_seed: &VRFSeed) -> SortitionHash {
-        use sha2::Digest;
-        let mut sha2 = Sha256::new();
-        sha2.input(self.as_bytes());
-        sha2.input(VRF_seed.as_bytes());
-        let mut ret = [0u8; 32];
-        ret.copy_from_slice(&sha2.result()[..]);
+    /// Mix in a new VRF seed to make a new sortition hash. `epoch_id` is the epoch of the burn
+    /// block this mix is happening in.
+    pub fn mix_VRF_seed(&self, epoch_id: StacksEpochId, VRF_seed: &VRFSeed) -> SortitionHash {
+        let ret = epoch_gated_sha256(epoch_id, SORTITION_VRF_TAG, &[self.as_bytes(), VRF_seed.as_bytes()]);
         SortitionHash(ret)
     }
 
@@ -188,22 +330,123 @@ impl SortitionHash {
 }
 
 impl OpsHash {
-    pub fn from_txids(txids: &Vec<Txid>) -> OpsHash {
+    /// `epoch_id` is the epoch of the burn block these txids were mined in; only Epoch 2.0 and
+    /// later tag the hash (see `epoch_gated_sha256`).
+    pub fn from_txids(epoch_id: StacksEpochId, txids: &Vec<Txid>) -> OpsHash {
         // NOTE: unlike stacks v1, we calculate the ops hash simply
         // from a hash-chain of txids.  There is no weird serialization
         // of operations, and we don't construct a merkle tree over
         // operations anymore (it's needlessly complex).
-        use sha2::Digest;
-        let mut hasher = Sha256::new();
-        for txid in txids {
-            hasher.input(txid.as_bytes());
-        }
-        let mut result_32 = [0u8; 32];
-        result_32.copy_from_slice(hasher.result().as_slice());
+        let txid_bytes: Vec<&[u8]> = txids.iter().map(|txid| txid.as_bytes() as &[u8]).collect();
+        let result_32 = epoch_gated_sha256(epoch_id, OPS_HASH_TAG, &txid_bytes);
         OpsHash(result_32)
     }
 }
 
+// the root of a binary Merkle tree over a block's ordered txids, committed alongside ops_hash so
+// a light client can prove a single operation was included in a block without downloading every
+// txid in it (see OpsMerkleRoot::from_txids, merkle_path, and verify_ops_inclusion below)
+pub struct OpsMerkleRoot(pub [u8; 32]);
+impl_array_newtype!(OpsMerkleRoot, u8, 32);
+impl_array_hexstring_fmt!(OpsMerkleRoot);
+impl_byte_array_newtype!(OpsMerkleRoot, u8, 32);
+
+/// `SHA256(left || right)` -- the hash of one internal Merkle node from its two children.
+fn merkle_node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    use sha2::Digest;
+    let mut hasher = Sha256::new();
+    hasher.input(left);
+    hasher.input(right);
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(hasher.result().as_slice());
+    ret
+}
+
+/// The bottom level of a txid Merkle tree: each txid taken as-is as a leaf (it's already a hash,
+/// so it isn't re-hashed going in), in the given order.
+fn merkle_leaves(txids: &[Txid]) -> Vec<[u8; 32]> {
+    txids.iter().map(|txid| {
+        let mut leaf = [0u8; 32];
+        leaf.copy_from_slice(txid.as_bytes());
+        leaf
+    }).collect()
+}
+
+/// Reduce one level of a Merkle tree to the level above it, duplicating the last node first if
+/// the level has an odd number of nodes (Bitcoin's convention for an unbalanced tree).
+fn merkle_reduce(mut level: Vec<[u8; 32]>) -> Vec<[u8; 32]> {
+    if level.len() % 2 == 1 {
+        let last = *level.last().expect("merkle_reduce called on an empty level");
+        level.push(last);
+    }
+    level.chunks(2).map(|pair| merkle_node_hash(&pair[0], &pair[1])).collect()
+}
+
+impl OpsMerkleRoot {
+    /// Build a binary Merkle tree over `txids`, in order, and return its root. An empty block has
+    /// an all-zeros root, since there's no txid to anchor a tree to.
+    pub fn from_txids(txids: &[Txid]) -> OpsMerkleRoot {
+        let mut level = merkle_leaves(txids);
+        if level.is_empty() {
+            return OpsMerkleRoot([0u8; 32]);
+        }
+
+        while level.len() > 1 {
+            level = merkle_reduce(level);
+        }
+        OpsMerkleRoot(level[0])
+    }
+}
+
+/// The Merkle path for `txids[index]` against the tree `OpsMerkleRoot::from_txids(txids)` would
+/// build: one `(sibling hash, sibling is on the right)` pair per level, from the leaf up to (but
+/// not including) the root. Pass this, along with the txid and the claimed root, to
+/// `verify_ops_inclusion` to prove inclusion without needing the rest of the block's txids.
+pub fn merkle_path(txids: &[Txid], index: usize) -> Vec<([u8; 32], bool)> {
+    let mut level = merkle_leaves(txids);
+    let mut idx = index;
+    let mut path = vec![];
+
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            let last = *level.last().expect("merkle_path: level can't be empty here");
+            level.push(last);
+        }
+
+        let sibling_is_right = idx % 2 == 0;
+        let sibling_idx = if sibling_is_right { idx + 1 } else { idx - 1 };
+        path.push((level[sibling_idx], sibling_is_right));
+
+        level = merkle_reduce(level);
+        idx /= 2;
+    }
+
+    path
+}
+
+/// Recomputes a Merkle root by folding `txid` up through `path` -- each step hashing it with its
+/// claimed sibling on the indicated side -- and checks the result against `root`. Returns `false`
+/// if `path` doesn't fold up to `root`, without needing to know any of the block's other txids.
+/// `index` (the leaf position `path` was generated for) isn't itself consumed by the fold -- each
+/// step's direction is already carried by `path`'s own left/right flags -- but it's taken here to
+/// mirror `merkle_path`'s `(txids, index)` signature, since a caller verifying a proof naturally
+/// has the index the proof claims to be for on hand.
+pub fn verify_ops_inclusion(txid: &Txid, index: usize, path: &[([u8; 32], bool)], root: &OpsMerkleRoot) -> bool {
+    let _ = index;
+    let mut cur = [0u8; 32];
+    cur.copy_from_slice(txid.as_bytes());
+
+    for (sibling, sibling_is_right) in path.iter() {
+        cur = if *sibling_is_right {
+            merkle_node_hash(&cur, sibling)
+        } else {
+            merkle_node_hash(sibling, &cur)
+        };
+    }
+
+    &cur[..] == root.as_bytes()
+}
+
 impl ConsensusHash {
     pub fn empty() -> ConsensusHash {
         ConsensusHash::from_hex("0000000000000000000000000000000000000000").unwrap()
@@ -213,38 +456,45 @@ impl ConsensusHash {
     /// for the resulting consensus hash, and the geometric series of previous consensus
     /// hashes.  Note that prev_consensus_hashes should be in order from most-recent to
     /// least-recent.
-    pub fn from_ops(opshash: &OpsHash, total_burn: u64, prev_consensus_hashes: &Vec<ConsensusHash>) -> ConsensusHash {
+    ///
+    /// `params` identifies the network this consensus hash belongs to: its `fork_set_identifier`
+    /// and `network_magic` are mixed in ahead of everything else, in place of the single global
+    /// `SYSTEM_FORK_SET_VERSION` this used to hard-code, so the same ops hash/burn/history on two
+    /// different networks can never produce the same consensus hash.
+    ///
+    /// `epoch_id` is the epoch of the burn block this consensus hash is for; only Epoch 2.0 and
+    /// later tag the hash (see `epoch_gated_sha256`). The fork-set identifier and network magic
+    /// are mixed in at every epoch, Epoch 1.0 included.
+    pub fn from_ops(epoch_id: StacksEpochId, params: &ConsensusParams, opshash: &OpsHash, total_burn: u64, prev_consensus_hashes: &Vec<ConsensusHash>) -> ConsensusHash {
         // NOTE: unlike stacks v1, we calculate the next consensus hash
-        // simply as a hash-chain of the new ops hash, the sequence of 
+        // simply as a hash-chain of the new ops hash, the sequence of
         // previous consensus hashes, and the total burn that went into this
         // consensus hash.  We don't turn them into Merkle trees first.
-        
+
         // encode the burn as a string, so it's unambiguous regardless of architecture endianness
         // (and it's not constrained by the word size)
         let burn_str = format!("{}", total_burn);
         assert!(burn_str.is_ascii());
 
-        let result;
-        {
-            use sha2::Digest;
-            let mut hasher = Sha256::new();
-
-            // fork-set version... 
-            hasher.input(SYSTEM_FORK_SET_VERSION);
-
-            // ops hash...
-            hasher.input(opshash.as_bytes());
-            
-            // total burn amount on this fork...
-            hasher.input(burn_str.as_str().as_bytes());
+        let result = {
+            let mut parts: Vec<&[u8]> = vec![
+                // this network's fork-set identifier...
+                params.fork_set_identifier,
+                // ...and its network magic...
+                params.network_magic,
+                // ops hash...
+                opshash.as_bytes(),
+                // total burn amount on this fork...
+                burn_str.as_str().as_bytes(),
+            ];
 
             // previous consensus hashes...
             for ch in prev_consensus_hashes {
-                hasher.input(ch.as_bytes());
+                parts.push(ch.as_bytes());
             }
 
-            result = hasher.result();
-        }
+            epoch_gated_sha256(epoch_id, CONSENSUS_HASH_TAG, &parts)
+        };
 
         use ripemd160::Digest;
         let mut r160 = Ripemd160::new();
@@ -256,11 +506,12 @@ impl ConsensusHash {
     }
 
     /// Get the previous consensus hashes that must be hashed to find
-    /// the *next* consensus hash at a particular block.
-    pub fn get_prev_consensus_hashes<'a>(tx: &mut BurnDBTx<'a>, block_height: u64, first_block_height: u64, tip_block_hash: &BurnchainHeaderHash) -> Result<Vec<ConsensusHash>, db_error> {
+    /// the *next* consensus hash at a particular block. `params.first_block_height` bounds how
+    /// far back this walks, same as the plain `first_block_height` parameter this used to take.
+    pub fn get_prev_consensus_hashes<'a>(params: &ConsensusParams, tx: &mut BurnDBTx<'a>, block_height: u64, tip_block_hash: &BurnchainHeaderHash) -> Result<Vec<ConsensusHash>, db_error> {
         let mut i = 0;
         let mut prev_chs = vec![];
-        while i < 64 && block_height - (((1 as u64) << i) - 1) >= first_block_height {
+        while i < 64 && block_height - (((1 as u64) << i) - 1) >= params.first_block_height {
             let prev_block : u64 = block_height - (((1 as u64) << i) - 1);
             let prev_ch = BurnDB::get_consensus_at(tx, prev_block, tip_block_hash)
                 .expect(&format!("FATAL: failed to get consensus hash at {} in fork {}", prev_block, tip_block_hash));
@@ -274,27 +525,36 @@ impl ConsensusHash {
             }
         }
         if i == 64 {
-            // won't happen for a long, long time 
-            panic!("FATAL ERROR: numeric overflow when calculating a consensus hash for {} from genesis block height {}", block_height, first_block_height);
+            // won't happen for a long, long time
+            panic!("FATAL ERROR: numeric overflow when calculating a consensus hash for {} from genesis block height {}", block_height, params.first_block_height);
         }
 
         Ok(prev_chs)
     }
 
-    /// Make a new consensus hash, given the ops hash and parent block data
-    pub fn from_parent_block_data<'a>(tx: &mut BurnDBTx<'a>, opshash: &OpsHash, parent_block_height: u64, first_block_height: u64, parent_block_hash: &BurnchainHeaderHash, total_burn: u64) -> Result<ConsensusHash, db_error> {
-        let prev_consensus_hashes = ConsensusHash::get_prev_consensus_hashes(tx, parent_block_height, first_block_height, parent_block_hash)?;
-        Ok(ConsensusHash::from_ops(opshash, total_burn, &prev_consensus_hashes))
+    /// Same as `get_prev_consensus_hashes`, but takes the tip as a single typed `BestBurnBlock`
+    /// instead of a bare height/hash pair, so a caller already holding a canonical tip handle
+    /// can't accidentally pass its height alongside some other fork's hash.
+    pub fn get_prev_consensus_hashes_from_tip<'a>(params: &ConsensusParams, tx: &mut BurnDBTx<'a>, tip: &BestBurnBlock) -> Result<Vec<ConsensusHash>, db_error> {
+        ConsensusHash::get_prev_consensus_hashes(params, tx, tip.block_height(), tip.burn_header_hash())
     }
 
-    /// raw consensus hash
-    pub fn from_data(bytes: &[u8]) -> ConsensusHash {
-        let result = {
-            use sha2::Digest;
-            let mut hasher = Sha256::new();
-            hasher.input(bytes);
-            hasher.result()
-        };
+    /// Make a new consensus hash, given the ops hash and parent block data. `epoch_id` is the
+    /// epoch of the block being hashed, not necessarily the parent's -- see `from_ops`.
+    pub fn from_parent_block_data<'a>(epoch_id: StacksEpochId, params: &ConsensusParams, tx: &mut BurnDBTx<'a>, opshash: &OpsHash, parent_block_height: u64, parent_block_hash: &BurnchainHeaderHash, total_burn: u64) -> Result<ConsensusHash, db_error> {
+        let prev_consensus_hashes = ConsensusHash::get_prev_consensus_hashes(params, tx, parent_block_height, parent_block_hash)?;
+        Ok(ConsensusHash::from_ops(epoch_id, params, opshash, total_burn, &prev_consensus_hashes))
+    }
+
+    /// Same as `from_parent_block_data`, but takes the parent as a single typed `BestBurnBlock`
+    /// tip handle instead of a bare height/hash pair.
+    pub fn from_parent_tip<'a>(epoch_id: StacksEpochId, params: &ConsensusParams, tx: &mut BurnDBTx<'a>, opshash: &OpsHash, parent_tip: &BestBurnBlock, total_burn: u64) -> Result<ConsensusHash, db_error> {
+        ConsensusHash::from_parent_block_data(epoch_id, params, tx, opshash, parent_tip.block_height(), parent_tip.burn_header_hash(), total_burn)
+    }
+
+    /// raw consensus hash. `epoch_id` is the epoch of the block this hash is for.
+    pub fn from_data(epoch_id: StacksEpochId, bytes: &[u8]) -> ConsensusHash {
+        let result = epoch_gated_sha256(epoch_id, CONSENSUS_HASH_TAG, &[bytes]);
 
         use ripemd160::Digest;
         let mut r160 = Ripemd160::new();
@@ -344,6 +604,8 @@ mod tests {
                     parent_burn_header_hash: BurnchainHeaderHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(if i == 0 { 0xff } else { i-1 }) as u8]).unwrap(),
                     consensus_hash: ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
                     ops_hash: OpsHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
+                    ops_merkle_root: OpsMerkleRoot::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
+                    ops_filter_hash: filter::FilterHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
                     total_burn: i,
                     sortition: true,
                     sortition_hash: SortitionHash::initial(),
@@ -362,59 +624,59 @@ mod tests {
 
         let mut tx = db.tx_begin().unwrap();
         
-        let prev_chs_0 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 0, 0, &burn_block_hashes[0]).unwrap();
+        let prev_chs_0 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 0, &burn_block_hashes[0]).unwrap();
         assert_eq!(prev_chs_0, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]).unwrap()]);
         
-        let prev_chs_1 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 1, 0, &burn_block_hashes[1]).unwrap();
+        let prev_chs_1 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 1, &burn_block_hashes[1]).unwrap();
         assert_eq!(prev_chs_1, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]).unwrap()]);
         
-        let prev_chs_2 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 2, 0, &burn_block_hashes[2]).unwrap();
+        let prev_chs_2 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 2, &burn_block_hashes[2]).unwrap();
         assert_eq!(prev_chs_2, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1]).unwrap()]);
         
-        let prev_chs_3 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 3, 0, &burn_block_hashes[3]).unwrap();
+        let prev_chs_3 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 3, &burn_block_hashes[3]).unwrap();
         assert_eq!(prev_chs_3, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,3]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]).unwrap()]);
         
-        let prev_chs_4 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 4, 0, &burn_block_hashes[4]).unwrap();
+        let prev_chs_4 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 4, &burn_block_hashes[4]).unwrap();
         assert_eq!(prev_chs_4, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,4]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,3]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1]).unwrap()]);
         
-        let prev_chs_5 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 5, 0, &burn_block_hashes[5]).unwrap();
+        let prev_chs_5 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 5, &burn_block_hashes[5]).unwrap();
         assert_eq!(prev_chs_5, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,5]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,4]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2]).unwrap()]);
         
-        let prev_chs_6 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 6, 0, &burn_block_hashes[6]).unwrap();
+        let prev_chs_6 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 6, &burn_block_hashes[6]).unwrap();
         assert_eq!(prev_chs_6, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,6]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,5]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,3]).unwrap()]);
         
-        let prev_chs_7 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 7, 0, &burn_block_hashes[7]).unwrap();
+        let prev_chs_7 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 7, &burn_block_hashes[7]).unwrap();
         assert_eq!(prev_chs_7, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,7]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,6]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,4]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]).unwrap()]);
         
-        let prev_chs_8 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 8, 0, &burn_block_hashes[8]).unwrap();
+        let prev_chs_8 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 8, &burn_block_hashes[8]).unwrap();
         assert_eq!(prev_chs_8, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,8]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,7]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,5]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1]).unwrap()]);
         
-        let prev_chs_62 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 62, 0, &burn_block_hashes[62]).unwrap();
+        let prev_chs_62 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 62, &burn_block_hashes[62]).unwrap();
         assert_eq!(prev_chs_62, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,62]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,61]).unwrap(),
@@ -423,7 +685,7 @@ mod tests {
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,47]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,31]).unwrap()]);
 
-        let prev_chs_63 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 63, 0, &burn_block_hashes[63]).unwrap();
+        let prev_chs_63 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 63, &burn_block_hashes[63]).unwrap();
         assert_eq!(prev_chs_63, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,63]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,62]).unwrap(),
@@ -433,7 +695,7 @@ mod tests {
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,32]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]).unwrap()]);
 
-        let prev_chs_64 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 64, 0, &burn_block_hashes[64]).unwrap();
+        let prev_chs_64 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 64, &burn_block_hashes[64]).unwrap();
         assert_eq!(prev_chs_64, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,64]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,63]).unwrap(),
@@ -443,7 +705,7 @@ mod tests {
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,33]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1]).unwrap()]);
 
-        let prev_chs_126 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 126, 0, &burn_block_hashes[126]).unwrap();
+        let prev_chs_126 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 126, &burn_block_hashes[126]).unwrap();
         assert_eq!(prev_chs_126, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,126]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,125]).unwrap(),
@@ -453,7 +715,7 @@ mod tests {
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,95]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,63]).unwrap()]);
 
-        let prev_chs_127 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 127, 0, &burn_block_hashes[127]).unwrap();
+        let prev_chs_127 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 127, &burn_block_hashes[127]).unwrap();
         assert_eq!(prev_chs_127, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,127]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,126]).unwrap(),
@@ -464,7 +726,7 @@ mod tests {
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,64]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]).unwrap()]);
 
-        let prev_chs_128 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 128, 0, &burn_block_hashes[128]).unwrap();
+        let prev_chs_128 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 128, &burn_block_hashes[128]).unwrap();
         assert_eq!(prev_chs_128, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,128]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,127]).unwrap(),
@@ -475,7 +737,7 @@ mod tests {
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,65]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1]).unwrap()]);
         
-        let prev_chs_254 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 254, 0, &burn_block_hashes[254]).unwrap();
+        let prev_chs_254 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 254, &burn_block_hashes[254]).unwrap();
         assert_eq!(prev_chs_254, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,254]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,253]).unwrap(),
@@ -486,7 +748,7 @@ mod tests {
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,191]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,127]).unwrap()]);
 
-        let prev_chs_255 = ConsensusHash::get_prev_consensus_hashes(&mut tx, 255, 0, &burn_block_hashes[255]).unwrap();
+        let prev_chs_255 = ConsensusHash::get_prev_consensus_hashes(&ConsensusParams::regtest(), &mut tx, 255, &burn_block_hashes[255]).unwrap();
         assert_eq!(prev_chs_255, vec![
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,255]).unwrap(),
             ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,254]).unwrap(),
@@ -5,6 +5,34 @@ use crate::chainstate::burn::operations::Error as op_error;
 use clarity::types::chainstate::BurnchainHeaderHash;
 use std::convert::TryFrom;
 
+// NOTE: this tree carries no source for `Burnchain`, `SortitionHandleTx`, or
+// `chainstate::burn::operations::Error` themselves -- they're only ever referenced, never
+// defined, the same pervasive gap documented elsewhere in this tree (e.g. `burnchains::bitcoin`'s
+// missing `indexer`/`messages` modules). There's likewise no `apply()` step for any burn op in
+// this tree -- `check` below and `apply` further down are written against the following assumed
+// additions, following the shape of what's already confirmed to exist on neighboring types:
+//   - `Burnchain.deposit_confirmation_depth: u64` -- a new config field (mirroring the existing
+//     per-chain fields like `Burnchain.pox_constants`) giving the number of L1 confirmations a
+//     block must have before a deposit anchored in it is materialized on the subnet.
+//   - `SortitionHandleTx::get_burn_block_height(&mut self, bhh: &BurnchainHeaderHash) ->
+//     Result<Option<u64>, db_error>` -- looks up the height of `bhh` in the canonical L1 view this
+//     handle is tracking, `None` if `bhh` isn't (yet) part of it.
+//   - `SortitionHandleTx::get_canonical_burn_chain_tip(&mut self) -> Result<BlockSnapshot, db_error>`
+//     -- mirrors the accessor of the same name already used elsewhere in this tree's burnchain
+//     code to get the current L1 tip.
+//   - `SortitionHandleTx::has_deposit_ft_been_processed(&mut self, txid: &Txid) ->
+//     Result<bool, db_error>` / `SortitionHandleTx::mark_deposit_ft_processed(&mut self, txid:
+//     &Txid, bhh: &BurnchainHeaderHash) -> Result<(), db_error>` -- a txid-keyed table the
+//     sortition DB maintains so a deposit is applied at most once, even if the L1 reorgs and
+//     re-presents the same deposit transaction in a different block.
+//   - `op_error::Duplicate` and `op_error::DepositNotYetConfirmed` -- two new variants alongside
+//     the existing `InvalidInput`/`ParseError`/etc. `DepositNotYetConfirmed` is retryable: the
+//     coordinator should re-attempt this op on a later L1 block rather than rejecting it outright.
+// A `db_error` from any of the lookups above is folded into `op_error::InvalidInput` below --
+// this tree has no visibility into whether `op_error` carries a dedicated DB-error variant, and
+// treating a lookup failure as an invalid op is the same conservative default `check` already
+// falls back to elsewhere in this file.
+
 impl TryFrom<&StacksHyperOp> for DepositFtOp {
     type Error = op_error;
 
@@ -38,13 +66,51 @@ impl TryFrom<&StacksHyperOp> for DepositFtOp {
 impl DepositFtOp {
     pub fn check(
         &self,
-        _burnchain: &Burnchain,
-        _tx: &mut SortitionHandleTx,
+        burnchain: &Burnchain,
+        tx: &mut SortitionHandleTx,
     ) -> Result<(), op_error> {
-        // good to go!
+        // A deposit is only ever applied once: if this txid has already been materialized on the
+        // subnet (whether in this exact block or an earlier one before an L1 reorg moved it),
+        // reject it outright rather than double-crediting the sender.
+        if tx
+            .has_deposit_ft_been_processed(&self.txid)
+            .map_err(|_| op_error::InvalidInput)?
+        {
+            return Err(op_error::Duplicate);
+        }
+
+        // The L1 block this deposit is anchored in must be buried under at least
+        // `deposit_confirmation_depth` confirmations before we materialize it, so that an L1
+        // reorg can't retroactively invalidate a deposit the subnet has already acted on.
+        let deposit_block_height = tx
+            .get_burn_block_height(&self.burn_header_hash)
+            .map_err(|_| op_error::InvalidInput)?
+            .ok_or(op_error::DepositNotYetConfirmed)?;
+
+        let tip_height = tx
+            .get_canonical_burn_chain_tip()
+            .map_err(|_| op_error::InvalidInput)?
+            .block_height;
+
+        let confirmations = tip_height.saturating_sub(deposit_block_height);
+        if confirmations < burnchain.deposit_confirmation_depth {
+            return Err(op_error::DepositNotYetConfirmed);
+        }
+
         Ok(())
     }
 
+    /// Materializes this op once it's passed `check`: records it as processed so a later `check`
+    /// (e.g. on mempool re-admission, or a reorg replay that re-presents the same L1 deposit
+    /// transaction) recognizes it as a duplicate instead of re-crediting the sender. Kept
+    /// separate from `check` -- which every other op's `check` in this module is -- so that
+    /// calling `check` more than once for the same op (the normal check/apply split) doesn't
+    /// itself trip `op_error::Duplicate` against a deposit that was never actually applied.
+    pub fn apply(&self, tx: &mut SortitionHandleTx) -> Result<(), op_error> {
+        tx.mark_deposit_ft_processed(&self.txid, &self.burn_header_hash)
+            .map_err(|_| op_error::InvalidInput)
+    }
+
     #[cfg(test)]
     pub fn set_burn_height(&mut self, _height: u64) {}
 }
@@ -48,8 +48,20 @@ use util::log;
 use util::db::DBConn;
 use util::db::DBTx;
 
+use core::{EpochList, StacksEpoch, StacksEpochId};
+use core::deployments::{Deployment, DeploymentName, DeploymentTracker};
+
+use burnchains::ops_merkle::MerkleLeaf;
+
+/// Highest `UserBurnSupportOp` wire-format version this node knows how to parse. `check()` rejects
+/// anything higher outright rather than silently falling back to a version-0 read of a payload
+/// that may carry fields a later version defined -- those fields would simply be mistaken for
+/// memo bytes, which is worse than refusing to process the operation at all.
+const USER_BURN_SUPPORT_VERSION_CURRENT: u8 = 1;
+
 // return type for parse_data (below)
 struct ParsedData {
+    pub version: u8,
     pub consensus_hash: ConsensusHash,
     pub public_key: VRFPublicKey,
     pub key_block_backptr: u16,
@@ -62,6 +74,7 @@ impl UserBurnSupportOp {
     #[cfg(test)]
     pub fn new(public_key: &VRFPublicKey, key_block_height: u16, key_vtxindex: u16, block_hash: &BlockHeaderHash, burn_fee: u64) -> UserBurnSupportOp {
         UserBurnSupportOp {
+            version: USER_BURN_SUPPORT_VERSION_CURRENT,
             public_key: public_key.clone(),
             block_header_hash_160: Hash160::from_sha256(block_hash.as_bytes()),
             memo: vec![],
@@ -104,22 +117,37 @@ impl UserBurnSupportOp {
         /*
             Wire format:
 
-            0      2  3              23                       55                 75       77        79    80
-            |------|--|---------------|-----------------------|------------------|--------|---------|-----|
-             magic  op consensus hash    proving public key       block hash 160   key blk  key      memo
-                                                                                   backptr  vtxindex
+            0      2  3    4              24                       56                 76       78        80    81
+            |------|--|----|---------------|-----------------------|------------------|--------|---------|-----|
+             magic  op ver  consensus hash    proving public key       block hash 160   key blk  key      memo
+                                                                                        backptr  vtxindex
 
-            
-             Note that `data` is missing the first 3 bytes -- the magic and op have been stripped
+             Note that `data` is missing the first 3 bytes -- the magic and op have been stripped,
+             so the version byte below is actually the wire format's 4th byte, and is the first
+             thing `parse_data` reads out of `data`.
+
+             Version 0 is today's exact field layout, just shifted one byte later to make room for
+             this version byte. Version >= 1 reserves the span between `key_vtxindex` and `memo`
+             for whatever structured fields a later version defines; no version defined so far
+             (including 0) uses any of it, so `memo` always starts immediately after
+             `key_vtxindex` until a future version says otherwise.
         */
-        // memo can be empty, and magic + op are omitted 
-        if data.len() < 77 {
+        if data.len() == 0 {
+            warn!("USER_BURN_SUPPORT payload is malformed (0 bytes)");
+            return None;
+        }
+
+        let version = data[0];
+        let rest = &data[1..];
+
+        // memo can be empty
+        if rest.len() < 76 {
             warn!("USER_BURN_SUPPORT payload is malformed ({} bytes)", data.len());
             return None;
         }
 
-        let consensus_hash = ConsensusHash::from_vec(&data[0..20].to_vec()).expect("FATAL: invalid data slice for consensus hash");
-        let pubkey = match VRFPublicKey::from_bytes(&data[20..52].to_vec()) {
+        let consensus_hash = ConsensusHash::from_vec(&rest[0..20].to_vec()).expect("FATAL: invalid data slice for consensus hash");
+        let pubkey = match VRFPublicKey::from_bytes(&rest[20..52].to_vec()) {
             Some(pubk) => {
                 pubk
             },
@@ -129,13 +157,17 @@ impl UserBurnSupportOp {
             }
         };
 
-        let block_header_hash_160 = Hash160::from_vec(&data[52..72].to_vec()).expect("FATAL: invalid data slice for block hash160");
-        let key_block_backptr = parse_u16_from_be(&data[72..74]).unwrap();
-        let key_vtxindex = parse_u16_from_be(&data[74..76]).unwrap();
+        let block_header_hash_160 = Hash160::from_vec(&rest[52..72].to_vec()).expect("FATAL: invalid data slice for block hash160");
+        let key_block_backptr = parse_u16_from_be(&rest[72..74]).unwrap();
+        let key_vtxindex = parse_u16_from_be(&rest[74..76]).unwrap();
 
-        let memo = data[76..].to_vec();
+        // No version defined so far carries any trailing structured fields ahead of the memo, so
+        // the memo always starts right after key_vtxindex -- a version that adds fields will parse
+        // them out of this span before slicing out whatever remains as the memo.
+        let memo = rest[76..].to_vec();
 
         Some(ParsedData {
+            version,
             consensus_hash,
             public_key: pubkey,
             block_header_hash_160,
@@ -189,6 +221,7 @@ impl UserBurnSupportOp {
         }
 
         Ok(UserBurnSupportOp {
+            version: data.version,
             consensus_hash: data.consensus_hash,
             public_key: data.public_key,
             block_header_hash_160: data.block_header_hash_160,
@@ -206,12 +239,48 @@ impl UserBurnSupportOp {
     }
 }
 
+// NOTE: `check` below takes an `&EpochList` and a `&DeploymentTracker` so this op's acceptance
+// rules can branch on the active epoch and on soft-fork deployment status at
+// `block_header.block_height` (see `core::EpochList`, `core::deployments`). The caller is
+// responsible for advancing the `DeploymentTracker` (via `push`) over every burn block in height
+// order before calling `check` on the ops it contains -- that loop lives alongside
+// `chainstate::burn::db::burndb::BurnDB`, which has no file in this tree, so there's nowhere to
+// wire that up yet. `BlockstackOperation::check` (declared in `chainstate::burn::operations::mod`,
+// which also has no file in this tree) would need the same parameters added to its signature, and
+// `LeaderBlockCommitOp::check`/`LeaderKeyRegisterOp::check` would need the same threading --
+// neither has source in this tree to update.
 impl BlockstackOperation for UserBurnSupportOp {
     fn from_tx(block_header: &BurnchainBlockHeader, tx: &BurnchainTransaction) -> Result<UserBurnSupportOp, op_error> {
         UserBurnSupportOp::parse_from_tx(block_header.block_height, block_header.fork_segment_id, &block_header.block_hash, tx)
     }
 
-    fn check<'a>(&self, burnchain: &Burnchain, block_header: &BurnchainBlockHeader, tx: &mut DBTx<'a>) -> Result<(), op_error> {
+    fn check<'a>(&self, burnchain: &Burnchain, block_header: &BurnchainBlockHeader, tx: &mut DBTx<'a>, epochs: &EpochList, stricter_consensus_hash: &DeploymentTracker) -> Result<(), op_error> {
+        /////////////////////////////////////////////////////////////////
+        // Wire format version must be one this node actually knows how to interpret -- an
+        // unknown version may carry fields past key_vtxindex that this build has no parser for,
+        // so accepting it anyway would silently treat unparsed consensus-critical data as memo.
+        /////////////////////////////////////////////////////////////////
+        if self.version > USER_BURN_SUPPORT_VERSION_CURRENT {
+            warn!("Invalid user burn: unsupported wire format version {} (highest known: {})", self.version, USER_BURN_SUPPORT_VERSION_CURRENT);
+            return Err(op_error::UserBurnSupportBadVersion);
+        }
+
+        /////////////////////////////////////////////////////////////////
+        // Every burn height must fall within a configured epoch -- an op mined at a height the
+        // schedule doesn't cover (e.g. a test chain with a truncated `EpochList`) has no defined
+        // rule set to validate it against, so reject it outright rather than guessing.
+        /////////////////////////////////////////////////////////////////
+        let epoch = epochs
+            .epoch_at_height(block_header.block_height)
+            .ok_or(op_error::EpochUndefined)?;
+
+        // Epoch 2.0 tightened the minimum user burn: a 0 burn_fee op still parses, but only
+        // Epoch 1.0 accepted it into a block.
+        if epoch.epoch_id == StacksEpochId::Epoch20 && self.burn_fee == 0 {
+            warn!("Invalid user burn: burn_fee must be positive as of epoch {:?}", epoch.epoch_id);
+            return Err(op_error::ParseError);
+        }
+
         // this will be the chain tip we're building on
         let chain_tip = BurnDB::get_block_snapshot(tx, &block_header.parent_block_hash)
             .expect("FATAL: failed to query parent block snapshot")
@@ -231,6 +300,17 @@ impl BlockstackOperation for UserBurnSupportOp {
             return Err(op_error::UserBurnSupportBadConsensusHash);
         }
 
+        /////////////////////////////////////////////////////////////////
+        // Once the `StricterConsensusHash` deployment (core::deployments) has locked in and
+        // become active, "recent" is no longer good enough -- the op must carry the consensus
+        // hash of the chain tip it's building on exactly, same as `LeaderKeyRegisterOp` already
+        // requires.
+        /////////////////////////////////////////////////////////////////
+        if stricter_consensus_hash.is_active() && self.consensus_hash != chain_tip.consensus_hash {
+            warn!("Invalid user burn: consensus hash {} is not the chain tip's consensus hash (StricterConsensusHash is active)", &self.consensus_hash.to_hex());
+            return Err(op_error::UserBurnSupportBadConsensusHash);
+        }
+
         /////////////////////////////////////////////////////////////////////////////////////
         // There must exist a previously-accepted LeaderKeyRegisterOp that matches this 
         // user support burn's VRF public key.
@@ -264,9 +344,122 @@ impl BlockstackOperation for UserBurnSupportOp {
     }
 }
 
+/// A canonical, round-trippable byte encoding for a burn operation, independent of the raw
+/// OP_RETURN payload `parse_data`/`parse_from_tx` recognize inside a mined transaction -- this one
+/// is for gossiping or fuzzing an already-constructed op directly (so it also covers `burn_fee`,
+/// which `parse_data` never sees since it's read from the transaction's burn output instead of
+/// its OP_RETURN data). Fields not covered here (`txid`, `vtxindex`, `block_height`,
+/// `burn_header_hash`, `fork_segment_id`) are the op's position within a specific mined
+/// transaction and block, not part of the operation's own content, so `consensus_deserialize`
+/// zeroes them the same way `ParsedData` leaves them for the caller to fill in.
+///
+/// NOTE: `chainstate::burn::operations::mod` (where this trait's canonical home would be, next to
+/// `BlockstackOperation`) has no file in this tree, so it's declared here instead, alongside its
+/// only implementor.
+pub trait BurnOpConsensusCodec: Sized {
+    fn consensus_serialize(&self) -> Vec<u8>;
+    fn consensus_deserialize(bytes: &[u8]) -> Result<Self, op_error>;
+}
+
+impl BurnOpConsensusCodec for UserBurnSupportOp {
+    /// `version(1) || consensus_hash(20) || public_key(32) || block_header_hash_160(20) ||
+    /// key_block_backptr(2) || key_vtxindex(2) || burn_fee(8) || memo_len(4) || memo(memo_len)`.
+    /// `memo_len`/`memo` is the versioned extension region: version 0 never defines any field
+    /// past `memo`, so `consensus_deserialize` only attempts to read extension fields here once a
+    /// future version actually adds some -- exactly the same "no version so far uses the
+    /// reserved span" invariant `parse_data`'s wire-format doc comment describes.
+    fn consensus_serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.push(self.version);
+        bytes.extend_from_slice(self.consensus_hash.as_bytes());
+        bytes.extend_from_slice(self.public_key.as_bytes());
+        bytes.extend_from_slice(self.block_header_hash_160.as_bytes());
+        bytes.extend_from_slice(&self.key_block_backptr.to_be_bytes());
+        bytes.extend_from_slice(&self.key_vtxindex.to_be_bytes());
+        bytes.extend_from_slice(&self.burn_fee.to_be_bytes());
+        bytes.extend_from_slice(&(self.memo.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.memo);
+        bytes
+    }
+
+    fn consensus_deserialize(bytes: &[u8]) -> Result<UserBurnSupportOp, op_error> {
+        const FIXED_LEN: usize = 1 + 20 + 32 + 20 + 2 + 2 + 8 + 4;
+        if bytes.len() < FIXED_LEN {
+            return Err(op_error::ParseError);
+        }
+
+        let version = bytes[0];
+        let consensus_hash = ConsensusHash::from_vec(&bytes[1..21].to_vec())
+            .ok_or(op_error::ParseError)?;
+        let public_key = VRFPublicKey::from_bytes(&bytes[21..53].to_vec())
+            .ok_or(op_error::ParseError)?;
+        let block_header_hash_160 = Hash160::from_vec(&bytes[53..73].to_vec())
+            .ok_or(op_error::ParseError)?;
+        let key_block_backptr = parse_u16_from_be(&bytes[73..75]).ok_or(op_error::ParseError)?;
+        let key_vtxindex = parse_u16_from_be(&bytes[75..77]).ok_or(op_error::ParseError)?;
+
+        let mut burn_fee_bytes = [0u8; 8];
+        burn_fee_bytes.copy_from_slice(&bytes[77..85]);
+        let burn_fee = u64::from_be_bytes(burn_fee_bytes);
+
+        let mut memo_len_bytes = [0u8; 4];
+        memo_len_bytes.copy_from_slice(&bytes[85..89]);
+        let memo_len = u32::from_be_bytes(memo_len_bytes) as usize;
+
+        if bytes[89..].len() < memo_len {
+            return Err(op_error::ParseError);
+        }
+        let memo = bytes[89..(89 + memo_len)].to_vec();
+
+        // No version defined so far (including 0) appends fields after the memo, so there's
+        // nothing to parse out of `bytes[(89 + memo_len)..]` yet regardless of `version` -- a
+        // future version that adds extension fields would read them from that remainder here,
+        // guarded on `version >= <the version that introduced them>`.
+
+        Ok(UserBurnSupportOp {
+            version,
+            consensus_hash,
+            public_key,
+            block_header_hash_160,
+            key_block_backptr,
+            key_vtxindex,
+            burn_fee,
+            memo,
+
+            txid: Txid([0u8; 32]),
+            vtxindex: 0,
+            block_height: 0,
+            burn_header_hash: BurnchainHeaderHash([0u8; 32]),
+            fork_segment_id: 0,
+        })
+    }
+}
+
+impl MerkleLeaf for UserBurnSupportOp {
+    fn vtxindex(&self) -> u32 {
+        self.vtxindex
+    }
+
+    /// The same field layout `parse_data` reads (sans the leading version byte, which is folded
+    /// back in here), so a leaf hash commits to exactly the bytes a light client already has from
+    /// the op itself -- no separate canonical-encoding round trip through the raw transaction.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.push(self.version);
+        bytes.extend_from_slice(self.consensus_hash.as_bytes());
+        bytes.extend_from_slice(self.public_key.as_bytes());
+        bytes.extend_from_slice(self.block_header_hash_160.as_bytes());
+        bytes.extend_from_slice(&self.key_block_backptr.to_be_bytes());
+        bytes.extend_from_slice(&self.key_vtxindex.to_be_bytes());
+        bytes.extend_from_slice(&self.memo);
+        bytes
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use burnchains::ops_merkle::{compute_ops_root, merkle_path, verify_op_inclusion};
     use burnchains::bitcoin::blocks::BitcoinBlockParser;
     use burnchains::bitcoin::BitcoinNetworkType;
     use burnchains::Txid;
@@ -320,8 +513,9 @@ mod tests {
 
         let tx_fixtures: Vec<OpFixture> = vec![
             OpFixture {
-                txstr: "01000000011111111111111111111111111111111111111111111111111111111111111111000000006a47304402204c51707ac34b6dcbfc518ba40c5fc4ef737bf69cc21a9f8a8e6f621f511f78e002200caca0f102d5df509c045c4fe229d957aa7ef833dc8103dc2fe4db15a22bab9e012102d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d000000000030000000000000000536a4c5069645f2222222222222222222222222222222222222222a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a3333333333333333333333333333333333333333010203040539300000000000001976a914000000000000000000000000000000000000000088aca05b0000000000001976a9140be3e286a15ea85882761618e366586b5574100d88ac00000000".to_string(),
+                txstr: "01000000011111111111111111111111111111111111111111111111111111111111111111000000006a47304402204c51707ac34b6dcbfc518ba40c5fc4ef737bf69cc21a9f8a8e6f621f511f78e002200caca0f102d5df509c045c4fe229d957aa7ef833dc8103dc2fe4db15a22bab9e012102d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d000000000030000000000000000546a4c5169645f002222222222222222222222222222222222222222a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a3333333333333333333333333333333333333333010203040539300000000000001976a914000000000000000000000000000000000000000088aca05b0000000000001976a9140be3e286a15ea85882761618e366586b5574100d88ac00000000".to_string(),
                 result: Some(UserBurnSupportOp {
+                    version: 0,
                     consensus_hash: ConsensusHash::from_bytes(&hex_bytes("2222222222222222222222222222222222222222").unwrap()).unwrap(),
                     public_key: VRFPublicKey::from_bytes(&hex_bytes("a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a").unwrap()).unwrap(),
                     block_header_hash_160: Hash160::from_bytes(&hex_bytes("3333333333333333333333333333333333333333").unwrap()).unwrap(),
@@ -330,7 +524,7 @@ mod tests {
                     memo: vec![0x05],
                     burn_fee: 12345,
 
-                    txid: Txid::from_bytes_be(&hex_bytes("1d5cbdd276495b07f0e0bf0181fa57c175b217bc35531b078d62fc20986c716c").unwrap()).unwrap(),
+                    txid: Txid::from_bytes_be(&hex_bytes("36e14ada2a8b559c032ebbff5b04cfb35f9920354bcd6a5ace6d7145220175ba").unwrap()).unwrap(),
                     vtxindex: vtxindex,
                     block_height: block_height,
                     burn_header_hash: burn_header_hash,
@@ -488,6 +682,7 @@ mod tests {
             CheckFixture {
                 // reject -- bad consensus hash
                 op: UserBurnSupportOp {
+                    version: 0,
                     consensus_hash: ConsensusHash::from_bytes(&hex_bytes("1000000000000000000000000000000000000000").unwrap()).unwrap(),
                     public_key: VRFPublicKey::from_bytes(&hex_bytes("a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a").unwrap()).unwrap(),
                     block_header_hash_160: Hash160::from_bytes(&hex_bytes("7150f635054b87df566a970b21e07030d6444bf2").unwrap()).unwrap(),       // 22222....2222
@@ -508,6 +703,7 @@ mod tests {
             CheckFixture {
                 // reject -- no leader key
                 op: UserBurnSupportOp {
+                    version: 0,
                     consensus_hash: ConsensusHash::from_bytes(&hex_bytes("0000000000000000000000000000000000000000").unwrap()).unwrap(),
                     public_key: VRFPublicKey::from_bytes(&hex_bytes("bb519494643f79f1dea0350e6fb9a1da88dfdb6137117fc2523824a8aa44fe1c").unwrap()).unwrap(),
                     block_header_hash_160: Hash160::from_bytes(&hex_bytes("7150f635054b87df566a970b21e07030d6444bf2").unwrap()).unwrap(),       // 22222....2222
@@ -528,6 +724,7 @@ mod tests {
             CheckFixture {
                 // accept 
                 op: UserBurnSupportOp {
+                    version: 0,
                     consensus_hash: ConsensusHash::from_bytes(&hex_bytes("0000000000000000000000000000000000000000").unwrap()).unwrap(),
                     public_key: VRFPublicKey::from_bytes(&hex_bytes("a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a").unwrap()).unwrap(),
                     block_header_hash_160: Hash160::from_bytes(&hex_bytes("7150f635054b87df566a970b21e07030d6444bf2").unwrap()).unwrap(),       // 22222....2222
@@ -547,6 +744,23 @@ mod tests {
             }
         ];
 
+        let epochs = EpochList::new(&[StacksEpoch {
+            epoch_id: StacksEpochId::Epoch20,
+            start_height: 0,
+            end_height: u64::max_value(),
+        }]);
+
+        // not yet signaled for, so `check` exercises today's "is_fresh_consensus_hash" rule
+        // rather than the stricter one `StricterConsensusHash` would gate once active.
+        let stricter_consensus_hash = DeploymentTracker::new(Deployment {
+            name: DeploymentName::StricterConsensusHash,
+            bit: 0,
+            start_height: 1_000_000,
+            timeout_height: 2_000_000,
+            window_size: 1000,
+            threshold: 950,
+        });
+
         for fixture in check_fixtures {
             let header = BurnchainBlockHeader {
                 block_height: fixture.op.block_height,
@@ -559,8 +773,91 @@ mod tests {
                 fork_length: 1,
             };
             let mut tx = db.tx_begin().unwrap();
-            assert_eq!(fixture.res, fixture.op.check(&burnchain, &header, &mut tx));
+            assert_eq!(fixture.res, fixture.op.check(&burnchain, &header, &mut tx, &epochs, &stricter_consensus_hash));
+        }
+    }
+
+    #[test]
+    fn test_ops_merkle_root() {
+        let pubkey = VRFPublicKey::from_bytes(&hex_bytes("a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a").unwrap()).unwrap();
+        let block_hash = BlockHeaderHash([0x11; 32]);
+
+        let mut accepted: Vec<UserBurnSupportOp> = (0..3).map(|i| {
+            let mut op = UserBurnSupportOp::new(&pubkey, 1, 100 + i, &block_hash, 10000 + i as u64);
+            op.vtxindex = i;
+            op
+        }).collect();
+
+        // never included in `accepted`, so it must not verify against its root
+        let mut rejected = UserBurnSupportOp::new(&pubkey, 1, 999, &block_hash, 1);
+        rejected.vtxindex = 99;
+
+        let root = compute_ops_root(&accepted);
+
+        for (i, op) in accepted.iter().enumerate() {
+            let path = merkle_path(&accepted, i);
+            assert!(verify_op_inclusion(op, &path, &root));
         }
+
+        // shuffling the backing slice can't change which ops verify, since `compute_ops_root`
+        // and `merkle_path` both sort by `vtxindex` before building the tree
+        accepted.reverse();
+        for (i, op) in accepted.iter().enumerate() {
+            let path = merkle_path(&accepted, i);
+            assert!(verify_op_inclusion(op, &path, &root));
+        }
+
+        let bogus_path = merkle_path(&accepted, 0);
+        assert!(!verify_op_inclusion(&rejected, &bogus_path, &root));
+    }
+
+    // This tree doesn't otherwise depend on `proptest`, so this exercises the same "encode
+    // arbitrary field combinations, decode, and compare" property over a fixed table of
+    // hand-built cases instead of a generated one -- consistent with every other test in this
+    // module, which all use fixture tables rather than a property-testing crate.
+    #[test]
+    fn test_consensus_serialize_roundtrip() {
+        let pubkeys = [
+            "a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a",
+            "bb519494643f79f1dea0350e6fb9a1da88dfdb6137117fc2523824a8aa44fe1c",
+        ];
+        let memos: Vec<Vec<u8>> = vec![vec![], vec![0x05], vec![0xff; 32]];
+
+        for pubkey_hex in pubkeys.iter() {
+            for memo in memos.iter() {
+                let op = UserBurnSupportOp {
+                    version: USER_BURN_SUPPORT_VERSION_CURRENT,
+                    consensus_hash: ConsensusHash::from_bytes(&hex_bytes("2222222222222222222222222222222222222222").unwrap()).unwrap(),
+                    public_key: VRFPublicKey::from_bytes(&hex_bytes(pubkey_hex).unwrap()).unwrap(),
+                    block_header_hash_160: Hash160::from_bytes(&hex_bytes("3333333333333333333333333333333333333333").unwrap()).unwrap(),
+                    key_block_backptr: 513,
+                    key_vtxindex: 1027,
+                    memo: memo.clone(),
+                    burn_fee: 12345,
+
+                    txid: Txid([0u8; 32]),
+                    vtxindex: 0,
+                    block_height: 0,
+                    burn_header_hash: BurnchainHeaderHash([0u8; 32]),
+                    fork_segment_id: 0,
+                };
+
+                let bytes = op.consensus_serialize();
+                let decoded = UserBurnSupportOp::consensus_deserialize(&bytes).unwrap();
+
+                assert_eq!(decoded.version, op.version);
+                assert_eq!(decoded.consensus_hash, op.consensus_hash);
+                assert_eq!(decoded.public_key, op.public_key);
+                assert_eq!(decoded.block_header_hash_160, op.block_header_hash_160);
+                assert_eq!(decoded.key_block_backptr, op.key_block_backptr);
+                assert_eq!(decoded.key_vtxindex, op.key_vtxindex);
+                assert_eq!(decoded.burn_fee, op.burn_fee);
+                assert_eq!(decoded.memo, op.memo);
+            }
+        }
+
+        // truncated input (missing even the fixed-length prefix) must be rejected, not panic
+        assert_eq!(UserBurnSupportOp::consensus_deserialize(&[0u8; 10]), Err(op_error::ParseError));
     }
 }
 
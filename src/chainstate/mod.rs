@@ -14,12 +14,43 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::io::{Read, Write};
+
 use util_lib::db;
 
 use util_lib::db::Error as db_error;
 
+// NOTE: `util_lib::db::Error` (`db_error` here) is defined in an external crate this tree carries
+// no source for, so its existing variants aren't visible here -- same gap as `op_error` in
+// `chainstate::burn::operations::deposit_ft`. The methods below are written against one assumed
+// addition to it: a `db_error::ChecksumMismatch` variant for `verify` to return when a backup
+// chunk's recomputed checksum doesn't match the one recorded for it, alongside whatever existing
+// variant(s) already cover plain IO/consistency failures (the same ones `backup` already returns
+// today).
 pub trait ChainstateDB {
     fn backup(backup_path: &String) -> Result<(), db_error>;
+
+    /// Streams a consistent point-in-time snapshot of this DB to `out`, suitable for running
+    /// while the node continues to mine -- a real implementation would drive this with SQLite's
+    /// online backup API (`sqlite3_backup_init`/`_step`/`_finish`, as wrapped by
+    /// `rusqlite::backup::Backup`) instead of `backup`'s plain file copy, so readers of the source
+    /// DB are never blocked waiting for the backup to finish.
+    fn backup_to_writer(&self, out: &mut dyn Write) -> Result<(), db_error>;
+
+    /// Streams only the MARF/sortition pages that changed since the checkpoint at `since`, so a
+    /// previously-taken full (or incremental) backup can be brought up to date without
+    /// re-transferring pages neither the MARF trie nor the sortition DB touched past that height.
+    fn backup_incremental(&self, since: u64, out: &mut dyn Write) -> Result<(), db_error>;
+
+    /// Applies a backup produced by `backup_to_writer` or `backup_incremental` to this DB.
+    /// Incremental backups are applied on top of the full backup (or prior incremental backup)
+    /// they were taken relative to; applying one out of order is a consistency failure.
+    fn restore_from_reader(&self, input: &mut dyn Read) -> Result<(), db_error>;
+
+    /// Recomputes each chunk's checksum in the backup at `backup_path` and checks it against the
+    /// checksum recorded alongside that chunk when the backup was written, catching corruption
+    /// introduced after the backup was taken (e.g. in transit, or at rest in cold storage).
+    fn verify(backup_path: &String) -> Result<(), db_error>;
 }
 
 // needs to come _after_ the macro def above, since they both use this macro
@@ -0,0 +1,276 @@
+/*
+ copyright: (c) 2013-2019 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+// A textual codec for `StacksAddress`, plus network/signature-kind inference from its version
+// byte.
+//
+// NOTE: `address.rs` was declared (`pub mod address;` in `chainstate::stacks::mod`) but had no
+// file behind it before this -- the same "declared but missing" gap `chainstate::stacks::events`/
+// `chainstate::stacks::block`/`chainstate::stacks::transaction` had before this session's
+// `[...#chunk22-2]`/`[...#chunk23-1]`/`[...#chunk23-3]` fixes. This implements the real c32check
+// scheme (big-integer base-32 digits, double-SHA256 checksum, single-character version prefix)
+// rather than an invented format, so that `StacksAddress::to_c32_string`/`from_string` agree with
+// what `vm::functions::principals`'s `principal-to-string`/`string-to-principal` natives (and any
+// real Stacks wallet/explorer) consider the canonical string for the same address bytes. The
+// encode/decode/checksum primitives here are `pub(crate)` so `principals` can build a principal's
+// string on top of the exact same code instead of maintaining a second, possibly-diverging copy.
+
+use sha2::{Digest, Sha256};
+
+use util::hash::{Hash160, HASH160_ENCODED_SIZE};
+
+use chainstate::stacks::{
+    StacksAddress, C32_ADDRESS_VERSION_MAINNET_MULTISIG, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+    C32_ADDRESS_VERSION_TESTNET_MULTISIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+};
+
+/// The alphabet a c32 encoding is drawn from: the 32 ASCII characters Crockford's base32 uses,
+/// chosen to exclude the visually-ambiguous `I`, `L`, `O`, and `U`.
+pub(crate) const C32_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The length, in bytes, of the payload that follows the version character in a c32 address
+/// string: the `Hash160` and a 4-byte checksum.
+const C32_ADDRESS_BODY_LEN: usize = HASH160_ENCODED_SIZE as usize + 4;
+
+/// Why `StacksAddress::from_string` rejected its input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddressParseError {
+    /// The input wasn't a `'S'` followed by characters drawn from the c32 alphabet, or wasn't
+    /// the length a c32-encoded address payload always is.
+    InvalidEncoding,
+    /// The decoded payload's checksum didn't match the one recomputed from the version byte and
+    /// `Hash160` it was decoded alongside.
+    InvalidChecksum,
+    /// The decoded version byte doesn't match any of the four `C32_ADDRESS_VERSION_*` constants.
+    UnknownVersion,
+    /// The input decoded successfully, but re-encoding the decoded bytes doesn't reproduce the
+    /// input exactly (e.g. it used lowercase letters, or non-canonical leading-zero digits) --
+    /// every address has exactly one canonical rendering, and this input wasn't it.
+    NotCanonical,
+}
+
+/// Which Stacks network a `StacksAddress`'s version byte was minted for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+/// Whether a `StacksAddress`'s version byte names a single-signature or multisignature account.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddrKind {
+    Singlesig,
+    Multisig,
+}
+
+/// Encodes `input` as a c32 string: big-endian base-32 digits drawn from `C32_ALPHABET`, with one
+/// leading `'0'` emitted per leading zero byte of `input` (so the encoded length of an
+/// all-zero-prefixed buffer doesn't collapse the way a naive big-integer encoding would).
+pub(crate) fn c32_encode(input: &[u8]) -> String {
+    let leading_zeros = input.iter().take_while(|&&b| b == 0).count();
+
+    let mut digits = input.to_vec();
+    let mut base32_digits = Vec::new();
+    while digits.iter().any(|&b| b != 0) {
+        let mut remainder: u32 = 0;
+        for byte in digits.iter_mut() {
+            let acc = (remainder << 8) | (*byte as u32);
+            *byte = (acc / 32) as u8;
+            remainder = acc % 32;
+        }
+        base32_digits.push(C32_ALPHABET[remainder as usize]);
+    }
+    base32_digits.reverse();
+
+    let chars: Vec<u8> = std::iter::repeat(C32_ALPHABET[0])
+        .take(leading_zeros)
+        .chain(base32_digits.into_iter())
+        .collect();
+    String::from_utf8(chars).expect("c32 alphabet is ASCII")
+}
+
+/// Reverses `c32_encode`, left-padding or validating down to exactly `expected_len` bytes.
+/// Returns `None` if `input` contains a character outside `C32_ALPHABET`, or decodes to more
+/// significant bytes than `expected_len` allows.
+pub(crate) fn c32_decode(input: &str, expected_len: usize) -> Option<Vec<u8>> {
+    let leading_zero_chars = input.bytes().take_while(|&b| b == C32_ALPHABET[0]).count();
+    let body = &input[leading_zero_chars..];
+
+    let mut value: Vec<u8> = Vec::new();
+    for ch in body.bytes() {
+        let digit = C32_ALPHABET.iter().position(|&a| a == ch.to_ascii_uppercase())? as u32;
+        let mut carry = digit;
+        for byte in value.iter_mut().rev() {
+            let acc = (*byte as u32) * 32 + carry;
+            *byte = (acc & 0xff) as u8;
+            carry = acc >> 8;
+        }
+        while carry > 0 {
+            value.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut result = vec![0u8; leading_zero_chars];
+    result.extend(value);
+
+    if result.len() > expected_len {
+        let strip = result.len() - expected_len;
+        if result[..strip].iter().any(|&b| b != 0) {
+            return None;
+        }
+        result = result[strip..].to_vec();
+    } else if result.len() < expected_len {
+        let mut padded = vec![0u8; expected_len - result.len()];
+        padded.extend(result);
+        result = padded;
+    }
+
+    Some(result)
+}
+
+/// The checksum a c32check payload is appended with: the first 4 bytes of
+/// `Sha256(Sha256(version_and_hash))`.
+pub(crate) fn c32_checksum(version_and_hash: &[u8]) -> [u8; 4] {
+    let first_pass = Sha256::digest(version_and_hash);
+    let second_pass = Sha256::digest(&first_pass);
+
+    let mut checksum = [0u8; 4];
+    checksum.copy_from_slice(&second_pass[0..4]);
+    checksum
+}
+
+impl StacksAddress {
+    /// Renders this address as `'S'` followed by the c32 encoding of its version byte and the
+    /// c32 encoding of its `Hash160` plus checksum. Always produces the unique canonical string
+    /// `from_string` will accept back.
+    pub fn to_c32_string(&self) -> String {
+        let mut preimage = Vec::with_capacity(1 + HASH160_ENCODED_SIZE as usize);
+        preimage.push(self.version);
+        preimage.extend_from_slice(self.bytes.as_bytes());
+        let checksum = c32_checksum(&preimage);
+
+        let mut body = Vec::with_capacity(C32_ADDRESS_BODY_LEN);
+        body.extend_from_slice(self.bytes.as_bytes());
+        body.extend_from_slice(&checksum);
+
+        format!("S{}{}", c32_encode(&[self.version]), c32_encode(&body))
+    }
+
+    /// Parses a c32-encoded address string, checking its checksum and rejecting any rendering
+    /// other than the one `to_c32_string` would have produced for the same bytes (e.g. one using
+    /// lowercase letters where the canonical form is uppercase).
+    pub fn from_string(input: &str) -> Result<StacksAddress, AddressParseError> {
+        let body = input.strip_prefix('S').ok_or(AddressParseError::InvalidEncoding)?;
+        if body.is_empty() {
+            return Err(AddressParseError::InvalidEncoding);
+        }
+
+        let version = c32_decode(&body[0..1], 1).ok_or(AddressParseError::InvalidEncoding)?[0];
+        let payload =
+            c32_decode(&body[1..], C32_ADDRESS_BODY_LEN).ok_or(AddressParseError::InvalidEncoding)?;
+        let (hash160_bytes, checksum) = payload.split_at(HASH160_ENCODED_SIZE as usize);
+
+        let mut preimage = Vec::with_capacity(1 + HASH160_ENCODED_SIZE as usize);
+        preimage.push(version);
+        preimage.extend_from_slice(hash160_bytes);
+        if c32_checksum(&preimage) != checksum {
+            return Err(AddressParseError::InvalidChecksum);
+        }
+
+        let address = StacksAddress {
+            version,
+            bytes: Hash160::from_bytes(hash160_bytes).expect(
+                "Hash160::from_bytes should always succeed on HASH160_ENCODED_SIZE bytes",
+            ),
+        };
+
+        // Confirms the version byte is one this node recognizes before handing back an address
+        // a caller might otherwise mistake for belonging to a network/signature kind it doesn't.
+        address.network_and_kind()?;
+
+        if address.to_c32_string() != input {
+            return Err(AddressParseError::NotCanonical);
+        }
+
+        Ok(address)
+    }
+
+    /// Infers this address's network and signature kind from its version byte, by matching it
+    /// against the four `C32_ADDRESS_VERSION_*` constants.
+    pub fn network_and_kind(&self) -> Result<(Network, AddrKind), AddressParseError> {
+        match self.version {
+            C32_ADDRESS_VERSION_MAINNET_SINGLESIG => Ok((Network::Mainnet, AddrKind::Singlesig)),
+            C32_ADDRESS_VERSION_MAINNET_MULTISIG => Ok((Network::Mainnet, AddrKind::Multisig)),
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG => Ok((Network::Testnet, AddrKind::Singlesig)),
+            C32_ADDRESS_VERSION_TESTNET_MULTISIG => Ok((Network::Testnet, AddrKind::Multisig)),
+            _ => Err(AddressParseError::UnknownVersion),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_c32_round_trip() {
+        let address = StacksAddress {
+            version: C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+            bytes: Hash160::from_bytes(&[0x05, 0x2a, 0x4f, 0x74, 0x99, 0xbe, 0xe3, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])
+                .unwrap(),
+        };
+        let rendered = address.to_c32_string();
+        let parsed = StacksAddress::from_string(&rendered).unwrap();
+        assert_eq!(address, parsed);
+    }
+
+    #[test]
+    fn test_c32_cross_checks_principal_to_string() {
+        use vm::functions::principals::encode_principal_string;
+
+        let address = StacksAddress {
+            version: C32_ADDRESS_VERSION_TESTNET_MULTISIG,
+            bytes: Hash160::from_bytes(&[0xaa; 20]).unwrap(),
+        };
+
+        // `StacksAddress::to_c32_string` must render the exact same string the Clarity
+        // `principal-to-string` native would for the same (version, hash) pair, since both now
+        // build on the same c32check primitives in this module.
+        let expected = encode_principal_string(address.version, address.bytes.as_bytes(), None);
+        assert_eq!(address.to_c32_string(), expected);
+    }
+
+    #[test]
+    fn test_from_string_rejects_bad_checksum() {
+        let address = StacksAddress {
+            version: C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+            bytes: Hash160::from_bytes(&[0x11; 20]).unwrap(),
+        };
+        let mut rendered = address.to_c32_string();
+        let last = rendered.pop().unwrap();
+        let replacement = if last == '0' { '1' } else { '0' };
+        rendered.push(replacement);
+
+        match StacksAddress::from_string(&rendered) {
+            Err(AddressParseError::InvalidChecksum) | Err(AddressParseError::NotCanonical) => {}
+            other => panic!("expected a checksum/canonical rejection, got {:?}", other),
+        }
+    }
+}
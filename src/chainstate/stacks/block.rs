@@ -0,0 +1,231 @@
+/*
+ copyright: (c) 2013-2019 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+// A BIP152-style compact block representation for `StacksBlock`: a peer that already holds most
+// of a block's transactions in its mempool can be sent a `CompactStacksBlock` -- the header plus
+// a 6-byte short ID per transaction, with a handful of transactions the sender doesn't expect the
+// receiver to have already (e.g. the coinbase) included in full -- and reconstruct the full block
+// locally instead of waiting on the whole ~1MB body.
+//
+// NOTE: the `net` module these message types and their relay logic ultimately belong to (the
+// p2p message carrying a `CompactStacksBlock`, and the follow-up request for any transactions a
+// peer couldn't resolve against its mempool) has no source anywhere in this tree -- only ever
+// referenced, e.g. `net::StacksMessageCodec`/`net::codec::{read_next, write_next}` above in this
+// same module's `mod.rs`, never defined. `reconstruct` below is the self-contained piece: given a
+// `CompactStacksBlock` and a peer's mempool contents, it either produces the full `StacksBlock` or
+// reports which transaction indices still need to be fetched, the same decision a real relay
+// handler would make before either delivering the block or sending a follow-up request. Wiring
+// that follow-up request over the wire isn't possible here.
+//
+// This also assumes `StacksBlockHeader` picks up a `StacksMessageCodec` impl of its own (it has no
+// impl in this tree -- `block.rs` was declared in `chainstate::stacks::mod` but had no file behind
+// it before this, the same "declared but missing" gap as `chainstate::stacks::events` had before
+// this session's `[toptrust3/stacks-blockchain#chunk22-2]` fix), since a compact block needs to
+// hash and transmit the header the same way `StacksBlock`'s own (equally un-implemented) codec
+// would.
+
+use std::hash::Hasher;
+
+use siphasher::sip::SipHasher24;
+
+use util::hash::Sha512_256;
+
+use burnchains::Txid;
+
+use net::StacksMessageCodec;
+use net::codec::{read_next, write_next};
+use net::Error as net_error;
+
+use chainstate::stacks::{StacksBlock, StacksBlockHeader, StacksTransaction};
+
+/// A 6-byte short transaction ID, computed by SipHash-2-4-ing a transaction's txid under a key
+/// derived from the compact block's own header and nonce (see `compute_short_id_key`) and
+/// truncating the 64-bit digest to its low 48 bits. Six bytes keeps the advertisement small while
+/// still making an accidental collision between two mempool transactions astronomically unlikely
+/// for any one block -- and on the rare occasion one does happen, the block is simply
+/// re-requested in full (see `reconstruct`).
+pub struct ShortTxId(pub [u8; 6]);
+impl_array_newtype!(ShortTxId, u8, 6);
+impl_array_hexstring_fmt!(ShortTxId);
+impl_byte_array_newtype!(ShortTxId, u8, 6);
+impl_byte_array_message_codec!(ShortTxId, 6);
+
+/// A transaction included in full in a `CompactStacksBlock`, at the position it occupies in the
+/// reconstructed block's transaction list. The sender always prefills any transaction it has no
+/// reason to expect the receiver already holds (most importantly the coinbase, which exists
+/// nowhere but in this one block).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefilledTransaction {
+    pub index: u64,
+    pub tx: StacksTransaction,
+}
+
+impl StacksMessageCodec for PrefilledTransaction {
+    fn serialize(&self) -> Vec<u8> {
+        let mut res = vec![];
+        write_next(&mut res, &self.index);
+        write_next(&mut res, &self.tx);
+        res
+    }
+
+    fn deserialize(buf: &Vec<u8>, index: &mut u32, max_size: u32) -> Result<PrefilledTransaction, net_error> {
+        let tx_index: u64 = read_next(buf, index, max_size)?;
+        let tx: StacksTransaction = read_next(buf, index, max_size)?;
+        Ok(PrefilledTransaction { index: tx_index, tx })
+    }
+}
+
+/// A BIP152-style compact announcement of a `StacksBlock`: its header, the nonce the short IDs
+/// below are salted with, a handful of transactions included in full (`prefilled`), and a short
+/// ID per remaining transaction (`short_ids`, in block order, skipping whichever indices are
+/// covered by `prefilled`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactStacksBlock {
+    pub header: StacksBlockHeader,
+    pub nonce: u64,
+    pub prefilled: Vec<PrefilledTransaction>,
+    pub short_ids: Vec<ShortTxId>,
+}
+
+impl StacksMessageCodec for CompactStacksBlock {
+    fn serialize(&self) -> Vec<u8> {
+        let mut res = vec![];
+        write_next(&mut res, &self.header);
+        write_next(&mut res, &self.nonce);
+        write_next(&mut res, &self.prefilled);
+        write_next(&mut res, &self.short_ids);
+        res
+    }
+
+    fn deserialize(buf: &Vec<u8>, index: &mut u32, max_size: u32) -> Result<CompactStacksBlock, net_error> {
+        let header: StacksBlockHeader = read_next(buf, index, max_size)?;
+        let nonce: u64 = read_next(buf, index, max_size)?;
+        let prefilled: Vec<PrefilledTransaction> = read_next(buf, index, max_size)?;
+        let short_ids: Vec<ShortTxId> = read_next(buf, index, max_size)?;
+        Ok(CompactStacksBlock { header, nonce, prefilled, short_ids })
+    }
+}
+
+/// Derives a compact block's SipHash-2-4 key from `Sha512_256(header_bytes || le_u64(nonce))`'s
+/// first 16 bytes, split little-endian into two `u64` halves -- salting each block's short IDs
+/// with both its header and an explicit nonce (rather than the header alone, the way
+/// `chainstate::burn::filter`'s BIP158 filters key off the burn header hash) so a sender that
+/// keeps hitting unlucky short-ID collisions against a given peer's mempool can simply pick a new
+/// nonce and retry, without having to alter the block itself to do so.
+fn compute_short_id_key(header: &StacksBlockHeader, nonce: u64) -> (u64, u64) {
+    let mut preimage = StacksMessageCodec::serialize(header);
+    preimage.extend_from_slice(&nonce.to_le_bytes());
+    let digest = Sha512_256::from_data(&preimage);
+    let bytes = digest.as_bytes();
+
+    let mut k0_bytes = [0u8; 8];
+    let mut k1_bytes = [0u8; 8];
+    k0_bytes.copy_from_slice(&bytes[0..8]);
+    k1_bytes.copy_from_slice(&bytes[8..16]);
+    (u64::from_le_bytes(k0_bytes), u64::from_le_bytes(k1_bytes))
+}
+
+/// Computes `txid`'s short ID under the SipHash-2-4 key `(k0, k1)`: the low 48 bits of
+/// `siphash24(k0, k1, txid_bytes)`, encoded little-endian into 6 bytes.
+fn compute_short_id(k0: u64, k1: u64, txid: &Txid) -> ShortTxId {
+    let mut hasher = SipHasher24::new_with_keys(k0, k1);
+    hasher.write(txid.as_bytes());
+    let digest = hasher.finish();
+
+    let mut bytes = [0u8; 6];
+    bytes.copy_from_slice(&digest.to_le_bytes()[0..6]);
+    ShortTxId(bytes)
+}
+
+/// Computes the short ID every transaction in `block` would be advertised under in a
+/// `CompactStacksBlock` keyed by `(block.header, nonce)`. Exposed so a sender can build a
+/// `CompactStacksBlock` from a block it already has in hand: prefill whichever transactions it
+/// chooses (typically just the coinbase), then advertise the rest by the short ID computed here.
+pub fn compute_short_ids(header: &StacksBlockHeader, nonce: u64, txids: &[Txid]) -> Vec<ShortTxId> {
+    let (k0, k1) = compute_short_id_key(header, nonce);
+    txids.iter().map(|txid| compute_short_id(k0, k1, txid)).collect()
+}
+
+/// The result of attempting to reconstruct a full block from a `CompactStacksBlock` against a
+/// peer's own mempool contents.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReconstructResult {
+    /// Every advertised short ID (and prefilled transaction) was resolved; here's the block.
+    Block(StacksBlock),
+    /// At least one short ID couldn't be matched to a mempool transaction -- here are the
+    /// positions (in `compact.short_ids`, i.e. excluding the prefilled ones) still missing, for
+    /// the receiver to request individually.
+    Missing(Vec<usize>),
+    /// Two or more mempool transactions hashed to the same short ID under this compact block's
+    /// key. Short IDs are too ambiguous to resolve safely in this case -- per BIP152, the block
+    /// must be re-requested in full (e.g. by asking for a `CompactStacksBlock` salted with a
+    /// fresh nonce, or for the block outright) rather than risk substituting the wrong
+    /// transaction into the reconstructed block.
+    Collision,
+}
+
+/// Reconstructs the block `compact` advertises, using `mempool_txs` as the set of transactions
+/// the receiver already holds. Mempool transactions are matched against `compact.short_ids` by
+/// recomputing each one's short ID under `compact`'s own `(header, nonce)` key; `compact.prefilled`
+/// entries are slotted into the result at their recorded index directly.
+pub fn reconstruct(compact: &CompactStacksBlock, mempool_txs: &[StacksTransaction]) -> ReconstructResult {
+    let (k0, k1) = compute_short_id_key(&compact.header, compact.nonce);
+
+    let mut by_short_id: Vec<(ShortTxId, &StacksTransaction)> = mempool_txs
+        .iter()
+        .map(|tx| (compute_short_id(k0, k1, &tx.txid()), tx))
+        .collect();
+    by_short_id.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+    for window in by_short_id.windows(2) {
+        if window[0].0 == window[1].0 {
+            return ReconstructResult::Collision;
+        }
+    }
+
+    let total_len = compact.prefilled.len() + compact.short_ids.len();
+    let mut txs: Vec<Option<StacksTransaction>> = vec![None; total_len];
+    for prefilled in compact.prefilled.iter() {
+        txs[prefilled.index as usize] = Some(prefilled.tx.clone());
+    }
+
+    let mut missing = vec![];
+    let mut short_id_cursor = 0;
+    for slot in txs.iter_mut() {
+        if slot.is_some() {
+            continue;
+        }
+        let short_id = &compact.short_ids[short_id_cursor];
+        short_id_cursor += 1;
+
+        match by_short_id.iter().find(|(id, _)| id == short_id) {
+            Some((_, tx)) => *slot = Some((*tx).clone()),
+            None => missing.push(short_id_cursor - 1),
+        }
+    }
+
+    if !missing.is_empty() {
+        return ReconstructResult::Missing(missing);
+    }
+
+    let ordered_txs: Vec<StacksTransaction> = txs.into_iter().map(|slot| slot.expect("every slot filled")).collect();
+    ReconstructResult::Block(StacksBlock {
+        header: compact.header.clone(),
+        txs: ordered_txs,
+    })
+}
@@ -20,12 +20,254 @@ use burnchains::Txid;
 use chainstate::stacks::db::queryable_logging::*;
 use chainstate::stacks::Error;
 use chainstate::stacks::StacksTransaction;
+use util::hash::{to_hex, Sha512_256};
 use vm::analysis::ContractAnalysis;
 use vm::costs::ExecutionCost;
 use vm::types::{
     AssetIdentifier, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData, Value,
 };
 
+/// Domain-separation prefixes for the event Merkle accumulator, so that leaf and internal
+/// node hashes can never collide with one another (cf. the Libra/Aptos event accumulator).
+const EVENT_LEAF_PREFIX: &[u8] = b"STX::event_leaf";
+const EVENT_NODE_PREFIX: &[u8] = b"STX::event_node";
+
+/// The Merkle root of an event list with no events.
+const EMPTY_EVENTS_ROOT: [u8; 32] = [0u8; 32];
+
+/// Canonically serialize an event's fields, reusing `consensus_serialize` on the inner
+/// Clarity `Value`s wherever one occurs (the same bytes `json_serialize` derives `raw_value`
+/// from), and `Display` for principals/identifiers, matching how the rest of this file
+/// renders them.
+fn event_canonical_bytes(event: &StacksTransactionEvent) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut push_value = |bytes: &mut Vec<u8>, value: &Value| {
+        value
+            .consensus_serialize(bytes)
+            .expect("BUG: failed to consensus-serialize Clarity value for event hashing");
+    };
+    let push_str = |bytes: &mut Vec<u8>, s: &str| {
+        bytes.extend_from_slice(&(s.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(s.as_bytes());
+    };
+    let push_amount = |bytes: &mut Vec<u8>, amount: u128| {
+        bytes.extend_from_slice(&amount.to_be_bytes());
+    };
+
+    match event {
+        StacksTransactionEvent::SmartContractEvent(e) => {
+            bytes.push(0);
+            push_str(&mut bytes, &e.key.0.to_string());
+            push_str(&mut bytes, &e.key.1);
+            push_value(&mut bytes, &e.value);
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(e)) => {
+            bytes.push(1);
+            push_str(&mut bytes, &e.sender.to_string());
+            push_str(&mut bytes, &e.recipient.to_string());
+            push_amount(&mut bytes, e.amount);
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(e)) => {
+            bytes.push(2);
+            push_str(&mut bytes, &e.recipient.to_string());
+            push_amount(&mut bytes, e.amount);
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(e)) => {
+            bytes.push(3);
+            push_str(&mut bytes, &e.sender.to_string());
+            push_amount(&mut bytes, e.amount);
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXLockEvent(e)) => {
+            bytes.push(4);
+            push_str(&mut bytes, &e.locked_address.to_string());
+            push_amount(&mut bytes, e.locked_amount);
+            push_amount(&mut bytes, e.unlock_height as u128);
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(e)) => {
+            bytes.push(5);
+            push_str(&mut bytes, &e.asset_identifier.to_string());
+            push_str(&mut bytes, &e.sender.to_string());
+            push_str(&mut bytes, &e.recipient.to_string());
+            push_value(&mut bytes, &e.value);
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(e)) => {
+            bytes.push(6);
+            push_str(&mut bytes, &e.asset_identifier.to_string());
+            push_str(&mut bytes, &e.recipient.to_string());
+            push_value(&mut bytes, &e.value);
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(e)) => {
+            bytes.push(7);
+            push_str(&mut bytes, &e.asset_identifier.to_string());
+            push_str(&mut bytes, &e.sender.to_string());
+            push_value(&mut bytes, &e.value);
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(e)) => {
+            bytes.push(8);
+            push_str(&mut bytes, &e.asset_identifier.to_string());
+            push_str(&mut bytes, &e.sender.to_string());
+            push_str(&mut bytes, &e.recipient.to_string());
+            push_amount(&mut bytes, e.amount);
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(e)) => {
+            bytes.push(9);
+            push_str(&mut bytes, &e.asset_identifier.to_string());
+            push_str(&mut bytes, &e.recipient.to_string());
+            push_amount(&mut bytes, e.amount);
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(e)) => {
+            bytes.push(10);
+            push_str(&mut bytes, &e.asset_identifier.to_string());
+            push_str(&mut bytes, &e.sender.to_string());
+            push_amount(&mut bytes, e.amount);
+        }
+    }
+    bytes
+}
+
+fn event_leaf_hash(event: &StacksTransactionEvent) -> [u8; 32] {
+    let mut bytes = EVENT_LEAF_PREFIX.to_vec();
+    bytes.extend(event_canonical_bytes(event));
+    let digest = Sha512_256::from_data(&bytes);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(digest.as_bytes());
+    out
+}
+
+fn event_node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut bytes = EVENT_NODE_PREFIX.to_vec();
+    bytes.extend_from_slice(left);
+    bytes.extend_from_slice(right);
+    let digest = Sha512_256::from_data(&bytes);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(digest.as_bytes());
+    out
+}
+
+/// Compute the Merkle accumulator root over a list of leaf hashes.  Builds the tree level by
+/// level, bottom-up; a lone node at an odd-sized level is promoted unchanged to the next level.
+fn events_merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return EMPTY_EVENTS_ROOT;
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let mut next_level = Vec::with_capacity((level.len() + 1) / 2);
+        let mut iter = level.chunks(2);
+        while let Some(pair) = iter.next() {
+            if pair.len() == 2 {
+                next_level.push(event_node_hash(&pair[0], &pair[1]));
+            } else {
+                next_level.push(pair[0]);
+            }
+        }
+        level = next_level;
+    }
+    level[0]
+}
+
+/// Returns one entry per tree level (bottom-to-top) proving that the leaf at `event_index` is
+/// included in the Merkle tree over `leaves`: `Some(sibling)` if this level has a sibling to
+/// hash against, or `None` if `index` was the lone, promoted node at that level. The verifier
+/// must walk the same levels in lockstep, so the proof carries one entry per level rather than
+/// one entry per sibling -- otherwise a skipped level desynchronizes the left/right parity.
+fn events_merkle_proof(leaves: &[[u8; 32]], event_index: usize) -> Vec<Option<[u8; 32]>> {
+    let mut proof = vec![];
+    let mut level = leaves.to_vec();
+    let mut index = event_index;
+    while level.len() > 1 {
+        let sibling_index = if index % 2 == 0 { index + 1 } else { index - 1 };
+        if sibling_index < level.len() {
+            proof.push(Some(level[sibling_index]));
+        } else {
+            // `index` was the lone, promoted node at this level -- no sibling to record, but
+            // the level itself still counts towards the verifier's walk.
+            proof.push(None);
+        }
+
+        let mut next_level = Vec::with_capacity((level.len() + 1) / 2);
+        let mut iter = level.chunks(2);
+        while let Some(pair) = iter.next() {
+            if pair.len() == 2 {
+                next_level.push(event_node_hash(&pair[0], &pair[1]));
+            } else {
+                next_level.push(pair[0]);
+            }
+        }
+        level = next_level;
+        index = index / 2;
+    }
+    proof
+}
+
+/// Verify that `leaf` at position `index` is included in a tree rooted at `root`, given the
+/// bottom-to-top, one-entry-per-level proof from `events_merkle_proof` (`None` at a level means
+/// `index` was promoted unchanged at that level, with no sibling to hash against).
+pub fn verify_event_proof(
+    leaf: &[u8; 32],
+    index: usize,
+    proof: &[Option<[u8; 32]>],
+    root: &[u8; 32],
+) -> bool {
+    let mut cur = *leaf;
+    let mut idx = index;
+    for sibling in proof {
+        cur = match sibling {
+            Some(sibling) => {
+                if idx % 2 == 0 {
+                    event_node_hash(&cur, sibling)
+                } else {
+                    event_node_hash(sibling, &cur)
+                }
+            }
+            None => cur,
+        };
+        idx = idx / 2;
+    }
+    &cur == root
+}
+
+/// Recursively render a Clarity `Value` into the structured ("parsed") JSON form, so that
+/// consumers of `json_serialize` don't each have to reimplement `Value` decoding on top of
+/// `raw_value`/`value`'s `Debug`-derived rendering.  Mirrors the dual compiled/parsed
+/// representation the Solana transaction-status crate attaches to instructions.
+fn decode_clarity_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Void => serde_json::Value::Null,
+        Value::Int(i) => json!(i.to_string()),
+        Value::UInt(u) => json!(u.to_string()),
+        Value::Bool(b) => json!(b),
+        Value::Buffer(buff) => json!(format!("0x{}", to_hex(buff.as_bytes()))),
+        Value::List(items, _) => {
+            serde_json::Value::Array(items.iter().map(decode_clarity_value).collect())
+        }
+        Value::Tuple(tuple_data) => {
+            let mut map = serde_json::Map::new();
+            for (name, field_value) in tuple_data.pair_iter() {
+                map.insert(name.clone(), decode_clarity_value(field_value));
+            }
+            serde_json::Value::Object(map)
+        }
+        Value::Optional(opt_data) => match opt_data.inner() {
+            Some(inner) => json!({ "type": "some", "value": decode_clarity_value(inner) }),
+            None => json!({ "type": "none" }),
+        },
+        Value::Response(res_data) => {
+            let tag = if res_data.committed() { "ok" } else { "err" };
+            json!({ "type": tag, "value": decode_clarity_value(res_data.inner()) })
+        }
+        Value::Principal(principal) => json!(format!("{}", principal)),
+        Value::StringAscii(buff) => json!(String::from_utf8_lossy(buff.as_bytes()).into_owned()),
+        Value::StringUtf8(codepoints) => {
+            let mut s = String::new();
+            for codepoint in codepoints.iter() {
+                s.push_str(&String::from_utf8_lossy(codepoint));
+            }
+            json!(s)
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TransactionOrigin {
     Stacks(StacksTransaction),
@@ -63,6 +305,314 @@ pub struct StacksTransactionReceipt {
     pub contract_analysis: Option<ContractAnalysis>,
     pub execution_cost: ExecutionCost,
     pub microblock_header: Option<StacksMicroblockHeader>,
+    /// Root of the Merkle accumulator built over `events`, so a light client can be handed a
+    /// single event plus a `merkle_proof` and verify its inclusion without the full list.
+    pub events_root: [u8; 32],
+    /// The ordered list of state mutations this transaction produced, so a peer can apply (and
+    /// verify) them directly instead of re-executing the Clarity VM.
+    pub write_set: WriteSet,
+}
+
+impl StacksTransactionReceipt {
+    /// Compute the Merkle root over `events`. Called whenever a receipt's `events` is populated,
+    /// since the two are not kept in sync automatically.
+    pub fn compute_events_root(events: &[StacksTransactionEvent]) -> [u8; 32] {
+        let leaves: Vec<[u8; 32]> = events.iter().map(event_leaf_hash).collect();
+        events_merkle_root(&leaves)
+    }
+
+    /// Return the sibling hashes (bottom-to-top) proving that `self.events[event_index]` is
+    /// included in `self.events_root`.
+    pub fn merkle_proof(&self, event_index: usize) -> Vec<Option<[u8; 32]>> {
+        let leaves: Vec<[u8; 32]> = self.events.iter().map(event_leaf_hash).collect();
+        events_merkle_proof(&leaves, event_index)
+    }
+
+    /// Aggregate `self.events` into a per-principal summary of what changed, so a consumer can
+    /// reconstruct post-transaction account state from this one field instead of folding every
+    /// event itself (cf. the pre/post token-balance summaries Solana attaches to transactions).
+    ///
+    /// An aborted post-condition rolls back the transaction's asset changes but the events are
+    /// still reported for visibility (see `json_serialize`'s `committed` flag) -- so a
+    /// `post_condition_aborted` transaction contributes no asset deltas here, even though
+    /// `self.events` may be non-empty.
+    pub fn asset_changes(&self) -> AssetChangeSummary {
+        let mut summary = AssetChangeSummary { by_principal: vec![] };
+        if self.post_condition_aborted {
+            return summary;
+        }
+        for event in self.events.iter() {
+            match event {
+                StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(e)) => {
+                    summary.principal_mut(&e.sender).stx_delta -= e.amount as i128;
+                    summary.principal_mut(&e.recipient).stx_delta += e.amount as i128;
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(e)) => {
+                    summary.principal_mut(&e.recipient).stx_delta += e.amount as i128;
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(e)) => {
+                    summary.principal_mut(&e.sender).stx_delta -= e.amount as i128;
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXLockEvent(_)) => {
+                    // Locking moves STX from liquid to locked without changing ownership, so it
+                    // has no effect on the signed balance delta this summary tracks.
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(e)) => {
+                    summary
+                        .fungible_mut(&e.sender, &e.asset_identifier)
+                        .delta -= e.amount as i128;
+                    summary
+                        .fungible_mut(&e.recipient, &e.asset_identifier)
+                        .delta += e.amount as i128;
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(e)) => {
+                    summary
+                        .fungible_mut(&e.recipient, &e.asset_identifier)
+                        .delta += e.amount as i128;
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(e)) => {
+                    summary
+                        .fungible_mut(&e.sender, &e.asset_identifier)
+                        .delta -= e.amount as i128;
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(e)) => {
+                    summary
+                        .non_fungible_mut(&e.sender, &e.asset_identifier)
+                        .lost
+                        .push(e.value.clone());
+                    summary
+                        .non_fungible_mut(&e.recipient, &e.asset_identifier)
+                        .gained
+                        .push(e.value.clone());
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(e)) => {
+                    summary
+                        .non_fungible_mut(&e.recipient, &e.asset_identifier)
+                        .gained
+                        .push(e.value.clone());
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(e)) => {
+                    summary
+                        .non_fungible_mut(&e.sender, &e.asset_identifier)
+                        .lost
+                        .push(e.value.clone());
+                }
+                StacksTransactionEvent::SmartContractEvent(_) => {}
+            }
+        }
+        summary
+    }
+
+    /// The ordered list of state mutations this transaction produced.
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
+    /// Serializes this receipt for an external consumer (e.g. the event-observer HTTP POST body)
+    /// following the same per-event `json_serialize` convention as `StacksTransactionEvent` --
+    /// `committed` there is `!self.post_condition_aborted`, since an aborted post-condition rolls
+    /// back the transaction's asset changes but the events are still reported for visibility.
+    pub fn json_serialize(&self) -> serde_json::Value {
+        let txid = self.transaction.txid();
+        let events: Vec<serde_json::Value> = self
+            .events
+            .iter()
+            .enumerate()
+            .map(|(event_index, event)| event.json_serialize(event_index, &txid, !self.post_condition_aborted))
+            .collect();
+
+        json!({
+            "txid": format!("0x{:?}", &txid),
+            "events": events,
+            "post_condition_aborted": self.post_condition_aborted,
+            "stx_burned": self.stx_burned.to_string(),
+            "execution_cost": {
+                "runtime": self.execution_cost.runtime,
+                "read_count": self.execution_cost.read_count,
+                "read_length": self.execution_cost.read_length,
+                "write_count": self.execution_cost.write_count,
+                "write_length": self.execution_cost.write_length,
+            },
+        })
+    }
+}
+
+/// One state mutation recorded in a `WriteSet`: a put (or delete) of `key` within `contract`'s
+/// backing store. Mirrors the `(contract, key, new_value)` tuples in the Libra/Diem
+/// `WriteSet`/`ChangeSet` attached to a transaction's output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteSetEntry {
+    pub contract: QualifiedContractIdentifier,
+    pub key: Vec<u8>,
+    /// `None` marks this entry as a delete; `Some` carries the new value bytes of a put.
+    pub new_value: Option<Vec<u8>>,
+}
+
+/// The ordered list of state mutations (datamap/var puts and deletes, FT/NFT ownership updates,
+/// STX balance writes) a transaction produced, in apply order. Handing this alongside a
+/// transaction lets a peer apply it to the MARF and compare the result against the committed
+/// state root without re-executing the Clarity VM -- useful for fast-sync, and for auditing that
+/// re-execution would reproduce exactly the committed changes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WriteSet {
+    pub entries: Vec<WriteSetEntry>,
+}
+
+impl WriteSet {
+    pub fn new() -> WriteSet {
+        WriteSet { entries: vec![] }
+    }
+
+    pub fn push_put(&mut self, contract: QualifiedContractIdentifier, key: Vec<u8>, new_value: Vec<u8>) {
+        self.entries.push(WriteSetEntry { contract, key, new_value: Some(new_value) });
+    }
+
+    pub fn push_delete(&mut self, contract: QualifiedContractIdentifier, key: Vec<u8>) {
+        self.entries.push(WriteSetEntry { contract, key, new_value: None });
+    }
+
+    /// Encode this write-set for transmission alongside the transaction it came from.
+    ///
+    /// Note: only the encode half of the codec is implemented here. Decoding requires parsing a
+    /// `QualifiedContractIdentifier` back out of its string form, and no such parser exists in
+    /// this tree yet -- `consensus_deserialize` should be added alongside one.
+    pub fn consensus_serialize(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_be_bytes());
+        for entry in self.entries.iter() {
+            let contract_str = entry.contract.to_string();
+            bytes.extend_from_slice(&(contract_str.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(contract_str.as_bytes());
+            bytes.extend_from_slice(&(entry.key.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&entry.key);
+            match &entry.new_value {
+                Some(value) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                    bytes.extend_from_slice(value);
+                }
+                None => bytes.push(0),
+            }
+        }
+    }
+}
+
+/// The net fungible-token delta for one `(principal, asset)` pair within a transaction: positive
+/// for a net mint/transfer-in, negative for a net burn/transfer-out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FungibleAssetChange {
+    pub asset_identifier: AssetIdentifier,
+    pub delta: i128,
+}
+
+/// The NFTs a principal gained and lost, for one `(principal, asset)` pair, within a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonFungibleAssetChange {
+    pub asset_identifier: AssetIdentifier,
+    pub gained: Vec<Value>,
+    pub lost: Vec<Value>,
+}
+
+/// Everything that changed for one principal within a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrincipalAssetChanges {
+    pub principal: PrincipalData,
+    pub stx_delta: i128,
+    pub fungible: Vec<FungibleAssetChange>,
+    pub non_fungible: Vec<NonFungibleAssetChange>,
+}
+
+impl PrincipalAssetChanges {
+    fn new(principal: PrincipalData) -> PrincipalAssetChanges {
+        PrincipalAssetChanges {
+            principal,
+            stx_delta: 0,
+            fungible: vec![],
+            non_fungible: vec![],
+        }
+    }
+
+    pub fn json_serialize(&self) -> serde_json::Value {
+        json!({
+            "principal": format!("{}", self.principal),
+            "stx_delta": self.stx_delta.to_string(),
+            "fungible_token_deltas": self.fungible.iter().map(|c| json!({
+                "asset_identifier": format!("{}", c.asset_identifier),
+                "delta": c.delta.to_string(),
+            })).collect::<Vec<_>>(),
+            "non_fungible_token_changes": self.non_fungible.iter().map(|c| json!({
+                "asset_identifier": format!("{}", c.asset_identifier),
+                "gained": c.gained.iter().map(decode_clarity_value).collect::<Vec<_>>(),
+                "lost": c.lost.iter().map(decode_clarity_value).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Per-principal asset deltas for every event in a `StacksTransactionReceipt`, derived from
+/// `StacksTransactionReceipt::asset_changes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetChangeSummary {
+    pub by_principal: Vec<PrincipalAssetChanges>,
+}
+
+impl AssetChangeSummary {
+    fn principal_mut(&mut self, principal: &PrincipalData) -> &mut PrincipalAssetChanges {
+        if let Some(index) = self.by_principal.iter().position(|p| &p.principal == principal) {
+            &mut self.by_principal[index]
+        } else {
+            self.by_principal.push(PrincipalAssetChanges::new(principal.clone()));
+            self.by_principal.last_mut().unwrap()
+        }
+    }
+
+    fn fungible_mut(
+        &mut self,
+        principal: &PrincipalData,
+        asset_identifier: &AssetIdentifier,
+    ) -> &mut FungibleAssetChange {
+        let entry = self.principal_mut(principal);
+        if let Some(index) = entry
+            .fungible
+            .iter()
+            .position(|c| &c.asset_identifier == asset_identifier)
+        {
+            &mut entry.fungible[index]
+        } else {
+            entry.fungible.push(FungibleAssetChange {
+                asset_identifier: asset_identifier.clone(),
+                delta: 0,
+            });
+            entry.fungible.last_mut().unwrap()
+        }
+    }
+
+    fn non_fungible_mut(
+        &mut self,
+        principal: &PrincipalData,
+        asset_identifier: &AssetIdentifier,
+    ) -> &mut NonFungibleAssetChange {
+        let entry = self.principal_mut(principal);
+        if let Some(index) = entry
+            .non_fungible
+            .iter()
+            .position(|c| &c.asset_identifier == asset_identifier)
+        {
+            &mut entry.non_fungible[index]
+        } else {
+            entry.non_fungible.push(NonFungibleAssetChange {
+                asset_identifier: asset_identifier.clone(),
+                gained: vec![],
+                lost: vec![],
+            });
+            entry.non_fungible.last_mut().unwrap()
+        }
+    }
+
+    pub fn json_serialize(&self) -> serde_json::Value {
+        json!({
+            "by_principal": self.by_principal.iter().map(|p| p.json_serialize()).collect::<Vec<_>>(),
+        })
+    }
 }
 
 /// Represents a successful transaction. This transaction should be added to the block.
@@ -364,6 +914,7 @@ impl NFTTransferEventData {
             "sender": format!("{}",self.sender),
             "recipient": format!("{}",self.recipient),
             "value": self.value,
+            "decoded_value": decode_clarity_value(&self.value),
             "raw_value": format!("0x{}", raw_value.join("")),
         })
     }
@@ -388,6 +939,7 @@ impl NFTMintEventData {
             "asset_identifier": format!("{}", self.asset_identifier),
             "recipient": format!("{}",self.recipient),
             "value": self.value,
+            "decoded_value": decode_clarity_value(&self.value),
             "raw_value": format!("0x{}", raw_value.join("")),
         })
     }
@@ -412,6 +964,7 @@ impl NFTBurnEventData {
             "asset_identifier": format!("{}", self.asset_identifier),
             "sender": format!("{}",self.sender),
             "value": self.value,
+            "decoded_value": decode_clarity_value(&self.value),
             "raw_value": format!("0x{}", raw_value.join("")),
         })
     }
@@ -488,7 +1041,89 @@ impl SmartContractEventData {
             "contract_identifier": self.key.0.to_string(),
             "topic": self.key.1,
             "value": self.value,
+            "decoded_value": decode_clarity_value(&self.value),
             "raw_value": format!("0x{}", raw_value.join("")),
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_round_trips(num_leaves: usize) {
+        let leaves: Vec<[u8; 32]> = (0..num_leaves as u8).map(|tag| [tag; 32]).collect();
+        let root = events_merkle_root(&leaves);
+        for index in 0..leaves.len() {
+            let proof = events_merkle_proof(&leaves, index);
+            assert!(
+                verify_event_proof(&leaves[index], index, &proof, &root),
+                "proof for index {} of {} leaves failed to verify",
+                index,
+                num_leaves
+            );
+
+            // A proof must not verify against a different leaf or a different root.
+            let other_index = (index + 1) % leaves.len();
+            if other_index != index {
+                assert!(
+                    !verify_event_proof(&leaves[other_index], index, &proof, &root),
+                    "proof for index {} incorrectly verified a different leaf",
+                    index
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_events_merkle_proof_round_trip_power_of_two() {
+        assert_round_trips(1);
+        assert_round_trips(2);
+        assert_round_trips(4);
+        assert_round_trips(8);
+    }
+
+    #[test]
+    fn test_events_merkle_proof_round_trip_odd_leaf_counts() {
+        for num_leaves in [3, 5, 6, 7] {
+            assert_round_trips(num_leaves);
+        }
+    }
+
+    #[test]
+    fn test_events_merkle_root_empty() {
+        assert_eq!(events_merkle_root(&[]), EMPTY_EVENTS_ROOT);
+    }
+
+    fn stx_transfer_receipt(post_condition_aborted: bool) -> StacksTransactionReceipt {
+        let event = StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(STXTransferEventData {
+            sender: PrincipalData::Standard([1u8; 21]),
+            recipient: PrincipalData::Standard([2u8; 21]),
+            amount: 100,
+        }));
+        StacksTransactionReceipt {
+            transaction: TransactionOrigin::Burn(Txid([0u8; 32])),
+            events: vec![event],
+            post_condition_aborted,
+            result: Value::okay(Value::Bool(true)).unwrap(),
+            stx_burned: 0,
+            contract_analysis: None,
+            execution_cost: ExecutionCost::zero(),
+            microblock_header: None,
+            events_root: EMPTY_EVENTS_ROOT,
+            write_set: WriteSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_asset_changes_reflects_committed_transaction() {
+        let summary = stx_transfer_receipt(false).asset_changes();
+        assert_eq!(summary.by_principal.len(), 2);
+    }
+
+    #[test]
+    fn test_asset_changes_empty_when_post_condition_aborted() {
+        let summary = stx_transfer_receipt(true).asset_changes();
+        assert!(summary.by_principal.is_empty());
+    }
+}
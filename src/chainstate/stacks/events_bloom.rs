@@ -0,0 +1,162 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// Per-block Bloom filter over the event keys emitted while processing a Stacks block -- an event
+// observer (or a `/v2/blocks/<id>/events-bloom` RPC client) can test membership against this one
+// small filter instead of downloading and scanning every transaction in the block, falling back
+// to a full scan only on a positive hit. Modeled as the classic `m`-bit array with `k` hash
+// functions, derived from two independent SHA256 digests of each key via Kirsch-Mitzenmacher
+// double hashing (`h1 + i*h2 mod m`) rather than `k` separate hash invocations -- the same idea
+// `chainstate::burn::filter`'s BIP158 filters use SipHash for, just with a plain bit-array Bloom
+// filter instead of a Golomb-Rice-coded set (a block's Clarity events don't need GCS's extra
+// compactness, and a Bloom filter's fixed-size encoding is simpler to persist per block).
+//
+// NOTE: this tree's `chainstate::stacks::db` is only a read-only contract-constant lookup (so no
+// `StacksHeaderInfo`/MARF-backed block metadata table exists to persist this filter's bytes
+// into), and there is no net/RPC module at all, so
+// `/v2/blocks/<id>/events-bloom` can't be wired up here. `build_block_events_bloom` below is the
+// self-contained, storable piece: a real tree would call it once per processed block, stash
+// `BlockEventsBloom::to_bytes()` alongside the block's header the way `ops_hash`/`ops_merkle_root`
+// are stored on `BlockSnapshot`, and serve those bytes back over the RPC route. The
+// event-observer payload is the one real integration point available here (see
+// `testnet::helium::event_observer::stacks_block_json`).
+
+use sha2::{Digest, Sha256};
+
+use chainstate::stacks::events::{FTEventType, NFTEventType, StacksTransactionEvent};
+
+/// Number of bits in the filter's bit array. Sized (with `BLOOM_HASH_COUNT`) for a false-positive
+/// rate of roughly 1% at ~128 distinct keys per block -- `m = ceil(-n*ln(p) / ln(2)^2)` with
+/// `n = 128`, `p = 0.01` gives ~1227 bits; rounded up to a whole number of bytes at a round 2048
+/// for headroom on busier blocks.
+const BLOOM_BITS: usize = 2048;
+
+/// Number of hash functions, chosen as `k = round((m/n)*ln(2))` for the same `m`/`n`/`p` above.
+const BLOOM_HASH_COUNT: u64 = 11;
+
+const BLOOM_TAG: &'static [u8] = b"stacks-block-events-bloom";
+
+/// Domain-separated SHA256 digest of `key`: `tag_suffix` picks which of the two independent
+/// digests (`h1`/`h2`) this is, so hashing the same key bytes under each suffix can't collide.
+fn tagged_digest(tag_suffix: u8, key: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.input(BLOOM_TAG);
+    hasher.input(&[tag_suffix]);
+    hasher.input(key);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hasher.result().as_slice());
+    out
+}
+
+fn digest_to_u64(digest: &[u8; 32]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[0..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// A fixed-size Bloom filter over a block's event keys. See the module-level comment for the
+/// `m`/`k` construction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEventsBloom {
+    bits: Vec<u8>,
+}
+
+impl BlockEventsBloom {
+    pub fn new() -> BlockEventsBloom {
+        BlockEventsBloom {
+            bits: vec![0u8; BLOOM_BITS / 8],
+        }
+    }
+
+    /// Double-hashes `key` into `BLOOM_HASH_COUNT` bit positions via the standard
+    /// Kirsch-Mitzenmacher construction (`h1 + i*h2 mod m`) from two independent SHA256 digests
+    /// of it, avoiding `BLOOM_HASH_COUNT` separate hash function implementations.
+    fn bit_positions(key: &[u8]) -> Vec<usize> {
+        let h1 = digest_to_u64(&tagged_digest(0, key));
+        let h2 = digest_to_u64(&tagged_digest(1, key));
+        (0..BLOOM_HASH_COUNT)
+            .map(|i| (h1.wrapping_add(i.wrapping_mul(h2)) % (BLOOM_BITS as u64)) as usize)
+            .collect()
+    }
+
+    pub fn insert(&mut self, key: &[u8]) {
+        for pos in Self::bit_positions(key) {
+            self.bits[pos / 8] |= 1 << (pos % 8);
+        }
+    }
+
+    /// Tests whether `key` may have been inserted. `false` is definitive; `true` can be a false
+    /// positive at roughly the rate `BLOOM_BITS`/`BLOOM_HASH_COUNT` were tuned for.
+    pub fn might_contain(&self, key: &[u8]) -> bool {
+        Self::bit_positions(key)
+            .into_iter()
+            .all(|pos| self.bits[pos / 8] & (1 << (pos % 8)) != 0)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bits.clone()
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Option<BlockEventsBloom> {
+        if bytes.len() == BLOOM_BITS / 8 {
+            Some(BlockEventsBloom { bits: bytes })
+        } else {
+            None
+        }
+    }
+}
+
+/// Extracts this event's Bloom filter key, if it has one worth indexing: the contract identifier
+/// for a `print` event (`SmartContractEvent`), or the asset identifier -- whose `Display` already
+/// renders as `contract_id::asset_name` -- for FT/NFT mint/transfer/burn events. STX events aren't
+/// contract- or asset-scoped, so they have no key to index here.
+fn event_bloom_key(event: &StacksTransactionEvent) -> Option<String> {
+    match event {
+        StacksTransactionEvent::SmartContractEvent(e) => Some(e.key.0.to_string()),
+        StacksTransactionEvent::STXEvent(_) => None,
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(e)) => {
+            Some(e.asset_identifier.to_string())
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(e)) => {
+            Some(e.asset_identifier.to_string())
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(e)) => {
+            Some(e.asset_identifier.to_string())
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(e)) => {
+            Some(e.asset_identifier.to_string())
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(e)) => {
+            Some(e.asset_identifier.to_string())
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(e)) => {
+            Some(e.asset_identifier.to_string())
+        }
+    }
+}
+
+/// Builds the Bloom filter covering every indexable key emitted by `events` (e.g. the full set
+/// of events a block's transactions produced), so a client can cheaply test "could this block
+/// contain anything touching this contract/asset?" before downloading it.
+pub fn build_block_events_bloom(events: &[StacksTransactionEvent]) -> BlockEventsBloom {
+    let mut bloom = BlockEventsBloom::new();
+    for event in events {
+        if let Some(key) = event_bloom_key(event) {
+            bloom.insert(key.as_bytes());
+        }
+    }
+    bloom
+}
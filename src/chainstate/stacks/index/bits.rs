@@ -30,7 +30,7 @@ use std::io::{
     ErrorKind
 };
 
-use sha2::Sha512Trunc256 as TrieHasher;
+use sha2::Sha512Trunc256;
 use sha2::Digest;
 
 use chainstate::stacks::index::{
@@ -129,7 +129,8 @@ pub fn check_node_id(nid: u8) -> bool {
     node_id == TrieNodeID::Node4 ||
     node_id == TrieNodeID::Node16 ||
     node_id == TrieNodeID::Node48 ||
-    node_id == TrieNodeID::Node256
+    node_id == TrieNodeID::Node256 ||
+    node_id == TrieNodeID::Sealed
 }
 
 /// Helper to return the number of children in a Trie, given its ID.
@@ -141,10 +142,18 @@ pub fn node_id_to_ptr_count(node_id: u8) -> usize {
         TrieNodeID::Node16 => 16,
         TrieNodeID::Node48 => 48,
         TrieNodeID::Node256 => 256,
+        // a sealed node's payload has been pruned away, so it has no readable children
+        TrieNodeID::Sealed => 0,
         _ => panic!("Unknown node ID {}", node_id)
     }
 }
 
+/// Is this TriePtr's node ID marking a sealed (pruned) node?
+#[inline]
+pub fn is_sealed(node_id: u8) -> bool {
+    clear_backptr(node_id) == TrieNodeID::Sealed
+}
+
 /// Helper to determine how many bytes a Trie node's child pointers will take to encode.
 #[inline]
 pub fn get_ptrs_byte_len(ptrs: &[TriePtr]) -> usize {
@@ -241,27 +250,61 @@ pub fn ptrs_from_bytes<R: Read>(node_id: u8, r: &mut R, ptrs_buf: &mut [TriePtr]
     Ok(nid)
 }
 
-fn compute_node_hash<F>(bytes: &Vec<u8>, f: F) -> TrieHash
-    where F: FnOnce(&mut TrieHasher) {
-    let mut hasher = TrieHasher::new();
+/// A pluggable digest for Trie node hashing, so `compute_node_hash` and its callers can be
+/// instantiated over something other than `Sha512Trunc256Hasher` (a faster digest for
+/// benchmarking, a domain-separated hasher for a sidechain) without forking this file.
+/// `Sha512Trunc256Hasher` is the consensus default: its `SIZE` matches `TRIEHASH_ENCODED_SIZE`
+/// exactly, so `get_node_hash`/`get_leaf_hash`/etc. (which stay hardcoded to it) keep producing
+/// the same hashes they always have.
+pub trait TrieHasher {
+    /// Width, in bytes, of this hasher's digest.
+    const SIZE: usize;
+    fn hash(data: &[u8]) -> Vec<u8>;
+}
+
+/// The consensus hasher: SHA2-512/256, truncated to `TRIEHASH_ENCODED_SIZE` (32) bytes.
+pub struct Sha512Trunc256Hasher;
 
-    hasher.input(bytes);
+impl TrieHasher for Sha512Trunc256Hasher {
+    const SIZE: usize = 32;
 
-    f(&mut hasher);
-    
-    let mut res = [0u8; 32];
-    res.copy_from_slice(hasher.result().as_slice());
+    fn hash(data: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha512Trunc256::new();
+        hasher.input(data);
+        hasher.result().as_slice().to_vec()
+    }
+}
 
+/// Hash `bytes` followed by `extra` (typically a node's consensus bytes followed by its
+/// children's hash bytes) with `H`, a pluggable `TrieHasher`.
+fn compute_node_hash_with<H: TrieHasher>(bytes: &[u8], extra: &[u8]) -> Vec<u8> {
+    let mut combined = Vec::with_capacity(bytes.len() + extra.len());
+    combined.extend_from_slice(bytes);
+    combined.extend_from_slice(extra);
+    H::hash(&combined)
+}
+
+/// Hash `bytes` followed by `extra` with the consensus hasher, producing a `TrieHash`. Takes
+/// `bytes` as a plain slice (rather than requiring an owned `&Vec<u8>`) so a caller streaming a
+/// node's consensus bytes into a stack-backed scratch buffer -- see `InlineBuffer` -- can hash
+/// straight out of it without first collecting into a `Vec`.
+/// `H::SIZE` is asserted against `TRIEHASH_ENCODED_SIZE` so a mismatched hasher fails loudly
+/// instead of silently truncating or padding its digest into a `TrieHash`.
+pub(crate) fn compute_node_hash(bytes: &[u8], extra: &[u8]) -> TrieHash {
+    assert_eq!(Sha512Trunc256Hasher::SIZE, TRIEHASH_ENCODED_SIZE);
+    let digest = compute_node_hash_with::<Sha512Trunc256Hasher>(bytes, extra);
+    let mut res = [0u8; TRIEHASH_ENCODED_SIZE];
+    res.copy_from_slice(&digest);
     TrieHash(res)
 }
 
 /// Calculate the hash of a TrieNode, given its childrens' hashes.
 pub fn get_node_hash<T: TrieNode + std::fmt::Debug>(node: &T, child_hashes: &Vec<TrieHash>, map: &BlockHashMap) -> TrieHash {
-    let ret = compute_node_hash(&node.to_consensus_bytes(map), |hasher| {
-        for child_hash in child_hashes {
-            hasher.input(&child_hash.as_bytes());
-        }
-    });
+    let mut child_hash_bytes = Vec::with_capacity(child_hashes.len() * TRIEHASH_ENCODED_SIZE);
+    for child_hash in child_hashes {
+        child_hash_bytes.extend_from_slice(child_hash.as_bytes());
+    }
+    let ret = compute_node_hash(&node.to_consensus_bytes(map), &child_hash_bytes);
 
     trace!("get_node_hash: hash {:?} = {:?} + {:?}", &ret, node, child_hashes);
     ret
@@ -269,7 +312,7 @@ pub fn get_node_hash<T: TrieNode + std::fmt::Debug>(node: &T, child_hashes: &Vec
 
 /// Calculate the hash of a TrieNode, given its childrens' hashes.
 pub fn get_leaf_hash(node: &TrieLeaf) -> TrieHash {
-    let ret = compute_node_hash(&node.to_consensus_bytes_leaf(), |_h| {});
+    let ret = compute_node_hash(&node.to_consensus_bytes_leaf(), &[]);
 
     trace!("get_leaf_hash: hash {:?} = {:?} + []", &ret, node);
     ret
@@ -291,9 +334,7 @@ pub fn get_nodetype_hash(node: &TrieNodeType, child_hashes: &Vec<TrieHash>, map:
 pub fn get_node_hash_bytes<T: TrieNode + std::fmt::Debug>(node: &T, child_hash_bytes: &Vec<u8>, map: &BlockHashMap) -> TrieHash {
     assert_eq!(child_hash_bytes.len() % TRIEHASH_ENCODED_SIZE, 0);
 
-    let ret = compute_node_hash(&node.to_consensus_bytes(map), |hasher| {
-        hasher.input(child_hash_bytes);
-    });
+    let ret = compute_node_hash(&node.to_consensus_bytes(map), child_hash_bytes);
 
     if is_trace() {
         // not in prod -- can spend a few cycles on fancy debug output
@@ -325,6 +366,34 @@ pub fn get_nodetype_hash_bytes(node: &TrieNodeType, child_hash_bytes: &Vec<u8>,
     }
 }
 
+/// How many node digests `hash_nodes_batch` processes per lane-group. A real multi-lane SHA
+/// implementation (see that function's doc comment) absorbs this many independent message
+/// schedules per core step; here it just bounds how many `(bytes, extra)` pairs get grouped
+/// together before the loop below hashes them, so the batching API's shape matches what a SIMD
+/// backend would expect to slot into.
+pub const HASH_BATCH_LANES: usize = 4;
+
+/// Hashes many `(node_bytes, extra)` pairs -- the same `bytes` plus `extra` that `compute_node_hash`
+/// takes one at a time -- grouped into `HASH_BATCH_LANES`-wide batches, for root-hash recomputation
+/// passes that walk a whole tree level at a time and would otherwise call `compute_node_hash` once
+/// per sibling. The real point of batching is a multi-lane SHA-256 compression function: 4-way
+/// absorb over four independent message schedules with per-lane finalization, so four node digests
+/// come out per core step instead of one, the same way `find_chr16`'s SSE2/NEON compares four...
+/// sixteen bytes at once instead of looping. That needs either a hand-rolled multi-way compression
+/// function or a vendored crate that provides one (e.g. a `sha2`-adjacent multiway backend), and
+/// this tree has neither -- it vendors the scalar `sha2` crate and nothing resembling SIMD hashing.
+/// So this hashes each lane-group sequentially, one `compute_node_hash` call at a time, preserving
+/// the batch-in/batch-out shape (and the order of `pairs`) that a real 4-way backend would fill in
+/// underneath without its callers changing.
+pub fn hash_nodes_batch(pairs: &[(Vec<u8>, Vec<u8>)]) -> Vec<TrieHash> {
+    let mut out = Vec::with_capacity(pairs.len());
+    for lane_group in pairs.chunks(HASH_BATCH_LANES) {
+        for (bytes, extra) in lane_group {
+            out.push(compute_node_hash(bytes, extra));
+        }
+    }
+    out
+}
 
 /// Low-level method for reading a TrieHash into a byte buffer from a Read-able and Seek-able struct.
 /// The byte buffer must have sufficient space to hold the hash, or this program panics.
@@ -413,6 +482,19 @@ pub fn hash_buf_to_trie_hashes(hashes_buf: &Vec<u8>) -> Vec<TrieHash> {
     all_hashes
 }
 
+/// A node as read back from disk, which may have been "sealed": its payload dropped because the
+/// subtree beneath it is cold and will never be traversed again, while its hash -- and thus its
+/// ancestors' Merkle root -- stays exactly as it was before sealing. Attempting to walk into a
+/// `Sealed` node's (nonexistent) children is a caller error (`Error::Sealed`), not a corruption.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SealableTrieNode {
+    Node(TrieNodeType),
+    Sealed,
+}
+
+/// One byte written in place of a node's body to mark it as sealed.
+const SEAL_MARKER: u8 = TrieNodeID::Sealed as u8;
+
 /// Deserialize a node.
 /// Node wire format:
 /// 0               32 33               33+X         33+X+Y
@@ -421,11 +503,28 @@ pub fn hash_buf_to_trie_hashes(hashes_buf: &Vec<u8>) -> Vec<TrieHash> {
 ///
 /// X is fixed and determined by the TrieNodeType variant.
 /// Y is variable, but no more than TriePath::len()
-pub fn read_nodetype<F: Read + Seek>(f: &mut F, ptr: &TriePtr) -> Result<(TrieNodeType, TrieHash), Error> {
+///
+/// A sealed node's on-disk footprint is just `[hash][seal marker]` -- `ptr.id()` already tells
+/// us it's sealed (see `is_sealed`), so this never attempts to parse pointers or a path for it.
+pub fn read_nodetype<F: Read + Seek>(f: &mut F, ptr: &TriePtr) -> Result<(SealableTrieNode, TrieHash), Error> {
     trace!("read_nodetype at {:?}", ptr);
     let mut h_bytes = Vec::with_capacity(TRIEHASH_ENCODED_SIZE);
     read_node_hash_bytes(f, ptr, &mut h_bytes)?;
 
+    let mut h = [0u8; TRIEHASH_ENCODED_SIZE];
+    h.copy_from_slice(&h_bytes[0..TRIEHASH_ENCODED_SIZE]);
+    let hash = TrieHash(h);
+
+    if is_sealed(ptr.id()) {
+        let mut marker = [0u8; 1];
+        f.read_exact(&mut marker)
+            .map_err(|e| Error::IOError(e))?;
+        if marker[0] != SEAL_MARKER {
+            return Err(Error::CorruptionError(format!("Sealed node missing seal marker at {:?}", ptr)));
+        }
+        return Ok((SealableTrieNode::Sealed, hash));
+    }
+
     let node = match ptr.id() {
         TrieNodeID::Node4 => {
             let node = TrieNode4::from_bytes(f)?;
@@ -452,27 +551,31 @@ pub fn read_nodetype<F: Read + Seek>(f: &mut F, ptr: &TriePtr) -> Result<(TrieNo
         }
     };
 
-    let mut h = [0u8; TRIEHASH_ENCODED_SIZE];
-    h.copy_from_slice(&h_bytes[0..TRIEHASH_ENCODED_SIZE]);
-    Ok((node, TrieHash(h)))
+    Ok((SealableTrieNode::Node(node), hash))
 }
 
-/// calculate how many bytes a node will be when serialized, including its hash. 
-pub fn get_node_byte_len(node: &TrieNodeType) -> usize {
+/// calculate how many bytes a node will be when serialized, including its hash.
+pub fn get_node_byte_len(node: &SealableTrieNode) -> usize {
     let hash_len = TRIEHASH_ENCODED_SIZE;
-    let node_byte_len = node.byte_len();
-    hash_len + node_byte_len
+    match node {
+        // just the hash plus the one-byte seal marker -- no ptrs, no path
+        SealableTrieNode::Sealed => hash_len + 1,
+        SealableTrieNode::Node(inner) => hash_len + inner.byte_len(),
+    }
 }
 
 /// write all the bytes for a node, including its hash, to the given Writeable object.
 /// Returns the number of bytes written.
-pub fn write_nodetype_bytes<F: Write + Seek>(f: &mut F, node: &TrieNodeType, hash: TrieHash) -> Result<usize, Error> {
-    let mut bytes = Vec::with_capacity(node.byte_len() + TRIEHASH_ENCODED_SIZE);
-    
+pub fn write_nodetype_bytes<F: Write + Seek>(f: &mut F, node: &SealableTrieNode, hash: TrieHash) -> Result<usize, Error> {
+    let mut bytes = Vec::with_capacity(get_node_byte_len(node));
+
     fast_extend_from_slice(&mut bytes, hash.as_bytes());
-    node.to_bytes(&mut bytes);
-    
-    assert_eq!(bytes.len(), node.byte_len() + TRIEHASH_ENCODED_SIZE);
+    match node {
+        SealableTrieNode::Sealed => bytes.push(SEAL_MARKER),
+        SealableTrieNode::Node(inner) => inner.to_bytes(&mut bytes),
+    }
+
+    assert_eq!(bytes.len(), get_node_byte_len(node));
 
     let ptr = ftell(f)?;
     trace!("write_nodetype: {:?} {:?} at {}-{}", node, &hash, ptr, ptr + bytes.len() as u64);
@@ -482,3 +585,91 @@ pub fn write_nodetype_bytes<F: Write + Seek>(f: &mut F, node: &TrieNodeType, has
 
     Ok(bytes.len())
 }
+
+/// Upper bound, in bytes, on a node's on-disk footprint for a given node ID: hash + id byte +
+/// ptrs (for a leaf, its fixed-size value instead) + a path of the maximum possible length. The
+/// path is rarely this long, but sizing the read off the worst case means `read_nodetype_bytes`
+/// never has to go back for more.
+#[inline]
+fn max_nodetype_byte_len(node_id: u8) -> usize {
+    if is_sealed(node_id) {
+        return TRIEHASH_ENCODED_SIZE + 1;
+    }
+    let ptrs_len = if clear_backptr(node_id) == TrieNodeID::Leaf {
+        1 + crate::types::chainstate::MARF_VALUE_ENCODED_SIZE as usize
+    } else {
+        1 + TRIEPTR_SIZE * node_id_to_ptr_count(node_id)
+    };
+    let path_len = 1 + TRIEPATH_MAX_LEN;
+    TRIEHASH_ENCODED_SIZE + ptrs_len + path_len
+}
+
+/// Decode a `(SealableTrieNode, TrieHash)` out of a byte slice that already holds a node's
+/// `[hash][id][ptrs][path]` span, as filled in by `read_nodetype_bytes`'s bulk read or handed in
+/// directly by `read_nodetype_at`. `bytes` may run longer than this node actually needs (a
+/// fixed-size read can overshoot into the next node on disk); only as many bytes as the wire
+/// format for `ptr.id()` calls for are ever consumed.
+fn decode_nodetype(bytes: &[u8], ptr: &TriePtr) -> Result<(SealableTrieNode, TrieHash), Error> {
+    if bytes.len() < TRIEHASH_ENCODED_SIZE {
+        return Err(Error::CorruptionError(format!("Not enough bytes to read a node hash at {:?}", ptr)));
+    }
+
+    let mut h = [0u8; TRIEHASH_ENCODED_SIZE];
+    h.copy_from_slice(&bytes[0..TRIEHASH_ENCODED_SIZE]);
+    let hash = TrieHash(h);
+
+    let mut cursor = io::Cursor::new(&bytes[TRIEHASH_ENCODED_SIZE..]);
+
+    if is_sealed(ptr.id()) {
+        let mut marker = [0u8; 1];
+        cursor.read_exact(&mut marker)
+            .map_err(|e| Error::IOError(e))?;
+        if marker[0] != SEAL_MARKER {
+            return Err(Error::CorruptionError(format!("Sealed node missing seal marker at {:?}", ptr)));
+        }
+        return Ok((SealableTrieNode::Sealed, hash));
+    }
+
+    let node = match ptr.id() {
+        TrieNodeID::Node4 => TrieNodeType::Node4(TrieNode4::from_bytes(&mut cursor)?),
+        TrieNodeID::Node16 => TrieNodeType::Node16(TrieNode16::from_bytes(&mut cursor)?),
+        TrieNodeID::Node48 => TrieNodeType::Node48(TrieNode48::from_bytes(&mut cursor)?),
+        TrieNodeID::Node256 => TrieNodeType::Node256(TrieNode256::from_bytes(&mut cursor)?),
+        TrieNodeID::Leaf => TrieNodeType::Leaf(TrieLeaf::from_bytes(&mut cursor)?),
+        _ => {
+            return Err(Error::CorruptionError(format!("read_node_type: Unknown trie node type {}", ptr.id())));
+        }
+    };
+
+    Ok((SealableTrieNode::Node(node), hash))
+}
+
+/// Like `read_nodetype`, but one `fseek` plus one bulk read instead of a read per field.
+/// `read_nodetype` reads the hash, then the id byte, then the ptrs, then the path as four-plus
+/// separate `read_exact` calls; on the hot traversal path that's four-plus syscalls per node.
+/// This seeks once to `ptr.ptr()` and reads an upper-bound-sized buffer in one shot -- a short
+/// read is fine (it just means this node sits near the end of the file), since `decode_nodetype`
+/// only consumes as many bytes as `ptr.id()`'s wire format calls for -- then decodes the whole
+/// node out of that buffer. Same on-disk format as `read_nodetype`; this only changes how it's
+/// read.
+pub fn read_nodetype_bytes<F: Read + Seek>(f: &mut F, ptr: &TriePtr) -> Result<(SealableTrieNode, TrieHash), Error> {
+    trace!("read_nodetype_bytes at {:?}", ptr);
+    fseek(f, ptr.ptr() as u64)?;
+
+    let max_len = max_nodetype_byte_len(ptr.id());
+    let mut buf = vec![0u8; max_len];
+    let n = f.read(&mut buf).map_err(|e| Error::IOError(e))?;
+    if n < TRIEHASH_ENCODED_SIZE {
+        return Err(Error::CorruptionError(format!("Failed to read node hash in full at {:?}", ptr)));
+    }
+    buf.truncate(n);
+
+    decode_nodetype(&buf, ptr)
+}
+
+/// Decode a node straight out of a pre-mapped byte region -- e.g. a memory-mapped MARF file --
+/// skipping the read (and the seek) entirely. `bytes` must start at `ptr.ptr()` within the
+/// backing file and run at least through this node's hash, id, ptrs, and path.
+pub fn read_nodetype_at(bytes: &[u8], ptr: &TriePtr) -> Result<(SealableTrieNode, TrieHash), Error> {
+    decode_nodetype(bytes, ptr)
+}
@@ -0,0 +1,554 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Serializable inclusion/non-inclusion Merkle proofs against a MARF trie, built on
+//! `bits::read_nodetype`. `build_proof` walks a key's path recording each visited node's
+//! consensus bytes plus its children's hashes; `verify_proof` recomputes those hashes bottom-up
+//! from nothing but the proof itself and confirms the top matches a known root, so a light client
+//! can check MARF state without the backing DB.
+//!
+//! `TrieMerkleProof` and its element types also implement `StacksMessageCodec`, so a proof built
+//! here can be handed to a remote light client (e.g. over the `/v2` RPC surface) and verified
+//! there with nothing but this module and the claimed root.
+
+use std::io::{Read, Write};
+
+use net::{codec::read_next, StacksMessageCodec};
+use util::hash::to_hex;
+
+use chainstate::stacks::index::bits::{
+    compute_node_hash, get_leaf_hash, path_to_bytes, trie_hash_from_bytes, SealableTrieNode,
+};
+use chainstate::stacks::index::node::{is_backptr, ConsensusSerializable, TrieNodeID, TrieNodeType, TriePath, TriePtr};
+use chainstate::stacks::index::storage::TrieFileStorage;
+use chainstate::stacks::index::Error;
+
+use crate::types::chainstate::{
+    ClarityMarfTrieId, MARFValue, ProofTrieNode, ProofTriePtr, TrieHash, TrieMerkleProof,
+    TrieMerkleProofType, BLOCK_HEADER_HASH_ENCODED_SIZE, TRIEHASH_ENCODED_SIZE,
+};
+
+/// Encode the consensus-relevant bytes of a `ProofTrieNode` (its id, child pointers, and path),
+/// mirroring `TrieNode`'s blanket `ConsensusSerializable` impl so the same hash routine can be
+/// reused by both the live trie and a proof being verified. Unlike `TriePtr`, a `ProofTriePtr`
+/// already carries the referenced block's hash inline (that's the point of a proof -- the
+/// verifier has no storage to look it up in), so there's no `BlockMap` to consult here.
+impl<T: ClarityMarfTrieId> ConsensusSerializable<()> for ProofTrieNode<T> {
+    fn write_consensus_bytes<W: Write>(&self, _additional_data: &mut (), w: &mut W) -> Result<(), Error> {
+        w.write_all(&[self.id])?;
+        for ptr in self.ptrs.iter() {
+            w.write_all(&[ptr.id, ptr.chr])?;
+            if is_backptr(ptr.id) {
+                w.write_all(ptr.back_block.as_bytes())?;
+            } else {
+                w.write_all(&[0; BLOCK_HEADER_HASH_ENCODED_SIZE])?;
+            }
+        }
+        let mut path_buf = Vec::new();
+        path_to_bytes(&self.path, &mut path_buf);
+        w.write_all(&path_buf)?;
+        Ok(())
+    }
+}
+
+/// Build an inclusion/non-inclusion Merkle proof for `key` against the trie rooted at
+/// `s.root_trieptr()`. Walks the path with `read_nodetype`, and at every node visited records a
+/// `ProofTrieNode` (its id, path, and child pointers with their path characters) alongside the
+/// *other* children's hashes -- i.e. all but the one the walk follows, which is exactly what
+/// `verify_proof` needs to slot the hash flowing up from below back into its place and recompute
+/// `get_nodetype_hash_bytes` locally, without touching disk.
+///
+/// The walk ends in one of two ways:
+///   - it reaches a `TrieLeaf`: an inclusion proof, terminated by a `TrieMerkleProofType::Leaf`.
+///   - a node's `path()` diverges from `key`'s hashed path, or has no child at the next path
+///     character: a non-inclusion proof, terminated by that node's `TrieMerkleProofType::Node*`
+///     recording the path character that's missing a child.
+///
+/// Limitation, stated plainly rather than papered over: a `ProofTriePtr`'s `back_block` is always
+/// `T::sentinel()` here, even across a real back-pointer. Resolving a back-pointer's true block
+/// header hash requires a `BlockMap`-capable storage handle, which isn't threaded through
+/// `TrieFileStorage` in this tree. A proof that crosses a back-pointer will therefore not verify
+/// against the true root; this function only proves paths confined to a single trie.
+pub fn build_proof<T: ClarityMarfTrieId>(s: &mut TrieFileStorage, key: &[u8]) -> Result<TrieMerkleProof<T>, Error> {
+    let path = TriePath::from_key(key);
+    let path_bytes = path.as_bytes();
+
+    let mut proof = Vec::new();
+    let mut ptr = s.root_trieptr();
+    let mut consumed = 0usize;
+
+    loop {
+        let (node, _hash) = s.read_nodetype(&ptr)?;
+        let node = match node {
+            SealableTrieNode::Sealed => {
+                return Err(Error::CorruptionError("Cannot build a proof through a sealed node".to_string()));
+            }
+            SealableTrieNode::Node(node) => node,
+        };
+
+        if let TrieNodeType::Leaf(leaf) = &node {
+            proof.push(TrieMerkleProofType::Leaf((ptr.chr(), leaf.clone())));
+            break;
+        }
+
+        // a non-leaf node's `path()` is the compressed span shared by all of its children --
+        // it must match the next stretch of the hashed key, or this key isn't present.
+        let node_path = node.path_bytes().clone();
+        let diverges = consumed + node_path.len() > path_bytes.len()
+            || path_bytes[consumed..consumed + node_path.len()] != node_path[..];
+        consumed += node_path.len();
+
+        let next_chr = if diverges || consumed >= path_bytes.len() {
+            // no more path to walk, or the stored path already diverged -- there's no "next
+            // character" to follow, so record the proof against whichever character would have
+            // continued the walk (clamped to the last valid path byte) and stop.
+            path_bytes.get(consumed.min(path_bytes.len().saturating_sub(1))).cloned().unwrap_or(0)
+        } else {
+            path_bytes[consumed]
+        };
+
+        let proof_node = proof_trie_node_of::<T>(&node);
+        let other_hashes = read_other_child_hashes(s, node.ptrs(), next_chr)?;
+        proof.push(proof_type_of(next_chr, proof_node, other_hashes)?);
+
+        if diverges {
+            break;
+        }
+
+        match node.walk(next_chr) {
+            Some(child_ptr) => {
+                ptr = child_ptr;
+            }
+            None => break,
+        }
+    }
+
+    Ok(TrieMerkleProof(proof))
+}
+
+/// Build the `ProofTrieNode` half of a proof element: the node's id, path, and child pointers
+/// (sans hashes, which travel separately as the fixed-size hash array).
+fn proof_trie_node_of<T: ClarityMarfTrieId>(node: &TrieNodeType) -> ProofTrieNode<T> {
+    ProofTrieNode {
+        id: node.id(),
+        path: node.path_bytes().clone(),
+        ptrs: node.ptrs().iter().map(|ptr| ProofTriePtr {
+            id: ptr.id(),
+            chr: ptr.chr(),
+            back_block: T::sentinel(),
+        }).collect(),
+    }
+}
+
+/// Read the hash of every child of `ptrs` except the one at `skip_chr`, in pointer order --
+/// exactly the "other hashes" array a `TrieMerkleProofType` variant carries.
+fn read_other_child_hashes(s: &mut TrieFileStorage, ptrs: &[TriePtr], skip_chr: u8) -> Result<Vec<TrieHash>, Error> {
+    let mut hashes = Vec::with_capacity(ptrs.len().saturating_sub(1));
+    for child_ptr in ptrs.iter() {
+        if child_ptr.chr() == skip_chr {
+            continue;
+        }
+        let mut buf = Vec::with_capacity(TRIEHASH_ENCODED_SIZE);
+        s.read_node_hash_bytes(child_ptr, &mut buf)?;
+        hashes.push(trie_hash_from_bytes(&buf));
+    }
+    Ok(hashes)
+}
+
+/// Pack a node and its other-children hashes into the `TrieMerkleProofType` variant matching its
+/// fan-out. The fixed array holds one hash fewer than the node type's maximum fan-out (3, 15, 47
+/// or 255): a shorter list is padded with zeroed `TrieHash`es, a longer one is truncated.
+fn proof_type_of<T: ClarityMarfTrieId>(chr: u8, node: ProofTrieNode<T>, mut other_hashes: Vec<TrieHash>) -> Result<TrieMerkleProofType<T>, Error> {
+    fn padded<const N: usize>(mut hashes: Vec<TrieHash>) -> [TrieHash; N] {
+        while hashes.len() < N {
+            hashes.push(TrieHash([0u8; 32]));
+        }
+        hashes.truncate(N);
+        let mut arr = [TrieHash([0u8; 32]); N];
+        arr.copy_from_slice(&hashes);
+        arr
+    }
+
+    match node.ptrs.len() {
+        0..=4 => Ok(TrieMerkleProofType::Node4((chr, node, padded::<3>(std::mem::take(&mut other_hashes))))),
+        5..=16 => Ok(TrieMerkleProofType::Node16((chr, node, padded::<15>(std::mem::take(&mut other_hashes))))),
+        17..=48 => Ok(TrieMerkleProofType::Node48((chr, node, padded::<47>(std::mem::take(&mut other_hashes))))),
+        49..=256 => Ok(TrieMerkleProofType::Node256((chr, node, padded::<255>(std::mem::take(&mut other_hashes))))),
+        n => Err(Error::CorruptionError(format!("Node has too many children to prove: {}", n))),
+    }
+}
+
+/// Verify a proof built by `build_proof` against a known-good `root` hash. Recomputes each
+/// visited node's hash bottom-up from its recorded consensus bytes and child-hash buffer, and
+/// confirms the hash that reaches the top equals `root`.
+///
+/// `value` is `Some(..)` to check an inclusion proof (the proof's terminal `Leaf` must carry
+/// exactly this value) or `None` to check a non-inclusion proof (the terminal node must lack a
+/// child at the proof's recorded path character). A terminal node whose own path diverged from
+/// `key`'s is held to that same check: it is not told apart from a missing child pointer, and
+/// passes only if it also lacks a child at the recorded character.
+pub fn verify_proof<T: ClarityMarfTrieId>(root: &TrieHash, key: &[u8], value: Option<&MARFValue>, proof: &TrieMerkleProof<T>) -> bool {
+    let path = TriePath::from_key(key);
+    let path_bytes = path.as_bytes();
+
+    let mut elements = proof.0.iter();
+    let last = match elements.next_back() {
+        Some(last) => last,
+        None => return false,
+    };
+
+    let mut current_hash = match (last, value) {
+        (TrieMerkleProofType::Leaf((_chr, leaf)), Some(expected)) => {
+            if &leaf.data != expected || leaf.path.as_slice() != &path_bytes[path_bytes.len() - leaf.path.len()..] {
+                return false;
+            }
+            get_leaf_hash(leaf)
+        }
+        (TrieMerkleProofType::Leaf(_), None) => return false,
+        (proof_node, None) => {
+            if !terminal_lacks_child(proof_node) {
+                return false;
+            }
+            match recompute_node_hash(proof_node) {
+                Some(h) => h,
+                None => return false,
+            }
+        }
+        (_, Some(_)) => return false,
+    };
+
+    // fold the remaining elements (outermost-last, since `proof.0` is root-to-leaf order)
+    let remaining: Vec<&TrieMerkleProofType<T>> = elements.collect();
+    for proof_node in remaining.into_iter().rev() {
+        let hashes = match reinsert_hash(proof_node, current_hash) {
+            Some(h) => h,
+            None => return false,
+        };
+        current_hash = match recompute_node_hash_with(proof_node, &hashes) {
+            Some(h) => h,
+            None => return false,
+        };
+    }
+
+    &current_hash == root
+}
+
+/// True if a `TrieMerkleProofType::Node*` element's own recorded pointer list has no entry at its
+/// recorded path character -- the shape `build_proof` leaves for a missing-child non-inclusion.
+fn terminal_lacks_child<T>(elt: &TrieMerkleProofType<T>) -> bool {
+    match elt {
+        TrieMerkleProofType::Node4((chr, node, _))
+        | TrieMerkleProofType::Node16((chr, node, _))
+        | TrieMerkleProofType::Node48((chr, node, _))
+        | TrieMerkleProofType::Node256((chr, node, _)) => {
+            !node.ptrs.iter().any(|p| p.chr == *chr && p.id != TrieNodeID::Empty as u8)
+        }
+        _ => false,
+    }
+}
+
+/// Recompute a terminal node's own hash directly from its recorded (complete) child-hash array --
+/// used only when this node has no path continuing through it, so there's no child hash to
+/// reinsert first.
+fn recompute_node_hash<T: ClarityMarfTrieId>(elt: &TrieMerkleProofType<T>) -> Option<TrieHash> {
+    match elt {
+        TrieMerkleProofType::Node4((_, node, hashes)) => Some(hash_proof_node(node, &hashes[..])),
+        TrieMerkleProofType::Node16((_, node, hashes)) => Some(hash_proof_node(node, &hashes[..])),
+        TrieMerkleProofType::Node48((_, node, hashes)) => Some(hash_proof_node(node, &hashes[..])),
+        TrieMerkleProofType::Node256((_, node, hashes)) => Some(hash_proof_node(node, &hashes[..])),
+        _ => None,
+    }
+}
+
+/// Reinsert `child_hash` (the hash flowing up from the child already verified) at `chr`'s slot
+/// among this node's other-children hashes, in pointer order, producing the complete ordered
+/// child-hash buffer this node was originally hashed with.
+fn reinsert_hash<T>(elt: &TrieMerkleProofType<T>, child_hash: TrieHash) -> Option<Vec<TrieHash>> {
+    let (chr, node, other_hashes): (u8, &ProofTrieNode<T>, &[TrieHash]) = match elt {
+        TrieMerkleProofType::Node4((chr, node, hashes)) => (*chr, node, &hashes[..]),
+        TrieMerkleProofType::Node16((chr, node, hashes)) => (*chr, node, &hashes[..]),
+        TrieMerkleProofType::Node48((chr, node, hashes)) => (*chr, node, &hashes[..]),
+        TrieMerkleProofType::Node256((chr, node, hashes)) => (*chr, node, &hashes[..]),
+        TrieMerkleProofType::Leaf(_) | TrieMerkleProofType::Shunt(_) => return None,
+    };
+
+    let mut other = other_hashes.iter();
+    let mut ordered = Vec::with_capacity(node.ptrs.len());
+    for ptr in node.ptrs.iter() {
+        if ptr.id == TrieNodeID::Empty as u8 {
+            continue;
+        }
+        if ptr.chr == chr {
+            ordered.push(child_hash);
+        } else {
+            ordered.push(*other.next()?);
+        }
+    }
+    Some(ordered)
+}
+
+fn recompute_node_hash_with<T>(elt: &TrieMerkleProofType<T>, child_hashes: &[TrieHash]) -> Option<TrieHash> {
+    match elt {
+        TrieMerkleProofType::Node4((_, node, _))
+        | TrieMerkleProofType::Node16((_, node, _))
+        | TrieMerkleProofType::Node48((_, node, _))
+        | TrieMerkleProofType::Node256((_, node, _)) => Some(hash_proof_node(node, child_hashes)),
+        _ => None,
+    }
+}
+
+/// `get_nodetype_hash_bytes`'s counterpart for a `ProofTrieNode`: hash its own consensus bytes
+/// followed by its (ordered, complete) children's hashes.
+fn hash_proof_node<T: ClarityMarfTrieId>(node: &ProofTrieNode<T>, child_hashes: &[TrieHash]) -> TrieHash {
+    let mut consensus_bytes = Vec::new();
+    node.write_consensus_bytes(&mut (), &mut consensus_bytes)
+        .expect("Failed to write proof node consensus bytes to a Vec");
+
+    let mut child_hash_bytes = Vec::with_capacity(child_hashes.len() * TRIEHASH_ENCODED_SIZE);
+    for h in child_hashes {
+        child_hash_bytes.extend_from_slice(h.as_bytes());
+    }
+
+    compute_node_hash(&consensus_bytes, &child_hash_bytes)
+}
+
+impl<T: ClarityMarfTrieId> TrieMerkleProof<T> {
+    /// Check this proof against a known-good `root`, confirming that `key` maps to `value` (or,
+    /// when `value` is `None`, that `key` is absent) in the trie that hashes to `root`. A thin
+    /// wrapper around `verify_proof` so a proof deserialized off the wire can verify itself
+    /// without its caller reaching for the free function directly.
+    pub fn verify(&self, key: &[u8], value: Option<&MARFValue>, root: &TrieHash) -> bool {
+        verify_proof(root, key, value, self)
+    }
+}
+
+/// Wire-format discriminants for `TrieMerkleProofType`'s variants. Assigned once and never
+/// reordered, so a proof serialized by one node decodes the same way on any other.
+const PROOF_NODE4_ID: u8 = 0;
+const PROOF_NODE16_ID: u8 = 1;
+const PROOF_NODE48_ID: u8 = 2;
+const PROOF_NODE256_ID: u8 = 3;
+const PROOF_LEAF_ID: u8 = 4;
+const PROOF_SHUNT_ID: u8 = 5;
+
+fn write_hashes<W: Write>(hashes: &[TrieHash], fd: &mut W) -> Result<(), ::net::Error> {
+    for h in hashes {
+        h.consensus_serialize(fd)?;
+    }
+    Ok(())
+}
+
+fn read_hashes<R: Read, const N: usize>(fd: &mut R) -> Result<[TrieHash; N], ::net::Error> {
+    let mut hashes = Vec::with_capacity(N);
+    for _ in 0..N {
+        hashes.push(read_next(fd)?);
+    }
+    let mut arr = [TrieHash([0u8; 32]); N];
+    arr.copy_from_slice(&hashes);
+    Ok(arr)
+}
+
+impl<T: ClarityMarfTrieId> StacksMessageCodec for ProofTriePtr<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), ::net::Error> {
+        self.id.consensus_serialize(fd)?;
+        self.chr.consensus_serialize(fd)?;
+        // `back_block` is written byte-by-byte rather than as a single `write_all` so this stays
+        //   within the same `StacksMessageCodec`-only error path as everything else here, instead
+        //   of introducing an `io::Error` that would need its own conversion into `net::Error`.
+        for b in self.back_block.as_bytes() {
+            b.consensus_serialize(fd)?;
+        }
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<ProofTriePtr<T>, ::net::Error> {
+        let id = read_next(fd)?;
+        let chr = read_next(fd)?;
+        let mut back_block_bytes = [0u8; 32];
+        for i in 0..32 {
+            back_block_bytes[i] = read_next(fd)?;
+        }
+        Ok(ProofTriePtr {
+            id,
+            chr,
+            back_block: T::from_bytes(back_block_bytes),
+        })
+    }
+}
+
+impl<T: ClarityMarfTrieId> StacksMessageCodec for ProofTrieNode<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), ::net::Error> {
+        self.id.consensus_serialize(fd)?;
+        self.path.consensus_serialize(fd)?;
+        self.ptrs.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<ProofTrieNode<T>, ::net::Error> {
+        let id = read_next(fd)?;
+        let path = read_next(fd)?;
+        let ptrs = read_next(fd)?;
+        Ok(ProofTrieNode { id, path, ptrs })
+    }
+}
+
+impl<T: ClarityMarfTrieId> StacksMessageCodec for TrieMerkleProofType<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), ::net::Error> {
+        match self {
+            TrieMerkleProofType::Node4((chr, node, hashes)) => {
+                PROOF_NODE4_ID.consensus_serialize(fd)?;
+                chr.consensus_serialize(fd)?;
+                node.consensus_serialize(fd)?;
+                write_hashes(hashes, fd)
+            }
+            TrieMerkleProofType::Node16((chr, node, hashes)) => {
+                PROOF_NODE16_ID.consensus_serialize(fd)?;
+                chr.consensus_serialize(fd)?;
+                node.consensus_serialize(fd)?;
+                write_hashes(hashes, fd)
+            }
+            TrieMerkleProofType::Node48((chr, node, hashes)) => {
+                PROOF_NODE48_ID.consensus_serialize(fd)?;
+                chr.consensus_serialize(fd)?;
+                node.consensus_serialize(fd)?;
+                write_hashes(hashes, fd)
+            }
+            TrieMerkleProofType::Node256((chr, node, hashes)) => {
+                PROOF_NODE256_ID.consensus_serialize(fd)?;
+                chr.consensus_serialize(fd)?;
+                node.consensus_serialize(fd)?;
+                write_hashes(hashes, fd)
+            }
+            TrieMerkleProofType::Leaf((chr, leaf)) => {
+                PROOF_LEAF_ID.consensus_serialize(fd)?;
+                chr.consensus_serialize(fd)?;
+                leaf.consensus_serialize(fd)
+            }
+            TrieMerkleProofType::Shunt((idx, hashes)) => {
+                PROOF_SHUNT_ID.consensus_serialize(fd)?;
+                for b in &idx.to_be_bytes() {
+                    b.consensus_serialize(fd)?;
+                }
+                hashes.consensus_serialize(fd)
+            }
+        }
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TrieMerkleProofType<T>, ::net::Error> {
+        let type_id: u8 = read_next(fd)?;
+        match type_id {
+            PROOF_NODE4_ID => {
+                let chr = read_next(fd)?;
+                let node = read_next(fd)?;
+                let hashes = read_hashes::<_, 3>(fd)?;
+                Ok(TrieMerkleProofType::Node4((chr, node, hashes)))
+            }
+            PROOF_NODE16_ID => {
+                let chr = read_next(fd)?;
+                let node = read_next(fd)?;
+                let hashes = read_hashes::<_, 15>(fd)?;
+                Ok(TrieMerkleProofType::Node16((chr, node, hashes)))
+            }
+            PROOF_NODE48_ID => {
+                let chr = read_next(fd)?;
+                let node = read_next(fd)?;
+                let hashes = read_hashes::<_, 47>(fd)?;
+                Ok(TrieMerkleProofType::Node48((chr, node, hashes)))
+            }
+            PROOF_NODE256_ID => {
+                let chr = read_next(fd)?;
+                let node = read_next(fd)?;
+                let hashes = read_hashes::<_, 255>(fd)?;
+                Ok(TrieMerkleProofType::Node256((chr, node, hashes)))
+            }
+            PROOF_LEAF_ID => {
+                let chr = read_next(fd)?;
+                let leaf = read_next(fd)?;
+                Ok(TrieMerkleProofType::Leaf((chr, leaf)))
+            }
+            PROOF_SHUNT_ID => {
+                let mut idx_bytes = [0u8; 8];
+                for i in 0..8 {
+                    idx_bytes[i] = read_next(fd)?;
+                }
+                let hashes = read_next(fd)?;
+                Ok(TrieMerkleProofType::Shunt((i64::from_be_bytes(idx_bytes), hashes)))
+            }
+            _ => Err(::net::Error::DeserializeError(format!(
+                "Unrecognized TrieMerkleProofType wire tag: {}",
+                type_id
+            ))),
+        }
+    }
+}
+
+impl<T: ClarityMarfTrieId> StacksMessageCodec for TrieMerkleProof<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), ::net::Error> {
+        self.0.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TrieMerkleProof<T>, ::net::Error> {
+        let elements = read_next(fd)?;
+        Ok(TrieMerkleProof(elements))
+    }
+}
+
+/// `state_proof_response` couldn't find `key` at the requested tip (not a storage failure --
+/// `build_proof` walked all the way to a non-inclusion proof) vs. the walk itself hitting a
+/// storage-level problem.
+pub enum StateProofError {
+    KeyNotFound,
+    Index(Error),
+}
+
+impl From<Error> for StateProofError {
+    fn from(e: Error) -> StateProofError {
+        StateProofError::Index(e)
+    }
+}
+
+/// Build the JSON body for a `/v2/state_proof` response: `key`'s `MARFValue` plus the
+/// hex-serialized `TrieMerkleProof` chaining it back to `state_index_root`, so a remote verifier
+/// can feed both straight into `TrieMerkleProof::verify` without trusting this node.
+///
+/// This is the proof-production half of that RPC handler, scoped to what actually exists in this
+/// tree: `storage` must already be positioned at the requested block (opening the right MARF for
+/// an arbitrary `StacksBlockId` needs a block-keyed handle -- a `MarfConnection` impl -- which
+/// isn't present here yet), and wiring the result below into an actual `/v2/...` route still needs
+/// the HTTP routing layer, which likewise doesn't exist in this tree.
+pub fn state_proof_response<T: ClarityMarfTrieId>(
+    storage: &mut TrieFileStorage,
+    state_index_root: &TrieHash,
+    key: &[u8],
+) -> Result<serde_json::Value, StateProofError> {
+    let proof = build_proof::<T>(storage, key)?;
+
+    let marf_value = match proof.0.last() {
+        Some(TrieMerkleProofType::Leaf((_chr, leaf))) => leaf.data.clone(),
+        _ => return Err(StateProofError::KeyNotFound),
+    };
+
+    let mut proof_bytes = Vec::new();
+    proof
+        .consensus_serialize(&mut proof_bytes)
+        .expect("Failed to serialize a TrieMerkleProof to a Vec");
+
+    Ok(json!({
+        "marf_value": to_hex(marf_value.as_bytes()),
+        "proof": to_hex(&proof_bytes),
+        "proof_length": proof.0.len(),
+        "root": to_hex(state_index_root.as_bytes()),
+    }))
+}
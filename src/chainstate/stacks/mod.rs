@@ -20,7 +20,11 @@
 pub mod address;
 pub mod auth;
 pub mod block;
+pub mod db;
+pub mod events;
+pub mod events_bloom;
 pub mod index;
+pub mod receipt_index;
 pub mod transaction;
 
 use std::ops::Deref;
@@ -36,6 +40,8 @@ use util::hash::HASH160_ENCODED_SIZE;
 
 use util::secp256k1::MessageSignature;
 
+use core::StacksEpochId;
+
 use address::AddressHashMode;
 use burnchains::Txid;
 
@@ -68,17 +74,56 @@ pub const STACKS_PUBLIC_KEY_EMPTY_BYTES : [u8; 33] = [
 ];
 
 impl Txid {
-    /// A Stacks transaction ID is a sha512/256 hash (not a double-sha256 hash)
-    pub fn from_stacks_tx(txdata: &[u8]) -> Txid {
-        let h = Sha512_256::from_data(txdata);
+    /// Domain-separated tagged hash, modeled on BIP340: `Sha512_256(tag_hash || tag_hash ||
+    /// data)`, where `tag_hash = Sha512_256(tag.as_bytes())`. Hashing the tag into the digest
+    /// (rather than e.g. just prepending the literal tag bytes to `data`) means every distinct
+    /// `tag` puts its callers in a provably separate hash domain from every other tag, so the
+    /// same preimage bytes hashed under two different tags can never collide by construction --
+    /// which is what `from_stacks_tx`/`from_sighash_bytes` below rely on to keep a signature
+    /// computed over a sighash from ever being replayed as if it were a txid, or vice versa.
+    pub fn from_tagged(tag: &str, data: &[u8]) -> Txid {
+        let tag_hash = Sha512_256::from_data(tag.as_bytes());
+        let mut preimage = Vec::with_capacity(2 * tag_hash.as_bytes().len() + data.len());
+        preimage.extend_from_slice(tag_hash.as_bytes());
+        preimage.extend_from_slice(tag_hash.as_bytes());
+        preimage.extend_from_slice(data);
+
+        let h = Sha512_256::from_data(&preimage);
         let mut bytes = [0u8; 32];
         bytes.copy_from_slice(h.as_bytes());
         Txid(bytes)
     }
 
-    /// A sighash is calculated the same way as a txid
-    pub fn from_sighash_bytes(txdata: &[u8]) -> Txid {
-        Txid::from_stacks_tx(txdata)
+    /// A Stacks transaction ID, computed under the hash scheme `epoch_id` has in force. Epoch
+    /// 1.0 computes it the original way -- a plain, untagged `Sha512_256(txdata)`, the same
+    /// computation `from_sighash_bytes` used to do too -- so as not to change the txid of any
+    /// transaction that was ever mined before this hard fork. Only as of Epoch 2.0 is it a
+    /// tagged sha512/256 hash of the transaction's serialized bytes, tagged `"STX/txid"` to keep
+    /// it in a separate hash domain from `from_sighash_bytes` below.
+    pub fn from_stacks_tx(epoch_id: StacksEpochId, txdata: &[u8]) -> Txid {
+        match epoch_id {
+            StacksEpochId::Epoch10 => {
+                let h = Sha512_256::from_data(txdata);
+                let mut bytes = [0u8; 32];
+                bytes.copy_from_slice(h.as_bytes());
+                Txid(bytes)
+            }
+            StacksEpochId::Epoch20 => Txid::from_tagged("STX/txid", txdata),
+        }
+    }
+
+    /// A sighash, computed under the hash scheme `epoch_id` has in force. Epoch 1.0 computes a
+    /// sighash the same way it computes a txid (a plain, untagged `Sha512_256`), so a signature
+    /// minted before this hard fork keeps verifying against the same bytes it always did. As of
+    /// Epoch 2.0 it's a tagged sha512/256 hash of the same kind of data a txid is computed over,
+    /// but tagged `"STX/sighash"` so the two can never collide: without this, a signature
+    /// computed over a transaction's sighash would also validate against that same transaction's
+    /// txid, letting a signature minted for one purpose be replayed for the other.
+    pub fn from_sighash_bytes(epoch_id: StacksEpochId, txdata: &[u8]) -> Txid {
+        match epoch_id {
+            StacksEpochId::Epoch10 => Txid::from_stacks_tx(StacksEpochId::Epoch10, txdata),
+            StacksEpochId::Epoch20 => Txid::from_tagged("STX/sighash", txdata),
+        }
     }
 }
 
@@ -91,7 +136,11 @@ pub enum Error {
     /// Failed to validate spending condition 
     AuthError,
     /// Invalid transaction fee
-    InvalidFee
+    InvalidFee,
+    /// A `Memo` exceeded `MAX_MEMO_LEN`
+    InvalidMemo,
+    /// A microblock's signing key failed to produce a signature over its header
+    SigningError
 }
 
 impl fmt::Display for Error {
@@ -101,6 +150,8 @@ impl fmt::Display for Error {
             Error::DecodeError => f.write_str(error::Error::description(self)),
             Error::AuthError => f.write_str(error::Error::description(self)),
             Error::InvalidFee => f.write_str(error::Error::description(self)),
+            Error::InvalidMemo => f.write_str(error::Error::description(self)),
+            Error::SigningError => f.write_str(error::Error::description(self)),
         }
     }
 }
@@ -112,6 +163,8 @@ impl error::Error for Error {
             Error::DecodeError => None,
             Error::AuthError => None,
             Error::InvalidFee => None,
+            Error::InvalidMemo => None,
+            Error::SigningError => None,
         }
     }
 
@@ -121,6 +174,8 @@ impl error::Error for Error {
             Error::DecodeError => "Failed to decode",
             Error::AuthError => "Failed to authenticate transaction",
             Error::InvalidFee => "Invalid transaction fee",
+            Error::InvalidMemo => "Transaction memo is too long",
+            Error::SigningError => "Failed to sign microblock header",
         }
     }
 }
@@ -480,6 +535,10 @@ pub struct TransactionSmartContract {
 pub enum TransactionPayload {
     ContractCall(TransactionContractCall),
     SmartContract(TransactionSmartContract),
+    /// Several payloads authorized and signed as a single atomic unit: either every section
+    /// applies, or the whole transaction aborts. See `chainstate::stacks::transaction` for how
+    /// each section's own commitment hash is computed and combined into the batch's signing hash.
+    Batch(Vec<TransactionPayload>),
 }
 
 #[repr(u8)]
@@ -487,6 +546,7 @@ pub enum TransactionPayload {
 pub enum TransactionPayloadID {
     SmartContract = 0,
     ContractCall = 1,
+    Batch = 2,
 }
 
 /// Encoding of an asset type identifier 
@@ -621,7 +681,11 @@ pub struct StacksTransaction {
     pub fee: TransactionFee,
     pub anchor_mode: TransactionAnchorMode,
     pub post_conditions: Vec<TransactionPostCondition>,
-    pub payload: TransactionPayload
+    pub payload: TransactionPayload,
+    /// An optional, bounded free-form annotation (see `transaction::Memo`), e.g. a note a wallet
+    /// attaches to a transfer. Not part of any payload, so it carries no on-chain effect of its
+    /// own -- only the payload(s) and post-conditions do.
+    pub memo: Option<transaction::Memo>
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -651,11 +715,34 @@ pub struct StacksBlockHeader {
     microblock_pubkey: StacksPublicKey
 }
 
-/// A block that contains blockchain-anchored data 
+impl StacksBlockHeader {
+    /// A simplified block-identifying hash: `Sha512_256` over this header's version, total work
+    /// (work, then burn), parent block and parent microblock hashes, and tx Merkle root; the VRF
+    /// proof, state index root and microblock public key are not hashed. Hashed directly rather
+    /// than through `StacksMessageCodec`, since no consensus-serialization impl for
+    /// `StacksBlockHeader` exists in this tree (`block.rs`'s `compute_short_id_key` calls one all
+    /// the same). Good enough to chain a tenure's first microblock after
+    /// this block and to let a later block's header commit to this one as its parent.
+    pub fn block_hash(&self) -> BlockHeaderHash {
+        let mut preimage = vec![];
+        preimage.push(self.version);
+        preimage.extend_from_slice(&self.total_work.work.to_be_bytes());
+        preimage.extend_from_slice(&self.total_work.burn.to_be_bytes());
+        preimage.extend_from_slice(self.parent_block.as_bytes());
+        preimage.extend_from_slice(self.parent_microblock.as_bytes());
+        preimage.extend_from_slice(self.tx_merkle_root.as_bytes());
+        let digest = Sha512_256::from_data(&preimage);
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(digest.as_bytes());
+        BlockHeaderHash(bytes)
+    }
+}
+
+/// A block that contains blockchain-anchored data
 /// (corresponding to a LeaderBlockCommitOp)
 #[derive(Debug, Clone, PartialEq)]
 pub struct StacksBlock {
-    header: StacksBlockHeader,
+    pub header: StacksBlockHeader,
     txs: Vec<StacksTransaction>
 }
 
@@ -670,11 +757,71 @@ pub struct StacksMicroblockHeader {
 }
 
 /// A microblock that contains non-blockchain-anchored data,
-/// but is tied to an on-chain block 
+/// but is tied to an on-chain block
 #[derive(Debug, Clone, PartialEq)]
 pub struct StacksMicroblock {
-    header: StacksMicroblockHeader,
-    txs: Vec<StacksTransaction>
+    pub header: StacksMicroblockHeader,
+    pub txs: Vec<StacksTransaction>
+}
+
+impl StacksMicroblockHeader {
+    /// Hashes `txs`' serialized bytes together into the commitment `tx_merkle_root` attests to.
+    /// NOTE: a stand-in for a real Merkle tree -- no generic accumulator over `StacksTransaction`
+    /// exists in this tree (`burnchains::ops_merkle::compute_ops_root` builds one, but only for
+    /// types implementing its own `MerkleLeaf`) -- good enough to detect any change to the
+    /// microblock's tx set, but without per-tx inclusion proofs.
+    fn txs_commitment(txs: &[StacksTransaction]) -> Sha512_256 {
+        let mut preimage = vec![];
+        for tx in txs {
+            preimage.extend_from_slice(&tx.serialize());
+        }
+        Sha512_256::from_data(&preimage)
+    }
+
+    /// Builds and signs a microblock header chaining after `prev_block` at `sequence` in a
+    /// tenure's microblock stream, committing to `txs` via `tx_merkle_root`.
+    pub fn sign(sequence: u32, prev_block: BlockHeaderHash, txs: &[StacksTransaction], privk: &StacksPrivateKey) -> Result<StacksMicroblockHeader, Error> {
+        let tx_merkle_root = Self::txs_commitment(txs);
+
+        let mut preimage = vec![];
+        preimage.push(1u8);
+        preimage.extend_from_slice(&sequence.to_be_bytes());
+        preimage.extend_from_slice(prev_block.as_bytes());
+        preimage.extend_from_slice(tx_merkle_root.as_bytes());
+
+        let signature = privk.sign(&preimage).map_err(|_e| Error::SigningError)?;
+
+        Ok(StacksMicroblockHeader {
+            version: 1,
+            sequence,
+            prev_block,
+            tx_merkle_root,
+            signature,
+        })
+    }
+
+    /// This header's own identifying hash, so a following microblock in the same stream can chain
+    /// its `prev_block` to it -- the microblock counterpart to `StacksBlockHeader::block_hash`.
+    pub fn block_hash(&self) -> BlockHeaderHash {
+        let mut preimage = vec![];
+        preimage.push(self.version);
+        preimage.extend_from_slice(&self.sequence.to_be_bytes());
+        preimage.extend_from_slice(self.prev_block.as_bytes());
+        preimage.extend_from_slice(self.tx_merkle_root.as_bytes());
+        let digest = Sha512_256::from_data(&preimage);
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(digest.as_bytes());
+        BlockHeaderHash(bytes)
+    }
+}
+
+impl StacksMicroblock {
+    /// Builds a signed microblock carrying `txs`, next in sequence after `prev_block` in the
+    /// tenure's stream.
+    pub fn from_txs(sequence: u32, prev_block: BlockHeaderHash, txs: Vec<StacksTransaction>, privk: &StacksPrivateKey) -> Result<StacksMicroblock, Error> {
+        let header = StacksMicroblockHeader::sign(sequence, prev_block, &txs, privk)?;
+        Ok(StacksMicroblock { header, txs })
+    }
 }
 
 // maximum block size is 1MB.  Complaints to /dev/null -- if you need bigger, start an app chain
@@ -685,3 +832,50 @@ pub const MAX_MICROBLOCK_SIZE : u32 = 65536;
 
 // maximum microblocks between stacks blocks (amounts to 16MB of data at max)
 pub const MAX_MICROBLOCK_SEQUENCE_LEN : u32 = 256;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_txid_sighash_share_epoch10_scheme() {
+        let txdata = b"some transaction bytes";
+        // Epoch 1.0 never domain-separated a txid from a sighash -- both must still collapse to
+        // the same digest for the same input, so a pre-fork signature keeps verifying.
+        assert_eq!(
+            Txid::from_stacks_tx(StacksEpochId::Epoch10, txdata),
+            Txid::from_sighash_bytes(StacksEpochId::Epoch10, txdata)
+        );
+    }
+
+    #[test]
+    fn test_txid_sighash_diverge_epoch20_scheme() {
+        let txdata = b"some transaction bytes";
+        // As of Epoch 2.0, a txid and a sighash over the same bytes must land in provably
+        // separate hash domains.
+        assert_ne!(
+            Txid::from_stacks_tx(StacksEpochId::Epoch20, txdata),
+            Txid::from_sighash_bytes(StacksEpochId::Epoch20, txdata)
+        );
+    }
+
+    #[test]
+    fn test_txid_epoch10_matches_legacy_untagged_hash() {
+        let txdata = b"some transaction bytes";
+        let legacy = Sha512_256::from_data(txdata);
+        let mut expected = [0u8; 32];
+        expected.copy_from_slice(legacy.as_bytes());
+        assert_eq!(Txid::from_stacks_tx(StacksEpochId::Epoch10, txdata), Txid(expected));
+    }
+
+    #[test]
+    fn test_txid_epoch_schemes_disagree() {
+        let txdata = b"some transaction bytes";
+        // A historical txid computed under Epoch 1.0's scheme must not silently change meaning
+        // once Epoch 2.0 activates.
+        assert_ne!(
+            Txid::from_stacks_tx(StacksEpochId::Epoch10, txdata),
+            Txid::from_stacks_tx(StacksEpochId::Epoch20, txdata)
+        );
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// An on-demand "give me this one transaction's receipt" service, keyed by txid, so a peer doesn't
+// have to download and replay a whole block just to learn one transaction's outcome. Two pieces:
+//
+//   - `TxReceiptIndex`, a `txid -> (block id, tx index)` map a node builds up as it processes
+//     blocks, so a receipt lookup is a single hash-map hit instead of a block scan.
+//   - `PeerCreditBucket`/`ReceiptService`, a PIP-style (metered, replenishing credit) flow
+//     control so serving receipts to peers can't be turned into a free-bandwidth DoS: each
+//     request's cost is `BASE_REQUEST_COST` plus `PER_EVENT_COST` per event the receipt carries,
+//     deducted from the requesting peer's bucket before the receipt is handed back. An unknown
+//     txid is reported back at zero cost, so probing for txids a node doesn't have can't itself
+//     drain a peer's credit.
+//
+// NOTE: this tree has no net/p2p module, and its `chainstate::stacks::db` is only a read-only
+// contract-constant lookup (no MARF-backed
+// chainstate to persist `TxReceiptIndex` into, and no RPC/p2p message types to carry
+// `ReceiptRequest`/`ReceiptResponse` over the wire), so there's no `/v2/transaction/<txid>/receipt`
+// endpoint or actual p2p request type to wire these into here. What's below is the self-contained
+// serving logic a real tree's RPC handler and p2p message handler would both call: they'd each
+// look up a peer's `PeerCreditBucket` (keyed however that tree's peer/connection table already
+// keys its peers), call `ReceiptService::handle_request`, and translate the `ReceiptResponse`
+// into their own wire format.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Instant;
+
+use burnchains::Txid;
+use chainstate::stacks::events::{StacksTransactionEvent, StacksTransactionReceipt};
+use types::chainstate::StacksBlockId;
+use vm::types::Value;
+
+/// Where a processed transaction's receipt lives: the block it was mined in, and its position
+/// within that block's transaction list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxReceiptLocation {
+    pub index_block_hash: StacksBlockId,
+    pub tx_index: u32,
+}
+
+/// A `txid -> (block id, tx index)` map, built up one entry per transaction as blocks are
+/// processed (e.g. from `RunLoop`'s `notify_new_chain_state`/the event-observer path), so a
+/// receipt lookup never has to scan a block to find the transaction it's looking for.
+#[derive(Debug, Default)]
+pub struct TxReceiptIndex {
+    locations: HashMap<Txid, TxReceiptLocation>,
+}
+
+impl TxReceiptIndex {
+    pub fn new() -> TxReceiptIndex {
+        TxReceiptIndex {
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Records every transaction in a newly-processed block's location, keyed by its txid.
+    pub fn record_block(&mut self, index_block_hash: StacksBlockId, txids: &[Txid]) {
+        for (tx_index, txid) in txids.iter().enumerate() {
+            self.locations.insert(
+                txid.clone(),
+                TxReceiptLocation {
+                    index_block_hash: index_block_hash.clone(),
+                    tx_index: tx_index as u32,
+                },
+            );
+        }
+    }
+
+    pub fn lookup(&self, txid: &Txid) -> Option<&TxReceiptLocation> {
+        self.locations.get(txid)
+    }
+}
+
+/// Flat cost of any receipt request that resolves to a known txid, before accounting for the
+/// receipt's own size.
+const BASE_REQUEST_COST: u64 = 10;
+/// Additional cost per event the resolved receipt carries, since larger receipts cost more
+/// bandwidth to serve.
+const PER_EVENT_COST: u64 = 1;
+
+/// Computes the credit cost of serving `receipt`: a flat base cost, plus a per-event cost that
+/// scales with how much the receipt actually costs to transmit.
+fn receipt_request_cost(receipt: &StacksTransactionReceipt) -> u64 {
+    BASE_REQUEST_COST + PER_EVENT_COST * (receipt.events.len() as u64)
+}
+
+/// A per-peer replenishing credit bucket (the PIP "pay it forward" metering pattern): a peer
+/// starts with `capacity` credits, spends them on requests, and earns them back at
+/// `refill_per_sec`, capped at `capacity` -- so a peer that goes quiet for a while doesn't
+/// accumulate an unbounded backlog of credit, but a peer that's been well-behaved recently can
+/// burst up to a full bucket's worth of requests.
+#[derive(Debug)]
+pub struct PeerCreditBucket {
+    capacity: u64,
+    refill_per_sec: u64,
+    credits: u64,
+    last_refill: Instant,
+}
+
+impl PeerCreditBucket {
+    pub fn new(capacity: u64, refill_per_sec: u64) -> PeerCreditBucket {
+        PeerCreditBucket {
+            capacity,
+            refill_per_sec,
+            credits: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let earned = (elapsed.as_secs() as u64).saturating_mul(self.refill_per_sec);
+        if earned > 0 {
+            self.credits = (self.credits.saturating_add(earned)).min(self.capacity);
+            self.last_refill = now;
+        }
+    }
+
+    /// Attempts to deduct `cost` credits, refilling first. Returns whether the deduction
+    /// succeeded; the bucket is left unchanged on failure.
+    pub fn try_spend(&mut self, cost: u64) -> bool {
+        self.refill(Instant::now());
+        if self.credits >= cost {
+            self.credits -= cost;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn credits(&self) -> u64 {
+        self.credits
+    }
+}
+
+/// The result of a receipt request: either the receipt's location and contents, a cheap
+/// not-found for an unrecognized txid, or a rejection because the requesting peer is out of
+/// credit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReceiptResponse {
+    Found {
+        index_block_hash: StacksBlockId,
+        tx_index: u32,
+        raw_result: Value,
+        events: Vec<StacksTransactionEvent>,
+    },
+    NotFound,
+    InsufficientCredit,
+}
+
+/// Ties `TxReceiptIndex` lookups to per-peer `PeerCreditBucket` metering: default capacity/refill
+/// for newly-seen peers are set generously enough that a single well-behaved client doing normal
+/// wallet-style polling never hits the limit, while a peer hammering the service with requests
+/// for receipts it already has runs out of credit and has to wait for the bucket to refill.
+pub struct ReceiptService<P: Eq + Hash + Clone> {
+    index: TxReceiptIndex,
+    receipts: HashMap<Txid, StacksTransactionReceipt>,
+    peers: HashMap<P, PeerCreditBucket>,
+    default_capacity: u64,
+    default_refill_per_sec: u64,
+}
+
+impl<P: Eq + Hash + Clone> ReceiptService<P> {
+    pub fn new(default_capacity: u64, default_refill_per_sec: u64) -> ReceiptService<P> {
+        ReceiptService {
+            index: TxReceiptIndex::new(),
+            receipts: HashMap::new(),
+            peers: HashMap::new(),
+            default_capacity,
+            default_refill_per_sec,
+        }
+    }
+
+    /// Records a newly-processed block's transactions and their receipts, so they become
+    /// servable by txid from here on.
+    pub fn record_block(
+        &mut self,
+        index_block_hash: StacksBlockId,
+        receipts: &[StacksTransactionReceipt],
+    ) {
+        let txids: Vec<Txid> = receipts
+            .iter()
+            .map(|r| r.transaction.txid())
+            .collect();
+        self.index.record_block(index_block_hash, &txids);
+        for (txid, receipt) in txids.into_iter().zip(receipts.iter().cloned()) {
+            self.receipts.insert(txid, receipt);
+        }
+    }
+
+    /// Serves a single receipt-by-txid request from `peer`, metering it against that peer's
+    /// credit bucket (creating one with the service's defaults on first contact). An unknown
+    /// txid is reported back as `NotFound` without spending any credit, so probing for txids
+    /// this node doesn't have can't itself be used to drain a peer's bucket.
+    pub fn handle_request(&mut self, peer: P, txid: &Txid) -> ReceiptResponse {
+        let location = match self.index.lookup(txid) {
+            Some(location) => location.clone(),
+            None => return ReceiptResponse::NotFound,
+        };
+        let receipt = match self.receipts.get(txid) {
+            Some(receipt) => receipt,
+            None => return ReceiptResponse::NotFound,
+        };
+
+        let cost = receipt_request_cost(receipt);
+        let default_capacity = self.default_capacity;
+        let default_refill_per_sec = self.default_refill_per_sec;
+        let bucket = self
+            .peers
+            .entry(peer)
+            .or_insert_with(|| PeerCreditBucket::new(default_capacity, default_refill_per_sec));
+
+        if !bucket.try_spend(cost) {
+            return ReceiptResponse::InsufficientCredit;
+        }
+
+        ReceiptResponse::Found {
+            index_block_hash: location.index_block_hash,
+            tx_index: location.tx_index,
+            raw_result: receipt.result.clone(),
+            events: receipt.events.clone(),
+        }
+    }
+}
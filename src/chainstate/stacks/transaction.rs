@@ -0,0 +1,172 @@
+/*
+ copyright: (c) 2013-2019 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+// A bounded memo annotation, plus wire encoding and per-section integrity commitments for
+// `TransactionPayload::Batch` -- the mechanism that lets a single `TransactionAuth` authorize
+// several contract calls/deploys as one atomic unit (`chainstate::stacks::TransactionPayload`
+// itself carries the `Batch` variant; this module is its supporting codec and hashing logic).
+//
+// NOTE: `transaction.rs` was declared (`pub mod transaction;` in `chainstate::stacks::mod`) but
+// had no file behind it before this -- the same "declared but missing" gap `chainstate::stacks::
+// events`/`chainstate::stacks::block` had before this session's
+// `[toptrust3/stacks-blockchain#chunk22-2]`/`[...#chunk23-1]` fixes -- so there was no prior
+// `StacksMessageCodec` impl anywhere in this tree for `TransactionContractCall`/
+// `TransactionSmartContract`/`TransactionPayload` to build on; the impls below are new, not a
+// port of something that existed elsewhere. And since `chainstate::stacks::db` holds only a
+// read-only contract-constant lookup (nothing that executes a batch's sections against the
+// MARF-backed contract state) and `vm::clarity_tx` is absent from this tree, there's no real `ClarityTx`/chainstate
+// handle here for "post-conditions evaluated against the aggregate state change of the whole
+// batch" to run against. What's implementable without that machinery -- per-section commitment
+// hashes and the batch signing hash they roll up into -- is below; a real tree's tx-processing
+// pipeline would run every section's payload through the same Clarity execution path a
+// standalone transaction's payload goes through, accumulate their asset-map deltas, and check
+// `post_conditions` against the accumulated delta instead of any one section's.
+
+use util::hash::Sha512_256;
+
+use net::StacksMessageCodec;
+use net::codec::{read_next, write_next};
+use net::Error as net_error;
+
+use chainstate::stacks::{
+    Error, StacksString, TransactionContractCall, TransactionPayload, TransactionPayloadID,
+    TransactionSmartContract,
+};
+
+/// The longest a `Memo`'s contents may be. Chosen to match the memo field size Stacks transfers
+/// have historically budgeted for short annotations (e.g. an exchange deposit tag) without
+/// meaningfully growing the transaction.
+pub const MAX_MEMO_LEN: usize = 34;
+
+/// A bounded free-form annotation a transaction can carry (see `StacksTransaction::memo`).
+/// Constructing one enforces `MAX_MEMO_LEN` up front, so a `Memo` in hand is always valid to
+/// serialize.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Memo(pub StacksString);
+
+impl Memo {
+    pub fn new(contents: StacksString) -> Result<Memo, Error> {
+        if contents.len() > MAX_MEMO_LEN {
+            return Err(Error::InvalidMemo);
+        }
+        Ok(Memo(contents))
+    }
+}
+
+impl StacksMessageCodec for Memo {
+    fn serialize(&self) -> Vec<u8> {
+        let mut res = vec![];
+        write_next(&mut res, &self.0);
+        res
+    }
+
+    fn deserialize(buf: &Vec<u8>, index: &mut u32, max_size: u32) -> Result<Memo, net_error> {
+        let contents: StacksString = read_next(buf, index, max_size)?;
+        Memo::new(contents).map_err(|_| net_error::DeserializeError)
+    }
+}
+
+impl StacksMessageCodec for TransactionContractCall {
+    fn serialize(&self) -> Vec<u8> {
+        let mut res = vec![];
+        write_next(&mut res, &self.contract_call);
+        res
+    }
+
+    fn deserialize(buf: &Vec<u8>, index: &mut u32, max_size: u32) -> Result<TransactionContractCall, net_error> {
+        let contract_call: StacksString = read_next(buf, index, max_size)?;
+        Ok(TransactionContractCall { contract_call })
+    }
+}
+
+impl StacksMessageCodec for TransactionSmartContract {
+    fn serialize(&self) -> Vec<u8> {
+        let mut res = vec![];
+        write_next(&mut res, &self.name);
+        write_next(&mut res, &self.code_body);
+        res
+    }
+
+    fn deserialize(buf: &Vec<u8>, index: &mut u32, max_size: u32) -> Result<TransactionSmartContract, net_error> {
+        let name: StacksString = read_next(buf, index, max_size)?;
+        let code_body: StacksString = read_next(buf, index, max_size)?;
+        Ok(TransactionSmartContract { name, code_body })
+    }
+}
+
+impl StacksMessageCodec for TransactionPayload {
+    fn serialize(&self) -> Vec<u8> {
+        let mut res = vec![];
+        match self {
+            TransactionPayload::SmartContract(data) => {
+                write_next(&mut res, &(TransactionPayloadID::SmartContract as u8));
+                write_next(&mut res, data);
+            }
+            TransactionPayload::ContractCall(data) => {
+                write_next(&mut res, &(TransactionPayloadID::ContractCall as u8));
+                write_next(&mut res, data);
+            }
+            TransactionPayload::Batch(payloads) => {
+                write_next(&mut res, &(TransactionPayloadID::Batch as u8));
+                write_next(&mut res, payloads);
+            }
+        }
+        res
+    }
+
+    fn deserialize(buf: &Vec<u8>, index: &mut u32, max_size: u32) -> Result<TransactionPayload, net_error> {
+        let type_id: u8 = read_next(buf, index, max_size)?;
+        let payload = if type_id == TransactionPayloadID::SmartContract as u8 {
+            let data: TransactionSmartContract = read_next(buf, index, max_size)?;
+            TransactionPayload::SmartContract(data)
+        } else if type_id == TransactionPayloadID::ContractCall as u8 {
+            let data: TransactionContractCall = read_next(buf, index, max_size)?;
+            TransactionPayload::ContractCall(data)
+        } else if type_id == TransactionPayloadID::Batch as u8 {
+            let payloads: Vec<TransactionPayload> = read_next(buf, index, max_size)?;
+            TransactionPayload::Batch(payloads)
+        } else {
+            return Err(net_error::DeserializeError);
+        };
+        Ok(payload)
+    }
+}
+
+/// The commitment a single payload section contributes to its batch: a `Sha512_256` over that
+/// section's own serialized bytes, independent of every other section. Letting each section
+/// commit to itself this way (rather than hashing the whole batch's concatenated bodies in one
+/// pass) is what lets one section be referenced and verified -- e.g. by a block explorer showing
+/// "section 2 of this batch did X" -- without needing the other sections' bytes in hand to
+/// recompute anything.
+pub fn payload_commitment(payload: &TransactionPayload) -> Sha512_256 {
+    Sha512_256::from_data(&payload.serialize())
+}
+
+/// The hash a batch transaction's signature actually covers: `Sha512_256` over the ordered
+/// concatenation of every section's own `payload_commitment`, rather than over the sections'
+/// concatenated bodies directly. Two batches that happen to commit to the same sections in the
+/// same order always produce the same signing hash regardless of how large any one section is,
+/// and verifying the signature never requires re-hashing more than one section at a time.
+pub fn batch_signing_hash(payloads: &[TransactionPayload]) -> Sha512_256 {
+    let mut preimage = Vec::with_capacity(payloads.len() * 32);
+    for payload in payloads {
+        preimage.extend_from_slice(payload_commitment(payload).as_bytes());
+    }
+    Sha512_256::from_data(&preimage)
+}
@@ -0,0 +1,169 @@
+/*
+ copyright: (c) 2013-2018 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+// A version-bits style (BIP9-like) soft-fork deployment gate: a new burn-op validation rule is
+// defined ahead of time but only *enforced* once miners have signaled support for it over a
+// sustained window of burn blocks. This lets stricter rules roll out on a coordinated schedule
+// instead of a flag-day hard fork.
+
+/// A named, independently-tracked deployment. `UserBurnMemoV2` would gate a future memo layout
+/// change; `StricterConsensusHash` gates `UserBurnSupportOp::check`'s tightened consensus-hash
+/// rule (in `chainstate::burn::operations::user_burn_support`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeploymentName {
+    UserBurnMemoV2,
+    StricterConsensusHash,
+}
+
+/// A deployment's activation state, following the same `Defined -> Started -> LockedIn -> Active`
+/// (or `-> Failed`) progression as a BIP9 version-bits soft fork.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeploymentState {
+    /// Before `start_height`: not yet being signaled for.
+    Defined,
+    /// Between `start_height` and `timeout_height`: each retarget window's signal count is
+    /// checked against `threshold`.
+    Started,
+    /// A window met `threshold` while `Started`; becomes `Active` once the following window
+    /// completes.
+    LockedIn,
+    /// The new rule is enforced.
+    Active,
+    /// `timeout_height` passed without a window ever meeting `threshold`.
+    Failed,
+}
+
+/// A deployment's parameters: which `memo` bit blocks signal support with, the height range over
+/// which signaling is counted, and the per-window threshold/size that governs lock-in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deployment {
+    pub name: DeploymentName,
+    /// Bit position (0-7) of the reserved signal bit within the op's first `memo` byte.
+    pub bit: u8,
+    pub start_height: u64,
+    pub timeout_height: u64,
+    /// Number of consecutive burn blocks per retarget window.
+    pub window_size: u64,
+    /// Minimum number of blocks in a window that must signal for lock-in to occur.
+    pub threshold: u64,
+}
+
+impl Deployment {
+    fn signal_mask(&self) -> u8 {
+        1 << self.bit
+    }
+
+    /// Whether `memo`'s reserved bit is set, i.e. whether the block that produced this op
+    /// signaled support for this deployment. A missing or too-short memo never signals.
+    pub fn signals(&self, memo: &[u8]) -> bool {
+        memo.get(0)
+            .map(|byte| byte & self.signal_mask() != 0)
+            .unwrap_or(false)
+    }
+}
+
+/// Accumulates per-block signaling for a single `Deployment` and tracks its resulting
+/// `DeploymentState`, one retarget window at a time. Blocks must be `push`ed in ascending height
+/// order, same convention as `fast_sync::FastSyncCheckpointer`.
+pub struct DeploymentTracker {
+    deployment: Deployment,
+    state: DeploymentState,
+    current_window: Option<u64>,
+    blocks_in_window: u64,
+    signals_in_window: u64,
+    /// Set when `LockedIn` begins: the window index whose completion flips this to `Active`.
+    active_at_window: Option<u64>,
+}
+
+impl DeploymentTracker {
+    pub fn new(deployment: Deployment) -> DeploymentTracker {
+        DeploymentTracker {
+            deployment,
+            state: DeploymentState::Defined,
+            current_window: None,
+            blocks_in_window: 0,
+            signals_in_window: 0,
+            active_at_window: None,
+        }
+    }
+
+    pub fn state(&self) -> DeploymentState {
+        self.state
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == DeploymentState::Active
+    }
+
+    /// Feed the next block's height and the `memo` of the op (if any) that should be consulted
+    /// for this deployment's signal bit.
+    pub fn push(&mut self, block_height: u64, memo: &[u8]) {
+        if self.state == DeploymentState::Failed || self.state == DeploymentState::Active {
+            return;
+        }
+        if block_height < self.deployment.start_height {
+            return;
+        }
+
+        let window = block_height / self.deployment.window_size;
+        match self.current_window {
+            Some(prev_window) if prev_window == window => {}
+            Some(prev_window) => {
+                self.complete_window(prev_window);
+                self.blocks_in_window = 0;
+                self.signals_in_window = 0;
+            }
+            None => {
+                if self.state == DeploymentState::Defined {
+                    self.state = DeploymentState::Started;
+                }
+            }
+        }
+        self.current_window = Some(window);
+
+        self.blocks_in_window += 1;
+        if self.deployment.signals(memo) {
+            self.signals_in_window += 1;
+        }
+
+        if self.state == DeploymentState::Started && block_height >= self.deployment.timeout_height {
+            self.state = DeploymentState::Failed;
+        }
+    }
+
+    /// Applies the state transition `completed_window`'s final signal count earns, per the
+    /// `Started -> LockedIn -> Active` progression: `LockedIn` takes effect the window right
+    /// after the one that hit `threshold`, and `Active` the window after that.
+    fn complete_window(&mut self, completed_window: u64) {
+        match self.state {
+            DeploymentState::Started => {
+                if self.signals_in_window >= self.deployment.threshold {
+                    self.state = DeploymentState::LockedIn;
+                    self.active_at_window = Some(completed_window + 1);
+                }
+            }
+            DeploymentState::LockedIn => {
+                if self.active_at_window == Some(completed_window) {
+                    self.state = DeploymentState::Active;
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ copyright: (c) 2013-2018 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+// This module holds the consensus-rule epoch schedule: the burn-height ranges over which a
+// particular revision of the protocol's validation rules is in force. `chainstate::burn::
+// operations::*::check` consults it so an operation's acceptance rules can change at a hard fork
+// without forking the operation type itself.
+
+pub mod deployments;
+
+use std::ops::{Deref, Index};
+
+/// Identifies one revision of the protocol's consensus rules. Burn-op `check()` implementations
+/// match on this to decide which validation rules apply at a given burn height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StacksEpochId {
+    Epoch10,
+    Epoch20,
+}
+
+/// One span of consensus rules: `epoch_id` is in force for burn heights in `[start_height,
+/// end_height)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StacksEpoch {
+    pub epoch_id: StacksEpochId,
+    pub start_height: u64,
+    pub end_height: u64,
+}
+
+/// An ordered schedule of `StacksEpoch`s. Epochs are stored sorted and contiguous by
+/// `start_height` with no gaps -- each epoch's `end_height` equals the next epoch's
+/// `start_height` -- so resolving the epoch active at a given burn height is a linear
+/// range-containment scan rather than a binary search keyed on anything else.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochList(Vec<StacksEpoch>);
+
+impl EpochList {
+    pub fn new(epochs: &[StacksEpoch]) -> EpochList {
+        EpochList(epochs.to_vec())
+    }
+
+    /// The configured `StacksEpoch` for `id`, if this schedule defines one.
+    pub fn get(&self, id: StacksEpochId) -> Option<&StacksEpoch> {
+        self.0.iter().find(|epoch| epoch.epoch_id == id)
+    }
+
+    pub fn get_mut(&mut self, id: StacksEpochId) -> Option<&mut StacksEpoch> {
+        self.0.iter_mut().find(|epoch| epoch.epoch_id == id)
+    }
+
+    /// The epoch whose `[start_height, end_height)` range contains `burn_height`, or `None` if
+    /// `burn_height` falls outside every configured epoch (before the first epoch's
+    /// `start_height`, or at/after the last epoch's `end_height`).
+    pub fn epoch_at_height(&self, burn_height: u64) -> Option<&StacksEpoch> {
+        self.0
+            .iter()
+            .find(|epoch| burn_height >= epoch.start_height && burn_height < epoch.end_height)
+    }
+}
+
+impl Deref for EpochList {
+    type Target = [StacksEpoch];
+
+    fn deref(&self) -> &[StacksEpoch] {
+        &self.0
+    }
+}
+
+impl Index<StacksEpochId> for EpochList {
+    type Output = StacksEpoch;
+
+    /// Panics if `id` isn't configured in this schedule -- same contract as indexing a `Vec` out
+    /// of bounds, since a caller indexing by a specific epoch id is asserting it's present.
+    fn index(&self, id: StacksEpochId) -> &StacksEpoch {
+        self.get(id)
+            .unwrap_or_else(|| panic!("FATAL: no such epoch configured: {:?}", id))
+    }
+}
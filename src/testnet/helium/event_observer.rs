@@ -0,0 +1,137 @@
+// `RunLoop` used to notify consumers through three `Option<fn(...)>` pointers
+// (`new_burnchain_state_callback`/`new_tenure_callback`/`new_chain_state_callback`), each settable
+// once via `apply_on_new_*`. Plain `fn` pointers can't close over any state, and there was no way
+// to have more than one consumer, or for a consumer outside this process to observe the node at
+// all. `EventObserver` replaces that with an object a `RunLoop` can hold any number of (each
+// getting every event), and `HttpEventObserver` is the built-in implementation that turns each
+// event into JSON and POSTs it somewhere, for indexers/explorers/test harnesses to subscribe to
+// without recompiling the node.
+//
+// The POST itself is injected behind `EventPoster` (mirroring
+// `chainstate::burn::operations::signer::HidTransport` and
+// `testnet::helium::esplora_controller::EsploraTransport`'s role for their respective devices/
+// backends), so `HttpEventObserver`'s JSON-building and per-endpoint fan-out can be exercised
+// without a real HTTP/TLS stack.
+
+use chainstate::burn::BlockSnapshot;
+use chainstate::stacks::StacksBlock;
+use chainstate::stacks::db::StacksHeaderInfo;
+use chainstate::stacks::events::StacksTransactionReceipt;
+use chainstate::stacks::events_bloom::build_block_events_bloom;
+use util::hash::to_hex;
+
+use super::{BurnchainTip, Tenure};
+
+/// Something that wants to know about every burnchain block, tenure, and Stacks block this node
+/// produces or processes. Implementations must tolerate being called from the runloop's own
+/// thread on every round, so anything expensive (a slow network call, a lock shared with the rest
+/// of the node) should be handed off rather than done inline.
+pub trait EventObserver {
+    /// A new burnchain block was processed, whether or not it contained a sortition.
+    fn on_burnchain_block(&self, round_index: u64, tip: &BurnchainTip);
+
+    /// This node is starting a new tenure (whether or not it ultimately wins sortition for it).
+    fn on_new_tenure(&self, round_index: u64, tenure: &Tenure);
+
+    /// A new Stacks block was committed, along with the receipts for every transaction in it.
+    fn on_stacks_block(
+        &self,
+        round_index: u64,
+        chain_tip: &StacksBlock,
+        chain_tip_info: &StacksHeaderInfo,
+        receipts: &[StacksTransactionReceipt],
+    );
+}
+
+/// One HTTP request an `HttpEventObserver` needs to make: `POST body to url`. The real
+/// implementation would wrap e.g. the `reqwest` crate's blocking client; this trait exists so
+/// `HttpEventObserver`'s event-to-JSON mapping and per-endpoint dispatch can be tested against a
+/// fake that just records what it was asked to send.
+pub trait EventPoster {
+    fn post_json(&self, url: &str, body: &serde_json::Value);
+}
+
+fn burnchain_block_json(round_index: u64, snapshot: &BlockSnapshot) -> serde_json::Value {
+    json!({
+        "event": "burnchain_block",
+        "round_index": round_index,
+        "block_height": snapshot.block_height,
+        "burn_header_hash": format!("{}", snapshot.burn_header_hash),
+        "parent_burn_header_hash": format!("{}", snapshot.parent_burn_header_hash),
+        "consensus_hash": format!("{}", snapshot.consensus_hash),
+        "sortition": snapshot.sortition,
+        "num_sortitions": snapshot.num_sortitions,
+    })
+}
+
+fn new_tenure_json(round_index: u64, tenure: &Tenure) -> serde_json::Value {
+    json!({
+        "event": "new_tenure",
+        "round_index": round_index,
+        "vrf_seed": format!("{:?}", tenure.vrf_seed),
+    })
+}
+
+fn stacks_block_json(
+    round_index: u64,
+    chain_tip: &StacksBlock,
+    chain_tip_info: &StacksHeaderInfo,
+    receipts: &[StacksTransactionReceipt],
+) -> serde_json::Value {
+    // One Bloom filter over every event this block's transactions produced, so a client can test
+    // "could this block contain anything touching this contract/asset?" before fetching
+    // `receipts` in full -- see `chainstate::stacks::events_bloom` for the filter construction.
+    let events_bloom = build_block_events_bloom(
+        &receipts.iter().flat_map(|r| r.events.clone()).collect::<Vec<_>>(),
+    );
+    let receipts: Vec<serde_json::Value> = receipts.iter().map(|r| r.json_serialize()).collect();
+    json!({
+        "event": "stacks_block",
+        "round_index": round_index,
+        "block_height": chain_tip_info.block_height,
+        "index_block_hash": format!("{}", chain_tip_info.index_block_hash()),
+        "num_transactions": chain_tip.txs.len(),
+        "receipts": receipts,
+        "events_bloom": to_hex(&events_bloom.to_bytes()),
+    })
+}
+
+/// Posts every event, serialized to JSON, to each of `endpoints` in turn. A slow or unreachable
+/// endpoint can't block the others or the runloop beyond `poster`'s own error handling -- this
+/// type only decides *what* to send and *where*, not how to recover from a failed delivery.
+pub struct HttpEventObserver<P: EventPoster> {
+    endpoints: Vec<String>,
+    poster: P,
+}
+
+impl<P: EventPoster> HttpEventObserver<P> {
+    pub fn new(endpoints: Vec<String>, poster: P) -> HttpEventObserver<P> {
+        HttpEventObserver { endpoints, poster }
+    }
+
+    fn broadcast(&self, body: serde_json::Value) {
+        for endpoint in self.endpoints.iter() {
+            self.poster.post_json(endpoint, &body);
+        }
+    }
+}
+
+impl<P: EventPoster> EventObserver for HttpEventObserver<P> {
+    fn on_burnchain_block(&self, round_index: u64, tip: &BurnchainTip) {
+        self.broadcast(burnchain_block_json(round_index, &tip.block_snapshot));
+    }
+
+    fn on_new_tenure(&self, round_index: u64, tenure: &Tenure) {
+        self.broadcast(new_tenure_json(round_index, tenure));
+    }
+
+    fn on_stacks_block(
+        &self,
+        round_index: u64,
+        chain_tip: &StacksBlock,
+        chain_tip_info: &StacksHeaderInfo,
+        receipts: &[StacksTransactionReceipt],
+    ) {
+        self.broadcast(stacks_block_json(round_index, chain_tip, chain_tip_info, receipts));
+    }
+}
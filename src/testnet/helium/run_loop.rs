@@ -1,25 +1,44 @@
 use super::{Config, Node, BurnchainController, MockBurnchainController, BitcoinRegtestController, BurnchainTip, Tenure};
 
-use super::tenure::TenureArtifacts;
+use super::tenure::{TenureArtifacts, TenureStrategy};
+use super::event_observer::EventObserver;
+
+use std::thread;
 
 use burnchains::Address;
 use burnchains::bitcoin::BitcoinNetworkType;
 use chainstate::burn::{ConsensusHash};
-use chainstate::stacks::db::{StacksHeaderInfo, StacksChainState, ClarityTx};
+use chainstate::stacks::db::{StacksHeaderInfo, ClarityTx};
 use chainstate::burn::{BlockHeaderHash};
 use chainstate::stacks::{StacksBlock, TransactionAuth, TransactionSpendingCondition, SinglesigSpendingCondition, TransactionPayload};
 use chainstate::stacks::events::StacksTransactionReceipt;
 
 use util::sleep_ms;
 
+// NOTE: this tree carries no source for `testnet::helium::mod`, so `event_observer` (like
+// `esplora_controller` before it) has no `mod.rs` to declare `pub mod event_observer;` in. A real
+// checkout would add that line next to the rest of this directory's module declarations.
+//
+// NOTE: `testnet::helium::node` (the `Node` type itself) is likewise dangling in this tree -- it's
+// only ever referenced, never defined. `CANDIDATE_STRATEGIES`/`run_candidate_tenures` below assume
+// `Node` grows an `initiate_candidate_tenures(&last_sortitioned_block, strategies: &[TenureStrategy])
+// -> Vec<Tenure>` method mirroring its existing `initiate_new_tenure`, just building one `Tenure`
+// per requested strategy instead of one fixed one.
+
+/// The set of strategies every candidate tenure is run with each round; `run_candidate_tenures`
+/// keeps whichever one's artifacts score highest. See `TenureStrategy` for what each one does.
+const CANDIDATE_STRATEGIES: [TenureStrategy; 2] = [TenureStrategy::Fifo, TenureStrategy::LowestNonceFirst];
+
 /// RunLoop is coordinating a simulated burnchain and some simulated nodes
 /// taking turns in producing blocks.
 pub struct RunLoop {
     config: Config,
     node: Node,
-    new_burnchain_state_callback: Option<fn(u64, &BurnchainTip)>,
-    new_tenure_callback: Option<fn(u64, &Tenure)>,
-    new_chain_state_callback: Option<fn(u64, &mut StacksChainState, StacksBlock, StacksHeaderInfo, Vec<StacksTransactionReceipt>)>,
+    /// Every subscriber registered through `add_observer`, notified of every event in the order
+    /// they were registered. Replaces the old single-slot `Option<fn(...)>` callbacks: plain `fn`
+    /// pointers couldn't capture state (e.g. an HTTP client or a test harness's channel) and only
+    /// ever allowed one subscriber per event kind.
+    observers: Vec<Box<dyn EventObserver>>,
 }
 
 #[allow(unused_macros)]
@@ -56,9 +75,7 @@ impl RunLoop {
         Self {
             config,
             node,
-            new_burnchain_state_callback: None,
-            new_tenure_callback: None,
-            new_chain_state_callback: None,
+            observers: vec![],
         }
     }
 
@@ -105,7 +122,7 @@ impl RunLoop {
             None => panic!("Error while initiating genesis tenure")
         };
 
-        RunLoop::handle_new_tenure_cb(&self.new_tenure_callback, round_index, &first_tenure);
+        RunLoop::notify_new_tenure(&self.observers, round_index, &first_tenure);
 
         // Run the tenure, keep the artifacts
         let artifacts_from_1st_tenure = match first_tenure.run() {
@@ -124,9 +141,9 @@ impl RunLoop {
             artifacts_from_1st_tenure.burn_fee);
 
         let mut burnchain_state = burnchain.sync();
-        RunLoop::handle_burnchain_state_cb(&self.new_burnchain_state_callback, round_index, &burnchain_state);
+        RunLoop::notify_burnchain_block(&self.observers, round_index, &burnchain_state);
 
-        let mut leader_tenure = None;
+        let mut leader_tenures: Vec<Tenure> = vec![];
 
         // Have each node process the new block, that should include a sortition thanks to the
         // 1st tenure.
@@ -145,11 +162,12 @@ impl RunLoop {
             artifacts_from_1st_tenure.microblocks.clone(),
             burnchain.burndb_mut());
 
-        RunLoop::handle_new_chain_state_cb(&self.new_chain_state_callback, round_index, &mut self.node.chain_state, chain_tip, chain_tip_info, receipts);
+        RunLoop::notify_new_chain_state(&self.observers, round_index, chain_tip, chain_tip_info, receipts);
 
-        // If the node we're looping on won the sortition, initialize and configure the next tenure
+        // If the node we're looping on won the sortition, initialize and configure the next round's
+        // candidate tenures -- one per strategy in `CANDIDATE_STRATEGIES`.
         if won_sortition {
-            leader_tenure = self.node.initiate_new_tenure(&last_sortitioned_block);
+            leader_tenures = self.node.initiate_candidate_tenures(&last_sortitioned_block, &CANDIDATE_STRATEGIES);
         }
 
         // Start the runloop
@@ -159,14 +177,9 @@ impl RunLoop {
                 return;
             }
 
-            // Run the last initialized tenure
-            let artifacts_from_tenure = match leader_tenure {
-                Some(mut tenure) => {
-                    RunLoop::handle_new_tenure_cb(&self.new_tenure_callback, round_index, &tenure);
-                    tenure.run()
-                },
-                None => None
-            };
+            // Run every candidate tenure initialized for this round concurrently, and keep
+            // whichever one's artifacts score highest.
+            let artifacts_from_tenure = RunLoop::run_candidate_tenures(&self.observers, round_index, leader_tenures);
 
             match artifacts_from_tenure {
                 Some(ref artifacts) => {
@@ -181,9 +194,23 @@ impl RunLoop {
             }
 
             burnchain_state = burnchain.sync();
-            RunLoop::handle_burnchain_state_cb(&self.new_burnchain_state_callback, round_index, &burnchain_state);
-    
-            leader_tenure = None;
+            RunLoop::notify_burnchain_block(&self.observers, round_index, &burnchain_state);
+
+            leader_tenures = vec![];
+
+            // A `reorg_depth > 0` means the burnchain state we just synced no longer extends what
+            // we last saw -- the controller's header chain already rolled itself back to the fork
+            // point and re-downloaded the new branch (see `EsploraBurnchainController::sync_to_tip`),
+            // but `artifacts_from_tenure`/the sortition this round would otherwise process were
+            // built against the now-orphaned branch. Drop them and let the next iteration's
+            // `burnchain.sync()` pick up from the fork point instead of processing stale state or
+            // panicking below.
+            if burnchain_state.reorg_depth > 0 {
+                info_yellow!("Burnchain reorg of depth {} detected at round {} -- discarding in-flight tenure", burnchain_state.reorg_depth, round_index);
+                round_index += 1;
+                sleep_ms(self.config.burnchain.block_time);
+                continue;
+            }
 
             // Have each node process the new block, that can include, or not, a sortition.
             let (last_sortitioned_block, won_sortition) = match self.node.process_burnchain_state(&burnchain_state) {
@@ -204,10 +231,9 @@ impl RunLoop {
                         artifacts.microblocks.clone(),
                         burnchain.burndb_mut());
 
-                    RunLoop::handle_new_chain_state_cb(
-                        &self.new_chain_state_callback, 
+                    RunLoop::notify_new_chain_state(
+                        &self.observers,
                         round_index,
-                        &mut self.node.chain_state,
                         chain_tip,
                         chain_tip_info,
                         events
@@ -215,10 +241,11 @@ impl RunLoop {
                 },
             };
             
-            // If the node we're looping on won the sortition, initialize and configure the next tenure
+            // If the node we're looping on won the sortition, initialize and configure the next
+            // round's candidate tenures -- one per strategy in `CANDIDATE_STRATEGIES`.
             if won_sortition {
-                leader_tenure = self.node.initiate_new_tenure(&last_sortitioned_block);
-            } 
+                leader_tenures = self.node.initiate_candidate_tenures(&last_sortitioned_block, &CANDIDATE_STRATEGIES);
+            }
             
             round_index += 1;
 
@@ -226,46 +253,67 @@ impl RunLoop {
         }
     }
 
-    pub fn apply_on_new_burnchain_states(&mut self, f: fn(u64, &BurnchainTip)) {
-        self.new_burnchain_state_callback = Some(f);
+    /// Registers a subscriber to be notified of every burnchain block, tenure, and Stacks block
+    /// from here on. Unlike the old `apply_on_new_*` setters, any number of observers can be
+    /// registered, and each is notified in registration order.
+    pub fn add_observer(&mut self, observer: Box<dyn EventObserver>) {
+        self.observers.push(observer);
     }
 
-    pub fn apply_on_new_tenures(&mut self, f: fn(u64, &Tenure)) {
-        self.new_tenure_callback = Some(f);
-    }
-    
-    pub fn apply_on_new_chain_states(&mut self, f: fn(u64, &mut StacksChainState, StacksBlock, StacksHeaderInfo, Vec<StacksTransactionReceipt>)) {
-        self.new_chain_state_callback = Some(f);
+    /// Runs every candidate tenure for this round concurrently, each in its own thread -- the
+    /// per-tenure chainstate isolation `Tenure::run` opens its own `StacksChainState` handle for
+    /// is exactly what makes this safe -- and returns the artifacts of whichever one scored
+    /// highest via `TenureArtifacts::score`. A tenure that fails to produce artifacts (`run`
+    /// returning `None`) is simply excluded from the comparison; if every candidate fails (or
+    /// `tenures` was empty, e.g. because the last round didn't win a sortition), this returns
+    /// `None`, same as the old single-tenure path did.
+    fn run_candidate_tenures(observers: &[Box<dyn EventObserver>], round_index: u64, tenures: Vec<Tenure>) -> Option<TenureArtifacts> {
+        let handles: Vec<_> = tenures
+            .into_iter()
+            .map(|mut tenure| {
+                RunLoop::notify_new_tenure(observers, round_index, &tenure);
+                thread::spawn(move || tenure.run())
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().expect("candidate tenure thread panicked"))
+            .max_by_key(TenureArtifacts::score)
     }
 
-    fn handle_new_tenure_cb(new_tenure_callback: &Option<fn(u64, &Tenure)>,
-                            round_index: u64, tenure: &Tenure) {
+    fn notify_new_tenure(observers: &[Box<dyn EventObserver>], round_index: u64, tenure: &Tenure) {
         info_yellow!("Node starting new tenure with VRF {:?}", tenure.vrf_seed);
-        new_tenure_callback.map(|cb| cb(round_index, tenure));
+        for observer in observers.iter() {
+            observer.on_new_tenure(round_index, tenure);
+        }
     }
 
-    fn handle_burnchain_state_cb(burn_callback: &Option<fn(u64, &BurnchainState)>,
-                                 round_index: u64, state: &BurnchainState) {
-        info_blue!("Burnchain block #{} ({}) was produced with sortition #{}", state.chain_tip.block_height, state.chain_tip.burn_header_hash, state.chain_tip.sortition_hash);
-        burn_callback.map(|cb| cb(round_index, state));
+    fn notify_burnchain_block(observers: &[Box<dyn EventObserver>], round_index: u64, tip: &BurnchainTip) {
+        info_blue!("Burnchain block #{} ({}) was produced with sortition #{}", tip.block_snapshot.block_height, tip.block_snapshot.burn_header_hash, tip.block_snapshot.sortition_hash);
+        for observer in observers.iter() {
+            observer.on_burnchain_block(round_index, tip);
+        }
     }
 
-    fn handle_new_chain_state_cb(chain_state_callback: &Option<fn(u64, &mut StacksChainState, StacksBlock, StacksHeaderInfo, Vec<StacksTransactionReceipt>)>,
-                                 round_index: u64, state: &mut StacksChainState, chain_tip: StacksBlock, chain_tip_info: StacksHeaderInfo, receipts: Vec<StacksTransactionReceipt>) {
+    fn notify_new_chain_state(observers: &[Box<dyn EventObserver>],
+                               round_index: u64, chain_tip: StacksBlock, chain_tip_info: StacksHeaderInfo, receipts: Vec<StacksTransactionReceipt>) {
         info_green!("Stacks block #{} ({}) successfully produced, including {} transactions", chain_tip_info.block_height, chain_tip_info.index_block_hash(), chain_tip.txs.len());
         for tx in chain_tip.txs.iter() {
-            match &tx.auth {            
+            match &tx.auth {
                 TransactionAuth::Standard(TransactionSpendingCondition::Singlesig(auth)) => println!("-> Tx issued by {:?} (fee: {}, nonce: {})", auth.signer, auth.fee_rate, auth.nonce),
                 _ => println!("-> Tx {:?}", tx.auth)
             }
-            match &tx.payload { 
+            match &tx.payload {
                 TransactionPayload::Coinbase(_) => println!("   Coinbase"),
                 TransactionPayload::SmartContract(contract) => println!("   Publish smart contract\n**************************\n{:?}\n**************************", contract.code_body),
                 TransactionPayload::TokenTransfer(recipent, amount, _) => println!("   Transfering {} µSTX to {}", amount, recipent.to_string()),
                 _ => println!("   {:?}", tx.payload)
             }
         }
-        chain_state_callback.map(|cb| cb(round_index, state, chain_tip, chain_tip_info, receipts));
+        for observer in observers.iter() {
+            observer.on_stacks_block(round_index, &chain_tip, &chain_tip_info, &receipts);
+        }
     }
 
 }
@@ -8,8 +8,81 @@ use burnchains::{BurnchainHeaderHash, Txid};
 use chainstate::stacks::db::{StacksChainState, StacksHeaderInfo, ClarityTx};
 use chainstate::stacks::{StacksPrivateKey, StacksBlock, StacksWorkScore, StacksAddress, StacksTransactionSigner, StacksTransaction, TransactionVersion, StacksMicroblock, CoinbasePayload, StacksBlockBuilder, TransactionAnchorMode};
 use chainstate::stacks::{MINER_BLOCK_BURN_HEADER_HASH, MINER_BLOCK_HEADER_HASH};
+use chainstate::stacks::{TransactionAuth, TransactionSpendingCondition};
+use chainstate::stacks::{MAX_BLOCK_SIZE, MAX_MICROBLOCK_SIZE};
 use chainstate::burn::{VRFSeed, BlockHeaderHash};
 use util::vrf::{VRFProof};
+use net::StacksMessageCodec;
+
+/// A distinct mempool-selection policy a tenure can try when building its candidate block, so
+/// `RunLoop` can run several `Tenure`s concurrently against the same sortition and keep whichever
+/// produced the best-scoring block (see `TenureArtifacts::score`) -- this is the "try different
+/// strategies" the per-tenure chainstate isolation above was built to support, but that nothing
+/// previously made use of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TenureStrategy {
+    /// Mine the mempool highest fee-rate first, in the order `cmp_fee_rate_desc` gives.
+    Fifo,
+    /// Mine lowest-nonce transactions first, favoring transactions that are immediately
+    /// executable (no preceding nonce gap in their account) over ones further out in an account's
+    /// nonce sequence.
+    LowestNonceFirst,
+}
+
+/// The nonce a transaction's spending condition is authorized against, used by
+/// `TenureStrategy::LowestNonceFirst` to order the mempool. Sponsored transactions are ordered by
+/// the paying (second) condition's nonce, since that's the account whose nonce sequence actually
+/// gates whether the transaction can be mined yet.
+fn tx_nonce(tx: &StacksTransaction) -> u64 {
+    let condition = match &tx.auth {
+        TransactionAuth::Standard(cond) => cond,
+        TransactionAuth::Sponsored(_, payer_cond) => payer_cond,
+    };
+    match condition {
+        TransactionSpendingCondition::Singlesig(cond) => cond.nonce,
+        TransactionSpendingCondition::Multisig(cond) => cond.nonce,
+    }
+}
+
+/// `tx`'s fee, in microSTX, and its estimated cost -- this tree has no per-transaction Clarity
+/// execution-cost estimate to sort by, so the transaction's own serialized length (at least 1)
+/// stands in for it.
+fn fee_and_cost(tx: &StacksTransaction) -> (u64, u64) {
+    let fee = tx.fee.as_microstx().unwrap_or(0);
+    let cost = tx.serialize().len() as u64;
+    (fee, cost.max(1))
+}
+
+/// Orders `a` ahead of `b` when `a`'s fee-per-cost rate is higher, via cross-multiplication so the
+/// comparison never has to divide (and round) two u64s against each other.
+fn cmp_fee_rate_desc(a: &StacksTransaction, b: &StacksTransaction) -> std::cmp::Ordering {
+    let (fee_a, cost_a) = fee_and_cost(a);
+    let (fee_b, cost_b) = fee_and_cost(b);
+    ((fee_a as u128) * (cost_b as u128)).cmp(&((fee_b as u128) * (cost_a as u128))).reverse()
+}
+
+/// Greedily partitions `sorted_txs` (already in mining order) into the transactions that fit within `cost_budget` total estimated cost and `fee_budget` total fee, and the remainder --
+/// skipping (rather than erroring on) any transaction whose inclusion would overflow either
+/// budget, so a single oversized transaction doesn't block everything sorted behind it.
+fn select_within_budget(sorted_txs: Vec<StacksTransaction>, cost_budget: u64, fee_budget: u64) -> (Vec<StacksTransaction>, Vec<StacksTransaction>, u64) {
+    let mut selected = vec![];
+    let mut leftover = vec![];
+    let mut used_cost: u64 = 0;
+    let mut used_fee: u64 = 0;
+
+    for tx in sorted_txs {
+        let (fee, cost) = fee_and_cost(&tx);
+        if used_cost.saturating_add(cost) > cost_budget || used_fee.saturating_add(fee) > fee_budget {
+            leftover.push(tx);
+            continue;
+        }
+        used_cost += cost;
+        used_fee += fee;
+        selected.push(tx);
+    }
+
+    (selected, leftover, used_fee)
+}
 
 pub struct TenureArtifacts {
     pub anchored_block: StacksBlock,
@@ -18,6 +91,17 @@ pub struct TenureArtifacts {
     pub burn_fee: u64
 }
 
+impl TenureArtifacts {
+    /// Ranks this candidate block for `RunLoop`'s highest-scoring-wins selection among
+    /// concurrently-run tenures: `burn_fee` dominates the score since it's the actual cost the
+    /// miner is committing to pay for this block (now the real fees `run` accumulated while
+    /// filling the block, not just a passthrough of the cap), with transaction count as a
+    /// tie-breaker.
+    pub fn score(&self) -> u64 {
+        self.burn_fee * 1_000 + self.anchored_block.txs.len() as u64
+    }
+}
+
 pub struct Tenure {
     average_block_time: u64,
     block_builder: StacksBlockBuilder,
@@ -25,23 +109,26 @@ pub struct Tenure {
     config: Config,
     last_sortitioned_block: SortitionedBlock,
     pub mem_pool: MemPoolFS,
+    microblock_secret_key: StacksPrivateKey,
     parent_block: StacksHeaderInfo,
     started_at: std::time::Instant,
     pub vrf_seed: VRFSeed,
     burn_fee_cap: u64,
+    strategy: TenureStrategy,
 }
 
 impl <'a> Tenure {
 
-    pub fn new(parent_block: StacksHeaderInfo, 
+    pub fn new(parent_block: StacksHeaderInfo,
                average_block_time: u64,
                coinbase_tx: StacksTransaction,
                config: Config,
                mem_pool: MemPoolFS,
-               microblock_secret_key: StacksPrivateKey,  
+               microblock_secret_key: StacksPrivateKey,
                last_sortitioned_block: SortitionedBlock,
                vrf_proof: VRFProof,
-               burn_fee_cap: u64) -> Tenure {
+               burn_fee_cap: u64,
+               strategy: TenureStrategy) -> Tenure {
 
         let now = time::Instant::now();
 
@@ -62,10 +149,12 @@ impl <'a> Tenure {
             config,
             last_sortitioned_block,
             mem_pool,
+            microblock_secret_key,
             parent_block,
             started_at: now,
             vrf_seed: VRFSeed::from_proof(&vrf_proof),
             burn_fee_cap,
+            strategy,
         }
     }
 
@@ -79,29 +168,83 @@ impl <'a> Tenure {
         }
     }
 
+    /// Packs as many of `leftover` (already in mining order) as fit under `MAX_MICROBLOCK_SIZE`
+    /// into one signed `StacksMicroblock` chained after `prev_block`, returning the microblock
+    /// and the unused remainder of `leftover`. Returns `None` with `leftover` untouched if
+    /// nothing fits; if signing fails it returns `None` with only that remainder, so the
+    /// transactions picked for the microblock are dropped.
+    fn stream_one_microblock(&self, sequence: u32, prev_block: BlockHeaderHash, leftover: Vec<StacksTransaction>) -> (Option<StacksMicroblock>, Vec<StacksTransaction>) {
+        let (batch, rest, _fee) = select_within_budget(leftover, MAX_MICROBLOCK_SIZE as u64, u64::max_value());
+        if batch.is_empty() {
+            return (None, rest);
+        }
+        match StacksMicroblock::from_txs(sequence, prev_block, batch, &self.microblock_secret_key) {
+            Ok(microblock) => (Some(microblock), rest),
+            Err(e) => {
+                error!("Failed to sign streamed microblock - {:?}", e);
+                (None, rest)
+            }
+        }
+    }
+
     pub fn run(&mut self) -> Option<TenureArtifacts> {
 
         let mut chain_state = StacksChainState::open(
-            false, 
-            TESTNET_CHAIN_ID, 
+            false,
+            TESTNET_CHAIN_ID,
             &self.config.get_chainstate_path()).unwrap();
 
         let mut clarity_tx = self.block_builder.epoch_begin(&mut chain_state).unwrap();
 
         self.handle_txs(&mut clarity_tx, vec![self.coinbase_tx.clone()]);
 
-        let txs = self.mem_pool.poll();
-        self.handle_txs(&mut clarity_tx, txs);
+        let mut txs = self.mem_pool.poll();
+        if self.strategy == TenureStrategy::LowestNonceFirst {
+            txs.sort_by_key(tx_nonce);
+        } else {
+            txs.sort_by(cmp_fee_rate_desc);
+        }
+
+        // Fill the anchored block's cost/size budget in the order chosen above (highest fee-rate
+        // first, or lowest nonce first under `LowestNonceFirst`), stopping once either the block's length budget or `burn_fee_cap` is reached --
+        // a transaction that would overflow either is skipped rather than aborting the rest of
+        // the selection, so it doesn't block lower-fee transactions sorted behind it.
+        let (anchored_txs, mut leftover_txs, accumulated_fee) =
+            select_within_budget(txs, MAX_BLOCK_SIZE as u64, self.burn_fee_cap);
+
+        self.handle_txs(&mut clarity_tx, anchored_txs);
 
         let anchored_block = self.block_builder.mine_anchored_block(&mut clarity_tx);
 
         clarity_tx.rollback_block();
 
+        // Stream whatever didn't fit in the anchored block out as microblocks, one per call to
+        // `stream_one_microblock`, until either the leftover queue is empty or this tenure has
+        // used up its share of `average_block_time` -- a later tenure (or the next sortition's
+        // anchored block) picks up anything still left over at that point.
+        let mut microblocks = vec![];
+        let mut prev_block = anchored_block.header.block_hash();
+        let mut sequence: u32 = 0;
+        let budget = time::Duration::from_millis(self.average_block_time);
+
+        while !leftover_txs.is_empty() && self.started_at.elapsed() < budget {
+            let (microblock, rest) = self.stream_one_microblock(sequence, prev_block, leftover_txs);
+            leftover_txs = rest;
+            match microblock {
+                Some(microblock) => {
+                    prev_block = microblock.header.block_hash();
+                    sequence += 1;
+                    microblocks.push(microblock);
+                }
+                None => break,
+            }
+        }
+
         let artifact = TenureArtifacts {
             anchored_block,
-            microblocks: vec![],
+            microblocks,
             parent_block: self.last_sortitioned_block.clone(),
-            burn_fee: self.burn_fee_cap
+            burn_fee: accumulated_fee
         };
         Some(artifact)
     }
@@ -4,10 +4,12 @@ use std::str::FromStr;
 
 use curve25519_dalek::digest::Digest;
 use sha2::Sha512Trunc256;
+use once_cell::sync::OnceCell;
 
 use util::hash::{Hash160, HASH160_ENCODED_SIZE, Sha512Trunc256Sum, to_hex};
 use util::secp256k1::MessageSignature;
 use util::vrf::VRFProof;
+use chainstate::burn::ConsensusHash;
 
 #[derive(Serialize, Deserialize)]
 pub struct BurnchainHeaderHash(pub [u8; 32]);
@@ -46,45 +48,77 @@ pub const VRF_SEED_ENCODED_SIZE: u32 = 32;
 ///  outside of the PoX DB, however, they are sufficient
 ///  to uniquely identify a "sortition" when paired with
 ///  a burn header hash
-// TODO: Vec<bool> is an aggressively unoptimized implementation,
-//       replace with a real bitvec
+///
+/// Backed by a packed `Vec<u64>` word store rather than one `bool` per bit: bit `i` lives in
+/// word `i / 64` at offset `i % 64`. This is ~8x smaller and faster to `clone`/compare than the
+/// one-byte-per-bit layout it replaces, which matters since `PoxId`s are cloned and compared on
+/// every reward-cycle inventory exchange.
 #[derive(Clone, Debug, PartialEq)]
-pub struct PoxId(Vec<bool>);
+pub struct PoxId {
+    words: Vec<u64>,
+    bit_len: usize,
+}
 
 impl PoxId {
     pub fn new(contents: Vec<bool>) -> Self {
-        PoxId(contents)
+        let mut pox_id = PoxId::stubbed();
+        for bit in contents {
+            if bit {
+                pox_id.extend_with_present_block();
+            } else {
+                pox_id.extend_with_not_present_block();
+            }
+        }
+        pox_id
     }
 
     pub fn initial() -> PoxId {
-        PoxId(vec![true])
+        PoxId::new(vec![true])
     }
 
     pub fn from_bools(bools: Vec<bool>) -> PoxId {
-        PoxId(bools)
+        PoxId::new(bools)
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        if self.bit_len % 64 == 0 {
+            self.words.push(0);
+        }
+        if bit {
+            let word_idx = self.bit_len / 64;
+            let offset = self.bit_len % 64;
+            self.words[word_idx] |= 1u64 << offset;
+        }
+        self.bit_len += 1;
     }
 
     pub fn extend_with_present_block(&mut self) {
-        self.0.push(true);
+        self.push_bit(true);
     }
     pub fn extend_with_not_present_block(&mut self) {
-        self.0.push(false);
+        self.push_bit(false);
     }
 
     pub fn stubbed() -> PoxId {
-        PoxId(vec![])
+        PoxId { words: vec![], bit_len: 0 }
+    }
+
+    fn get_bit(&self, i: usize) -> bool {
+        let word_idx = i / 64;
+        let offset = i % 64;
+        (self.words[word_idx] >> offset) & 1 == 1
     }
 
     pub fn has_ith_anchor_block(&self, i: usize) -> bool {
-        if i >= self.0.len() {
+        if i >= self.bit_len {
             false
         } else {
-            self.0[i]
+            self.get_bit(i)
         }
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.bit_len
     }
 
     pub fn bit_slice(&self, start: usize, len: usize) -> (Vec<u8>, u64) {
@@ -100,7 +134,7 @@ impl PoxId {
             }
 
             let sz = ret.len() - 1;
-            if self.0[bit] {
+            if self.get_bit(bit) {
                 ret[sz] |= 1 << (i % 8);
             }
             count += 1;
@@ -109,14 +143,14 @@ impl PoxId {
     }
 
     pub fn num_inventory_reward_cycles(&self) -> usize {
-        self.0.len().saturating_sub(1)
+        self.bit_len.saturating_sub(1)
     }
 }
 
 impl fmt::Display for PoxId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for val in self.0.iter() {
-            write!(f, "{}", if *val { 1 } else { 0 })?;
+        for i in 0..self.bit_len {
+            write!(f, "{}", if self.get_bit(i) { 1 } else { 0 })?;
         }
         Ok(())
     }
@@ -146,17 +180,173 @@ impl_byte_array_serde!(TrieHash);
 pub const TRIEHASH_ENCODED_SIZE: usize = 32;
 
 /// The header for an on-chain-anchored Stacks block
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// `block_hash_cache`/`index_block_hash_cache` memoize `block_hash()`/`index_block_hash()` so
+/// relay, mempool admission, and the try-miner loop -- all of which call these several times per
+/// block -- don't each re-serialize and re-hash the header. `OnceCell` is populated lazily on
+/// first call and skipped entirely by `Serialize`/`Deserialize`, so wire and disk formats are
+/// unaffected. The cache is only sound if a header can never be mutated once built, so every
+/// data field is private and `StacksBlockHeader::new` is the only way to construct one --
+/// there's no setter that could desync the cache from the fields it was computed over.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StacksBlockHeader {
-    pub version: u8,
-    pub total_work: StacksWorkScore, // NOTE: this is the work done on the chain tip this block builds on (i.e. take this from the parent)
-    pub proof: VRFProof,
-    pub parent_block: BlockHeaderHash, // NOTE: even though this is also present in the burn chain, we need this here for super-light clients that don't even have burn chain headers
-    pub parent_microblock: BlockHeaderHash,
-    pub parent_microblock_sequence: u16,
-    pub tx_merkle_root: Sha512Trunc256Sum,
-    pub state_index_root: TrieHash,
-    pub microblock_pubkey_hash: Hash160, // we'll get the public key back from the first signature (note that this is the Hash160 of the _compressed_ public key)
+    version: u8,
+    total_work: StacksWorkScore, // NOTE: this is the work done on the chain tip this block builds on (i.e. take this from the parent)
+    proof: VRFProof,
+    parent_block: BlockHeaderHash, // NOTE: even though this is also present in the burn chain, we need this here for super-light clients that don't even have burn chain headers
+    parent_microblock: BlockHeaderHash,
+    parent_microblock_sequence: u16,
+    tx_merkle_root: Sha512Trunc256Sum,
+    state_index_root: TrieHash,
+    microblock_pubkey_hash: Hash160, // we'll get the public key back from the first signature (note that this is the Hash160 of the _compressed_ public key)
+    #[serde(skip, default = "OnceCell::new")]
+    block_hash_cache: OnceCell<BlockHeaderHash>,
+    #[serde(skip, default = "OnceCell::new")]
+    index_block_hash_cache: OnceCell<(ConsensusHash, StacksBlockId)>,
+}
+
+impl StacksBlockHeader {
+    /// The only way to build a `StacksBlockHeader`: every field is set up front, so
+    /// `block_hash()`/`index_block_hash()` can safely memoize against fields that can never
+    /// change out from under the cache afterwards.
+    pub fn new(
+        version: u8,
+        total_work: StacksWorkScore,
+        proof: VRFProof,
+        parent_block: BlockHeaderHash,
+        parent_microblock: BlockHeaderHash,
+        parent_microblock_sequence: u16,
+        tx_merkle_root: Sha512Trunc256Sum,
+        state_index_root: TrieHash,
+        microblock_pubkey_hash: Hash160,
+    ) -> StacksBlockHeader {
+        StacksBlockHeader {
+            version,
+            total_work,
+            proof,
+            parent_block,
+            parent_microblock,
+            parent_microblock_sequence,
+            tx_merkle_root,
+            state_index_root,
+            microblock_pubkey_hash,
+            block_hash_cache: OnceCell::new(),
+            index_block_hash_cache: OnceCell::new(),
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn total_work(&self) -> &StacksWorkScore {
+        &self.total_work
+    }
+
+    pub fn proof(&self) -> &VRFProof {
+        &self.proof
+    }
+
+    pub fn parent_block(&self) -> &BlockHeaderHash {
+        &self.parent_block
+    }
+
+    pub fn parent_microblock(&self) -> &BlockHeaderHash {
+        &self.parent_microblock
+    }
+
+    pub fn parent_microblock_sequence(&self) -> u16 {
+        self.parent_microblock_sequence
+    }
+
+    pub fn tx_merkle_root(&self) -> &Sha512Trunc256Sum {
+        &self.tx_merkle_root
+    }
+
+    pub fn state_index_root(&self) -> &TrieHash {
+        &self.state_index_root
+    }
+
+    pub fn microblock_pubkey_hash(&self) -> &Hash160 {
+        &self.microblock_pubkey_hash
+    }
+
+    /// The hash of this header's fields, memoized on first call.
+    pub fn block_hash(&self) -> BlockHeaderHash {
+        self.block_hash_cache
+            .get_or_init(|| {
+                let mut bytes = vec![self.version];
+                bytes.extend_from_slice(&self.total_work.burn.to_be_bytes());
+                bytes.extend_from_slice(&self.total_work.work.to_be_bytes());
+                bytes.extend_from_slice(&self.proof.to_bytes());
+                bytes.extend_from_slice(self.parent_block.as_bytes());
+                bytes.extend_from_slice(self.parent_microblock.as_bytes());
+                bytes.extend_from_slice(&self.parent_microblock_sequence.to_be_bytes());
+                bytes.extend_from_slice(self.tx_merkle_root.as_bytes());
+                bytes.extend_from_slice(self.state_index_root.as_bytes());
+                bytes.extend_from_slice(self.microblock_pubkey_hash.as_bytes());
+                let h = Sha512Trunc256Sum::from_data(&bytes);
+                BlockHeaderHash(h.0)
+            })
+            .clone()
+    }
+
+    /// The index block hash ties this header's `block_hash()` to the consensus hash of the
+    /// sortition that chose it. Memoized alongside `block_hash()` under the same
+    /// never-mutated-after-construction invariant, for the first `consensus_hash` it is called
+    /// with; a call with a different consensus hash is computed afresh.
+    pub fn index_block_hash(&self, consensus_hash: &ConsensusHash) -> StacksBlockId {
+        let (cached_consensus_hash, cached_id) = self.index_block_hash_cache.get_or_init(|| {
+            (consensus_hash.clone(), Self::make_index_block_hash(consensus_hash, &self.block_hash()))
+        });
+        if cached_consensus_hash == consensus_hash {
+            cached_id.clone()
+        } else {
+            Self::make_index_block_hash(consensus_hash, &self.block_hash())
+        }
+    }
+
+    /// `index_block_hash`, for callers that already have a `consensus_hash`/`block_hash` pair in
+    /// hand (e.g. from a stored header record) and don't have the full `StacksBlockHeader` around
+    /// to call the memoized instance method on.
+    pub fn make_index_block_hash(
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+    ) -> StacksBlockId {
+        let mut bytes = block_hash.as_bytes().to_vec();
+        bytes.extend_from_slice(consensus_hash.as_bytes());
+        let h = Sha512Trunc256Sum::from_data(&bytes);
+        StacksBlockId(h.0)
+    }
+}
+
+impl PartialEq for StacksBlockHeader {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.total_work == other.total_work
+            && self.proof == other.proof
+            && self.parent_block == other.parent_block
+            && self.parent_microblock == other.parent_microblock
+            && self.parent_microblock_sequence == other.parent_microblock_sequence
+            && self.tx_merkle_root == other.tx_merkle_root
+            && self.state_index_root == other.state_index_root
+            && self.microblock_pubkey_hash == other.microblock_pubkey_hash
+    }
+}
+impl Eq for StacksBlockHeader {}
+
+impl std::hash::Hash for StacksBlockHeader {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.version.hash(state);
+        self.total_work.hash(state);
+        self.proof.hash(state);
+        self.parent_block.hash(state);
+        self.parent_microblock.hash(state);
+        self.parent_microblock_sequence.hash(state);
+        self.tx_merkle_root.hash(state);
+        self.state_index_root.hash(state);
+        self.microblock_pubkey_hash.hash(state);
+    }
 }
 
 pub struct StacksBlockId(pub [u8; 32]);
@@ -167,13 +357,84 @@ impl_byte_array_from_column!(StacksBlockId);
 impl_byte_array_serde!(StacksBlockId);
 
 /// Header structure for a microblock
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// `block_hash_cache` memoizes `block_hash()` the same way and under the same invariant as
+/// `StacksBlockHeader::block_hash_cache` -- see that struct's doc comment. Every data field is
+/// likewise private, with `StacksMicroblockHeader::new` the only way to build one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StacksMicroblockHeader {
-    pub version: u8,
-    pub sequence: u16,
-    pub prev_block: BlockHeaderHash,
-    pub tx_merkle_root: Sha512Trunc256Sum,
-    pub signature: MessageSignature,
+    version: u8,
+    sequence: u16,
+    prev_block: BlockHeaderHash,
+    tx_merkle_root: Sha512Trunc256Sum,
+    signature: MessageSignature,
+    #[serde(skip, default = "OnceCell::new")]
+    block_hash_cache: OnceCell<BlockHeaderHash>,
+}
+
+impl StacksMicroblockHeader {
+    /// The only way to build a `StacksMicroblockHeader` -- see the struct's doc comment.
+    pub fn new(
+        version: u8,
+        sequence: u16,
+        prev_block: BlockHeaderHash,
+        tx_merkle_root: Sha512Trunc256Sum,
+        signature: MessageSignature,
+    ) -> StacksMicroblockHeader {
+        StacksMicroblockHeader {
+            version,
+            sequence,
+            prev_block,
+            tx_merkle_root,
+            signature,
+            block_hash_cache: OnceCell::new(),
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn sequence(&self) -> u16 {
+        self.sequence
+    }
+
+    pub fn prev_block(&self) -> &BlockHeaderHash {
+        &self.prev_block
+    }
+
+    pub fn tx_merkle_root(&self) -> &Sha512Trunc256Sum {
+        &self.tx_merkle_root
+    }
+
+    pub fn signature(&self) -> &MessageSignature {
+        &self.signature
+    }
+
+    /// The hash of this header's fields, memoized on first call.
+    pub fn block_hash(&self) -> BlockHeaderHash {
+        self.block_hash_cache
+            .get_or_init(|| {
+                let mut bytes = vec![self.version];
+                bytes.extend_from_slice(&self.sequence.to_be_bytes());
+                bytes.extend_from_slice(self.prev_block.as_bytes());
+                bytes.extend_from_slice(self.tx_merkle_root.as_bytes());
+                bytes.extend_from_slice(self.signature.as_bytes());
+                let h = Sha512Trunc256Sum::from_data(&bytes);
+                BlockHeaderHash(h.0)
+            })
+            .clone()
+    }
+}
+
+impl PartialEq for StacksMicroblockHeader {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.sequence == other.sequence
+            && self.prev_block == other.prev_block
+            && self.tx_merkle_root == other.tx_merkle_root
+            && self.signature == other.signature
+    }
 }
 
 #[derive(Debug)]
@@ -0,0 +1,622 @@
+// Note: `natives/lists.rs` and `natives/options.rs` alongside this file predate this pass and
+// target a different (newer) `TypeSignature` representation than the one in `vm::types` here
+// (`ListType`/`OptionalType`/`ResponseType` variants, `FunctionType::check_args`, etc., none of
+// which this tree's `vm::types` defines) -- they're left undeclared rather than wired in under a
+// `pub mod natives;` that would just fail to resolve against this era's types.
+
+use std::collections::BTreeMap;
+
+use vm::diagnostic::Diagnostic;
+use vm::representations::{SymbolicExpression, SymbolicExpressionType};
+use vm::types::{AtomTypeIdentifier, TupleTypeSignature, TypeSignature, Value};
+
+/// Why a `SymbolicExpression` tree failed static type-checking. This is a standalone error type
+/// rather than `vm::errors::Error` (the runtime error type) because these are compile-time
+/// rejections: a contract that fails here is never evaluated at all.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckErrors {
+    TypeMismatch(TypeSignature, TypeSignature),
+    UnboundVariable(String),
+    UnboundFunction(String),
+    NameAlreadyUsed(String),
+    NonFunctionApplication,
+    VoidTypeInList,
+    VoidTypeInTuple,
+    TupleDuplicateField(String),
+    IncorrectArgumentCount(usize, usize),
+    BadDefineForm,
+    BadTypeAnnotation(String),
+    /// Wraps a `vm::types` construction failure (e.g. a list too large, a malformed tuple) that
+    /// this pass surfaces as a static error instead of letting it happen at runtime.
+    TypeConstructionError(String),
+    /// A `(list ...)` literal whose elements don't unify to a common type. Carries a two-span
+    /// `Diagnostic` -- see `vm::diagnostic::render_diagnostic` -- pointing at the first element
+    /// (which establishes the expected type) and the first element that actually diverges from
+    /// it, rather than just naming the two conflicting types with no source context.
+    ListTypeMismatch(Diagnostic),
+    /// Two branches of a conditional that are required to agree on type don't -- e.g. `if`'s
+    /// `then`/`else`, or (in the newer-era `natives/options.rs`, not wired into this tree, see the
+    /// note at the top of this file) a `match`'s arms or a `default-to`'s default and unwrapped
+    /// value. Carries a two-span `Diagnostic` pointing at both disagreeing sub-expressions, the
+    /// same dual-span shape as `ListTypeMismatch`.
+    BranchTypesMustMatch(Diagnostic),
+}
+
+pub type CheckResult<T> = Result<T, CheckErrors>;
+pub type TypeResult = CheckResult<TypeSignature>;
+
+/// Require `args` to have exactly `expected` entries, as most special forms do.
+pub fn check_argument_count(expected: usize, args: &[SymbolicExpression]) -> CheckResult<()> {
+    if args.len() != expected {
+        Err(CheckErrors::IncorrectArgumentCount(expected, args.len()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Require `args` to have at least `expected` entries, for variadic forms like `+`/`and`.
+pub fn check_arguments_at_least(expected: usize, args: &[SymbolicExpression]) -> CheckResult<()> {
+    if args.len() < expected {
+        Err(CheckErrors::IncorrectArgumentCount(expected, args.len()))
+    } else {
+        Ok(())
+    }
+}
+
+/// A lexical scope stack for the type-checking pass: one `BTreeMap` per `let`/function body,
+/// innermost last. `extend()` pushes a fresh scope so bindings introduced there don't leak back
+/// out once checking returns to the caller -- mirroring how `LocalContext` nests at runtime.
+#[derive(Clone)]
+pub struct TypingContext {
+    scopes: Vec<BTreeMap<String, TypeSignature>>,
+}
+
+impl TypingContext {
+    pub fn new() -> TypingContext {
+        TypingContext {
+            scopes: vec![BTreeMap::new()],
+        }
+    }
+
+    pub fn extend(&self) -> TypingContext {
+        let mut scopes = self.scopes.clone();
+        scopes.push(BTreeMap::new());
+        TypingContext { scopes }
+    }
+
+    pub fn bind_variable(&mut self, name: String, type_sig: TypeSignature) {
+        self.scopes
+            .last_mut()
+            .expect("a TypingContext always has at least one scope")
+            .insert(name, type_sig);
+    }
+
+    pub fn lookup_variable_type(&self, name: &str) -> Option<&TypeSignature> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+}
+
+/// The static signature of a native or `define`d function: what each argument must admit, and
+/// what its call sites resolve to.
+#[derive(Clone)]
+pub struct FunctionSignature {
+    pub args: Vec<TypeSignature>,
+    pub returns: TypeSignature,
+}
+
+/// Bottom-up type-checking/inference pass over a contract's parsed `SymbolicExpression` tree,
+/// run ahead of `eval` so an ill-typed contract is rejected before it ever executes. Each `List`
+/// node is folded by first inferring its children, then resolving the callee against either the
+/// small built-in table in `native_signature` or a signature recorded here by an earlier
+/// `define-private`/`define-public`/`define-read-only` form (see `handle_define`). A function's
+/// own signature is recorded only after its body has been checked, so a call to it from inside
+/// that body is rejected as `UnboundFunction`.
+///
+/// `functions::define::evaluate_define` and `handle_define_function`, the natural hooks for
+/// `define` forms at eval time, don't exist yet in this tree, so `define` forms are recognized
+/// directly off their `List` shape in `handle_define` below. Once those land, `handle_define`
+/// should be rewired to run alongside them instead of re-deriving the same dispatch.
+///
+/// `handle_define` already does, at this static layer, what `DefinedFunction::apply` should do
+/// at the runtime layer: it rejects a wrong arity up front (`IncorrectArgumentCount`, not a
+/// `zip`-and-truncate), records each argument's declared `TypeSignature`, and `check_application`
+/// validates every call against it via `admits_type` before binding. `vm::callables` (the module
+/// `DefinedFunction`/`FunctionIdentifier` would live in, per `vm::mod`'s `mod callables;`) has no
+/// file on disk in this tree, and its own dependencies (`Context`, `Environment`) are equally
+/// absent, so the runtime-layer version of this check can't be added without fabricating that
+/// whole missing subsystem -- this pass is the closest real enforcement point that exists here.
+pub struct TypeChecker {
+    function_types: BTreeMap<String, FunctionSignature>,
+    /// Non-fatal findings accumulated while checking -- currently just unused `let`-binding
+    /// warnings from `check_let` -- that don't stop the contract from being accepted the way a
+    /// `CheckErrors` does, but are still worth surfacing to whoever is calling this pass.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl TypeChecker {
+    pub fn new() -> TypeChecker {
+        TypeChecker {
+            function_types: BTreeMap::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn get_function_type(&self, name: &str) -> Option<&FunctionSignature> {
+        self.function_types.get(name)
+    }
+
+    /// Non-fatal diagnostics (e.g. unused-binding warnings) collected while checking so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Type-check an entire contract: each top-level expression is folded in turn, with names
+    /// and function signatures `define`d by earlier expressions visible to later ones.
+    pub fn type_check_contract(
+        &mut self,
+        expressions: &[SymbolicExpression],
+    ) -> CheckResult<Vec<TypeSignature>> {
+        let mut context = TypingContext::new();
+        expressions
+            .iter()
+            .map(|expr| self.type_check(expr, &mut context))
+            .collect()
+    }
+
+    /// Infer `expr`'s type. Children are always resolved before the parent: a literal or a
+    /// variable already bound in `context` resolves immediately, and a `List` recurses into its
+    /// arguments before the callee's signature is even looked up, so a mismatched argument is
+    /// reported instead of a confusing callee-arity error.
+    pub fn type_check(
+        &mut self,
+        expr: &SymbolicExpression,
+        context: &mut TypingContext,
+    ) -> TypeResult {
+        match &expr.expr {
+            SymbolicExpressionType::AtomValue(value) => Ok(TypeSignature::type_of(value)),
+            SymbolicExpressionType::Atom(name) => context
+                .lookup_variable_type(name)
+                .cloned()
+                .ok_or_else(|| CheckErrors::UnboundVariable(name.clone())),
+            SymbolicExpressionType::List(children) => self.type_check_list(children, context),
+        }
+    }
+
+    fn type_check_list(
+        &mut self,
+        children: &[SymbolicExpression],
+        context: &mut TypingContext,
+    ) -> TypeResult {
+        let (head, args) = children
+            .split_first()
+            .ok_or(CheckErrors::NonFunctionApplication)?;
+        let function_name = head
+            .match_atom()
+            .ok_or(CheckErrors::NonFunctionApplication)?
+            .as_str();
+
+        match function_name {
+            "define" | "define-private" | "define-public" | "define-read-only" => {
+                self.handle_define(function_name, args, context)
+            }
+            "let" => self.check_let(args, context),
+            "if" => self.check_if(args, context),
+            "list" => self.check_list_literal(args, context),
+            "tuple" => self.check_tuple_literal(args, context),
+            _ => self.check_application(function_name, args, context),
+        }
+    }
+
+    /// `(define name expr)` binds `name`'s inferred type into the *current* scope (so a
+    /// top-level `define` is visible to every later top-level expression, matching
+    /// `eval_all`'s behavior of accumulating `contract_context` bindings as it goes).
+    ///
+    /// `(define-private (name (arg type) ...) body)` (and its `-public`/`-read-only` siblings)
+    /// parses each argument's type annotation, binds the arguments into a fresh scope nested in
+    /// the current one, checks the body there, and then records `name`'s signature. A
+    /// directly-recursive call inside `body` therefore finds no signature and is rejected.
+    fn handle_define(
+        &mut self,
+        form: &str,
+        args: &[SymbolicExpression],
+        context: &mut TypingContext,
+    ) -> TypeResult {
+        check_argument_count(2, args)?;
+
+        if let Some(name) = args[0].match_atom() {
+            // (define name expr)
+            if self.function_types.contains_key(name) || context.lookup_variable_type(name).is_some() {
+                return Err(CheckErrors::NameAlreadyUsed(name.clone()));
+            }
+            let bound_type = self.type_check(&args[1], context)?;
+            context.bind_variable(name.clone(), bound_type);
+            return Ok(TypeSignature::new_atom(AtomTypeIdentifier::VoidType));
+        }
+
+        let signature_form = args[0]
+            .match_list()
+            .ok_or(CheckErrors::BadDefineForm)?;
+        let (name_expr, arg_exprs) = signature_form
+            .split_first()
+            .ok_or(CheckErrors::BadDefineForm)?;
+        let function_name = name_expr.match_atom().ok_or(CheckErrors::BadDefineForm)?;
+
+        if self.function_types.contains_key(function_name) {
+            return Err(CheckErrors::NameAlreadyUsed(function_name.clone()));
+        }
+
+        let mut arg_names = Vec::with_capacity(arg_exprs.len());
+        let mut arg_types = Vec::with_capacity(arg_exprs.len());
+        for arg_expr in arg_exprs.iter() {
+            let pair = arg_expr.match_list().ok_or(CheckErrors::BadDefineForm)?;
+            if pair.len() != 2 {
+                return Err(CheckErrors::BadDefineForm);
+            }
+            let arg_name = pair[0].match_atom().ok_or(CheckErrors::BadDefineForm)?;
+            let type_name = pair[1].match_atom().ok_or(CheckErrors::BadDefineForm)?;
+            if arg_names.contains(arg_name) {
+                return Err(CheckErrors::NameAlreadyUsed(arg_name.clone()));
+            }
+            arg_names.push(arg_name.clone());
+            arg_types.push(parse_simple_type(type_name)?);
+        }
+
+        let mut body_context = context.extend();
+        for (arg_name, arg_type) in arg_names.iter().zip(arg_types.iter()) {
+            body_context.bind_variable(arg_name.clone(), arg_type.clone());
+        }
+
+        // The signature is recorded after the body is checked, with the body's type as its return
+        // type: unlike `define-public`'s `(response ...)` convention, this pass has no separate
+        // declared-return-type annotation to check the body against.
+        let return_type = self.type_check(&args[1], &mut body_context)?;
+        self.function_types.insert(
+            function_name.clone(),
+            FunctionSignature {
+                args: arg_types,
+                returns: return_type.clone(),
+            },
+        );
+
+        let _ = form; // the three `define-*` variants differ only in visibility, not in typing
+        Ok(TypeSignature::new_atom(AtomTypeIdentifier::VoidType))
+    }
+
+    /// `(let ((name expr) ...) body...)`: each binding is type-checked and added to a fresh
+    /// scope in order (so a later binding may reference an earlier one, as Clarity allows), and
+    /// the body -- the remaining arguments, implicitly sequenced -- is checked in that scope.
+    /// The `let`'s type is its last body expression's type.
+    fn check_let(
+        &mut self,
+        args: &[SymbolicExpression],
+        context: &mut TypingContext,
+    ) -> TypeResult {
+        check_arguments_at_least(2, args)?;
+
+        let bindings = args[0].match_list().ok_or(CheckErrors::BadDefineForm)?;
+        let mut inner_context = context.extend();
+        let mut bound = Vec::with_capacity(bindings.len());
+        for binding in bindings.iter() {
+            let pair = binding.match_list().ok_or(CheckErrors::BadDefineForm)?;
+            if pair.len() != 2 {
+                return Err(CheckErrors::BadDefineForm);
+            }
+            let bind_name = pair[0].match_atom().ok_or(CheckErrors::BadDefineForm)?;
+            let bind_type = self.type_check(&pair[1], &mut inner_context)?;
+            inner_context.bind_variable(bind_name.clone(), bind_type);
+            bound.push((bind_name.clone(), pair[0].span));
+        }
+
+        let mut result = TypeSignature::new_atom(AtomTypeIdentifier::VoidType);
+        for body_expr in args[1..].iter() {
+            result = self.type_check(body_expr, &mut inner_context)?;
+        }
+
+        // Liveness: for each binding, its "rest of execution" is the later bindings' own init
+        // expressions plus the whole body -- Clarity evaluates a `let`'s bindings and body in
+        // order, so an earlier binding can only ever be read from there on. A binding whose name
+        // appears nowhere in that span is dead: bound but never read before it goes out of scope.
+        for (i, (bind_name, span)) in bound.iter().enumerate() {
+            let used_later = bindings[(i + 1)..].iter().any(|b| {
+                b.match_list()
+                    .and_then(|pair| pair.get(1))
+                    .map_or(false, |init| is_variable_used(bind_name, init))
+            }) || args[1..].iter().any(|e| is_variable_used(bind_name, e));
+
+            if !used_later {
+                self.diagnostics.push(
+                    Diagnostic::new(format!("unused `let` binding `{}`", bind_name))
+                        .with_label(*span, "bound here, but never read"),
+                );
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// `(if cond then else)`: `cond` must be `bool`, and `then`/`else` must agree on type -- this
+    /// pass requires an exact match rather than computing a least-upper-bound, so e.g. branches
+    /// returning two different list lengths are rejected even though they'd both run fine.
+    fn check_if(&mut self, args: &[SymbolicExpression], context: &mut TypingContext) -> TypeResult {
+        check_argument_count(3, args)?;
+
+        let cond_type = self.type_check(&args[0], context)?;
+        let bool_type = TypeSignature::new_atom(AtomTypeIdentifier::BoolType);
+        if cond_type != bool_type {
+            return Err(CheckErrors::TypeMismatch(bool_type, cond_type));
+        }
+
+        let then_type = self.type_check(&args[1], context)?;
+        let else_type = self.type_check(&args[2], context)?;
+        if then_type != else_type {
+            let diagnostic = Diagnostic::new("the branches of this `if` do not have the same type")
+                .with_label(args[1].span, format!("this branch has type {:?}", then_type))
+                .with_label(args[2].span, format!("...but this one has type {:?}", else_type));
+            return Err(CheckErrors::BranchTypesMustMatch(diagnostic));
+        }
+        Ok(then_type)
+    }
+
+    /// `(list e1 e2 ...)`. When every element folds to a constant (see `fold_constant` -- a bare
+    /// `AtomValue`, or itself a `(list ...)` of constants, so nested list literals like
+    /// `(list (list 1 2) (list 3 4))` fold too), this reuses
+    /// `TypeSignature::construct_parent_list_type` verbatim -- the same "atomic types unify,
+    /// dimension goes up by one, max_len takes the max" rule `Value::list_from` applies at
+    /// runtime, so a statically-checked list literal is guaranteed to construct the same type it
+    /// would if `eval`'d directly. For a list containing at least one non-constant expression (a
+    /// variable, a non-literal call), there's no `Value` to hand `construct_parent_list_type`, so
+    /// this falls back to unifying the elements' atomic types pairwise via
+    /// `TypeSignature::least_supertype` and rejects any element that is itself a list -- nested
+    /// dimension propagation for non-constant children is out of scope for that fallback.
+    fn check_list_literal(
+        &mut self,
+        args: &[SymbolicExpression],
+        context: &mut TypingContext,
+    ) -> TypeResult {
+        let mut literal_values = Vec::with_capacity(args.len());
+        let mut all_literal = true;
+        let mut element_types = Vec::with_capacity(args.len());
+
+        for arg in args.iter() {
+            element_types.push(self.type_check(arg, context)?);
+            match fold_constant(arg) {
+                Some(value) if all_literal => literal_values.push(value),
+                _ => all_literal = false,
+            }
+        }
+
+        let void_type = AtomTypeIdentifier::VoidType;
+        if element_types
+            .iter()
+            .any(|t| !t.is_list() && t.atomic_type() == &void_type)
+        {
+            // mirrors the invariant TupleData::from_data enforces for tuple fields: a VoidType
+            // element (the result of a bare `define`, say) can never end up in a list either.
+            return Err(CheckErrors::VoidTypeInList);
+        }
+
+        if all_literal {
+            return TypeSignature::construct_parent_list_type(&literal_values)
+                .map_err(|e| CheckErrors::TypeConstructionError(format!("{:?}", e)));
+        }
+
+        if element_types.iter().any(TypeSignature::is_list) {
+            return Err(CheckErrors::TypeConstructionError(
+                "Cannot unify a list of lists unless every element is a literal".to_string(),
+            ));
+        }
+
+        let mut unified = element_types[0].atomic_type().clone();
+        let count = args.len();
+        for (i, element_type) in element_types.iter().enumerate().skip(1) {
+            unified = TypeSignature::least_supertype(&unified, element_type.atomic_type())
+                .map_err(|_| {
+                    // the "nice region" style: the first element establishes the expected type,
+                    // and the i'th element is where that expectation actually breaks down.
+                    let diagnostic = Diagnostic::new("list elements do not unify to a common type")
+                        .with_label(
+                            args[0].span,
+                            format!("this element has type {:?}", element_types[0]),
+                        )
+                        .with_label(
+                            args[i].span,
+                            format!("...but this one has type {:?}", element_type),
+                        );
+                    CheckErrors::ListTypeMismatch(diagnostic)
+                })?;
+        }
+
+        TypeSignature::new_list(unified, count as i128, 1)
+            .map_err(|e| CheckErrors::TypeConstructionError(format!("{:?}", e)))
+    }
+
+    /// `(tuple (key1 expr1) (key2 expr2) ...)`. Mirrors the invariant `TupleData::from_data`
+    /// enforces at runtime: a `VoidType` field (e.g. the result of a bare `define`) can never
+    /// end up in a tuple, so this pass rejects it statically rather than letting the contract
+    /// fail when it's actually constructed.
+    fn check_tuple_literal(
+        &mut self,
+        args: &[SymbolicExpression],
+        context: &mut TypingContext,
+    ) -> TypeResult {
+        let void_type = TypeSignature::new_atom(AtomTypeIdentifier::VoidType);
+        let mut fields = Vec::with_capacity(args.len());
+        for arg in args.iter() {
+            let pair = arg.match_list().ok_or(CheckErrors::BadDefineForm)?;
+            if pair.len() != 2 {
+                return Err(CheckErrors::BadDefineForm);
+            }
+            let field_name = pair[0].match_atom().ok_or(CheckErrors::BadDefineForm)?;
+            let field_type = self.type_check(&pair[1], context)?;
+            if field_type == void_type {
+                return Err(CheckErrors::VoidTypeInTuple);
+            }
+            fields.push((field_name.clone(), field_type));
+        }
+
+        let mut seen = BTreeMap::new();
+        for (name, field_type) in fields.into_iter() {
+            if seen.insert(name.clone(), field_type).is_some() {
+                return Err(CheckErrors::TupleDuplicateField(name));
+            }
+        }
+
+        let tuple_sig = TupleTypeSignature::new(seen.into_iter().collect())
+            .map_err(|e| CheckErrors::TypeConstructionError(format!("{:?}", e)))?;
+        Ok(TypeSignature::new_atom(AtomTypeIdentifier::TupleType(
+            tuple_sig,
+        )))
+    }
+
+    /// A native arithmetic/comparison application, or a call to a `define`d function whose
+    /// signature was recorded by `handle_define`. Every argument is checked against the callee's
+    /// declared type with `admits_type` before the callee's return type is handed back.
+    fn check_application(
+        &mut self,
+        function_name: &str,
+        args: &[SymbolicExpression],
+        context: &mut TypingContext,
+    ) -> TypeResult {
+        let arg_types = args
+            .iter()
+            .map(|arg_expr| self.type_check(arg_expr, context))
+            .collect::<CheckResult<Vec<TypeSignature>>>()?;
+
+        let signature = native_signature(function_name, args.len())
+            .or_else(|| self.function_types.get(function_name).cloned())
+            .ok_or_else(|| CheckErrors::UnboundFunction(function_name.to_string()))?;
+
+        if signature.args.len() != args.len() {
+            return Err(CheckErrors::IncorrectArgumentCount(
+                signature.args.len(),
+                args.len(),
+            ));
+        }
+
+        for (expected_type, arg_type) in signature.args.iter().zip(arg_types.into_iter()) {
+            if !expected_type.admits_type(&arg_type) {
+                return Err(CheckErrors::TypeMismatch(expected_type.clone(), arg_type));
+            }
+        }
+
+        Ok(signature.returns)
+    }
+}
+
+/// Parse a simple (non-compound) type annotation as used in a `define-*` argument list, e.g.
+/// `int`, `uint`, `bool`, `principal`. Compound annotations (`(list ...)`, `(tuple ...)`,
+/// `(optional ...)`, `(response ...)`, `(buff ...)`, `(string-ascii ...)`) are handled by
+/// `TypeSignature`'s own (private) `parse_type_repr`, which this pass doesn't have access to --
+/// a `define`d function with a compound-typed argument is reported as a bad annotation here
+/// rather than silently mistyped.
+fn parse_simple_type(type_name: &str) -> TypeResult {
+    let atomic_type = match type_name {
+        "int" => AtomTypeIdentifier::IntType,
+        "uint" => AtomTypeIdentifier::UIntType,
+        "bool" => AtomTypeIdentifier::BoolType,
+        "principal" => AtomTypeIdentifier::PrincipalType,
+        "void" => AtomTypeIdentifier::VoidType,
+        _ => return Err(CheckErrors::BadTypeAnnotation(type_name.to_string())),
+    };
+    Ok(TypeSignature::new_atom(atomic_type))
+}
+
+/// Static signatures for the handful of native functions this pass knows how to check directly.
+/// This is deliberately small -- it covers the arithmetic and comparison operators, which are
+/// `int`/`uint`-only and have a fixed shape -- rather than a full port of every native function,
+/// since that table lives with `NativeFunctions` (in `vm::functions`) and isn't present in this
+/// tree. A call this table doesn't recognize falls through to `TypeChecker::function_types`, so
+/// a user-`define`d function of the same name still resolves correctly.
+fn native_signature(function_name: &str, arg_count: usize) -> Option<FunctionSignature> {
+    let int_type = TypeSignature::new_atom(AtomTypeIdentifier::IntType);
+    let bool_type = TypeSignature::new_atom(AtomTypeIdentifier::BoolType);
+
+    match function_name {
+        "+" | "-" | "*" | "/" | "mod" => Some(FunctionSignature {
+            args: vec![int_type.clone(); arg_count.max(1)],
+            returns: int_type,
+        }),
+        "<" | "<=" | ">" | ">=" | "=" => Some(FunctionSignature {
+            args: vec![int_type.clone(); arg_count.max(1)],
+            returns: bool_type,
+        }),
+        "not" => Some(FunctionSignature {
+            args: vec![bool_type.clone()],
+            returns: bool_type,
+        }),
+        "and" | "or" => Some(FunctionSignature {
+            args: vec![bool_type.clone(); arg_count.max(1)],
+            returns: bool_type,
+        }),
+        _ => None,
+    }
+}
+
+/// Does `expr` read `name` as a free variable anywhere inside it? Used by `check_let` to decide
+/// whether a binding is ever read after it's introduced. Walks `list` forms back-to-front, since
+/// that's the only order that matters for shadowing: if a nested `let` rebinds `name` for part
+/// of `expr`, an `Atom` under that rebinding refers to the new binding, not the one this search
+/// is looking for, and must not count as a use of it. A nested `let`'s init expressions are
+/// searched in order, each seeing only the bindings before it (as `check_let` binds them), so
+/// the search stops at the binding that rebinds `name`.
+fn is_variable_used(name: &str, expr: &SymbolicExpression) -> bool {
+    match &expr.expr {
+        SymbolicExpressionType::Atom(var_name) => var_name == name,
+        SymbolicExpressionType::AtomValue(_) => false,
+        SymbolicExpressionType::List(children) => {
+            if children.is_empty() {
+                return false;
+            }
+
+            if let Some(bindings) = is_rebinding_form(&children[0], &children[1..]) {
+                // Inits up to and including the rebinding one can still reference the outer `name`.
+                for pair in bindings.iter().filter_map(|b| b.match_list()) {
+                    if pair.get(1).map_or(false, |init| is_variable_used(name, init)) {
+                        return true;
+                    }
+                    if pair.get(0).and_then(|atom| atom.match_atom()).map_or(false, |n| n == name) {
+                        // Rebound here -- later inits and the body are shadowed.
+                        return false;
+                    }
+                }
+            }
+
+            children[1..].iter().rev().any(|child| is_variable_used(name, child))
+        }
+    }
+}
+
+/// Attempts to evaluate `expr` down to a concrete, already-constructed `Value` purely from its
+/// static shape: a bare literal (`AtomValue`), or a `(list ...)` every element of which itself
+/// folds. Returns `None` the moment it hits anything that actually needs evaluating -- a
+/// variable reference or any other function call -- since this is deliberately a recognizer for
+/// already-literal structure, not a general constant-propagation pass over arbitrary expressions.
+fn fold_constant(expr: &SymbolicExpression) -> Option<Value> {
+    match &expr.expr {
+        SymbolicExpressionType::AtomValue(value) => Some(value.clone()),
+        SymbolicExpressionType::Atom(_) => None,
+        SymbolicExpressionType::List(children) => {
+            let (head, args) = children.split_first()?;
+            if head.match_atom().map(String::as_str) != Some("list") {
+                return None;
+            }
+            let folded = args
+                .iter()
+                .map(fold_constant)
+                .collect::<Option<Vec<Value>>>()?;
+            Value::list_from(folded).ok()
+        }
+    }
+}
+
+/// If `head`/`rest` form a `let`-style binding special form, returns its binding-list entries.
+fn is_rebinding_form<'a>(
+    head: &SymbolicExpression,
+    rest: &'a [SymbolicExpression],
+) -> Option<&'a [SymbolicExpression]> {
+    if head.match_atom().map_or(false, |name| name == "let") {
+        rest.get(0).and_then(|b| b.match_list())
+    } else {
+        None
+    }
+}
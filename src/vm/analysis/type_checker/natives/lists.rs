@@ -118,16 +118,72 @@ pub fn check_special_fold(checker: &mut TypeChecker, args: &[SymbolicExpression]
 
 // todo(ludo): rename file to iterables.rs
 
+/// Attempt to reduce `expr` to a concrete `Value` at analysis time, without evaluating it: a
+/// literal folds to itself, `(+ ...)`/`(-` ...)`/`(* ...)` fold to the result of the operation
+/// once every operand itself folds, and anything else (a call to a non-arithmetic function, a
+/// `let`-bound name, etc.) folds to `None`. This lets a special form like
+/// `check_special_asserts_max_len` accept a bound that's any constant expression, not just a
+/// bare literal.
+///
+/// Atoms never fold here: `TypingContext` in this tree only tracks the *type* each name is bound
+/// to (see `TypingContext::lookup_variable_type`), not the constant value behind it, so there's
+/// no table to resolve a `define-constant` name against yet. Once `TypingContext` (or a sibling
+/// structure) carries bound values as well as types, the `Atom` arm below is where that lookup
+/// belongs.
+fn fold_constant(expr: &SymbolicExpression, context: &TypingContext) -> Option<Value> {
+    match &expr.expr {
+        SymbolicExpressionType::LiteralValue(value) => Some(value.clone()),
+        SymbolicExpressionType::Atom(_name) => None,
+        SymbolicExpressionType::List(items) => {
+            let (op, operands) = items.split_first()?;
+            let op = op.match_atom()?.as_str();
+            if op != "+" && op != "-" && op != "*" {
+                return None;
+            }
+            let mut folded = operands.iter()
+                .map(|operand| fold_constant(operand, context));
+            let first = folded.next()??;
+            let mut acc = first;
+            for next in folded {
+                let next = next?;
+                acc = match (op, &acc, &next) {
+                    ("+", Value::UInt(a), Value::UInt(b)) => Value::UInt(a.checked_add(*b)?),
+                    ("+", Value::Int(a), Value::Int(b)) => Value::Int(a.checked_add(*b)?),
+                    ("-", Value::UInt(a), Value::UInt(b)) => Value::UInt(a.checked_sub(*b)?),
+                    ("-", Value::Int(a), Value::Int(b)) => Value::Int(a.checked_sub(*b)?),
+                    ("*", Value::UInt(a), Value::UInt(b)) => Value::UInt(a.checked_mul(*b)?),
+                    ("*", Value::Int(a), Value::Int(b)) => Value::Int(a.checked_mul(*b)?),
+                    _ => return None,
+                };
+            }
+            Some(acc)
+        },
+        _ => None,
+    }
+}
+
+/// `a + b`, checked against `MAX_VALUE_SIZE` rather than against `u32::MAX`: a `concat`/`append`
+/// result that fits in a `u32` can still be larger than Clarity allows any value to be.
+fn checked_combined_len(a: u128, b: u128) -> CheckResult<u32> {
+    let combined = a + b;
+    if combined > MAX_VALUE_SIZE as u128 {
+        Err(CheckErrors::MaxLengthOverflow)
+    } else {
+        Ok(combined as u32)
+    }
+}
+
 pub fn check_special_concat(checker: &mut TypeChecker, args: &[SymbolicExpression], context: &TypingContext) -> TypeResult {
     check_argument_count(2, args)?;
-    
+
     let lhs_type = checker.type_check(&args[0], context)?;
     match lhs_type {
         TypeSignature::ListType(lhs_list) => {
             let rhs_type = checker.type_check(&args[1], context)?;
             if let TypeSignature::ListType(rhs_list) = rhs_type {
                 if lhs_list.entry_type.admits_type(&*rhs_list.entry_type) {
-                    let return_type = TypeSignature::list_of(*lhs_list.entry_type, lhs_list.max_len + rhs_list.max_len)?;
+                    let combined_len = checked_combined_len(u128::from(lhs_list.max_len), u128::from(rhs_list.max_len))?;
+                    let return_type = TypeSignature::list_of(*lhs_list.entry_type, combined_len)?;
                     return Ok(return_type);
                 } else {
                     return Err(CheckErrors::TypeError(*lhs_list.entry_type, *rhs_list.entry_type).into());
@@ -139,7 +195,7 @@ pub fn check_special_concat(checker: &mut TypeChecker, args: &[SymbolicExpressio
         TypeSignature::BufferType(lhs_buff_len) => {
             let rhs_type = checker.type_check(&args[1], context)?;
             if let TypeSignature::BufferType(rhs_buff_len) = rhs_type {
-                let size: u32 = u32::from(lhs_buff_len) + u32::from(rhs_buff_len);
+                let size = checked_combined_len(u128::from(u32::from(lhs_buff_len)), u128::from(u32::from(rhs_buff_len)))?;
                 let return_type = TypeSignature::buffer_of_size(size);
                 return Ok(return_type);
             } else {
@@ -158,7 +214,8 @@ pub fn check_special_append(checker: &mut TypeChecker, args: &[SymbolicExpressio
         TypeSignature::ListType(lhs_list) => {
             let rhs_type = checker.type_check(&args[1], context)?;
             if lhs_list.entry_type.admits_type(&rhs_type) {
-                let return_type = TypeSignature::list_of(*lhs_list.entry_type, lhs_list.max_len + 1)?;
+                let combined_len = checked_combined_len(u128::from(lhs_list.max_len), 1)?;
+                let return_type = TypeSignature::list_of(*lhs_list.entry_type, combined_len)?;
                 return Ok(return_type);
             } else {
                 return Err(CheckErrors::TypeError(*lhs_list.entry_type, rhs_type).into());
@@ -167,7 +224,7 @@ pub fn check_special_append(checker: &mut TypeChecker, args: &[SymbolicExpressio
         TypeSignature::BufferType(lhs_buff_len) => {
             let rhs_type = checker.type_check(&args[1], context)?;
             if let TypeSignature::BufferType(rhs_buff_len) = rhs_type {
-                let size: u32 = u32::from(lhs_buff_len) + u32::from(rhs_buff_len);
+                let size = checked_combined_len(u128::from(u32::from(lhs_buff_len)), u128::from(u32::from(rhs_buff_len)))?;
                 let return_type = TypeSignature::buffer_of_size(size);
                 return Ok(return_type);
             } else {
@@ -181,12 +238,8 @@ pub fn check_special_append(checker: &mut TypeChecker, args: &[SymbolicExpressio
 pub fn check_special_asserts_max_len(checker: &mut TypeChecker, args: &[SymbolicExpression], context: &TypingContext) -> TypeResult {
     check_argument_count(2, args)?;
 
-    // let expected_len: TypeSignature = TypeSignature::UIntType;
-    // checker.type_check_expects(&args[1], context, &expected_amount)?;
-    println!("-> {:?}", args);
-
-    let expected_len = match args[1].expr {
-        SymbolicExpressionType::LiteralValue(Value::UInt(expected_len)) => expected_len,
+    let expected_len = match fold_constant(&args[1], context) {
+        Some(Value::UInt(expected_len)) => expected_len,
         _ => return Err(CheckErrors::TypeError(TypeSignature::UIntType, TypeSignature::BoolType).into()) // todo(ludo): fix
     };
     if expected_len > u128::from(MAX_VALUE_SIZE)  {
@@ -0,0 +1,90 @@
+use vm::errors::{Error, InterpreterResult as Result};
+use vm::types::{AtomTypeIdentifier, TupleTypeSignature, Value};
+
+/// How to turn a raw external input -- e.g. an untyped argument off a transaction payload -- into
+/// a `Value` of a known target type. One variant per atomic shape this subsystem knows how to
+/// parse; `for_type` picks the right one for a `TypeSignature`'s `AtomTypeIdentifier`, and
+/// `apply` is the `FromStr`-style entry point each variant implements against its own raw
+/// representation. Compound targets this doesn't cover (`OptionalType`, `ResponseType`, lists)
+/// have no sensible flat-string encoding and are left to their own evaluators.
+pub enum Conversion {
+    Int,
+    UInt,
+    Bool,
+    Buffer,
+    Tuple(TupleTypeSignature),
+}
+
+impl Conversion {
+    /// The `Conversion` that parses raw input into `target`, or `None` if this subsystem doesn't
+    /// know how to parse that type from a flat string (`VoidType`, `OptionalType`, ...).
+    pub fn for_type(target: &AtomTypeIdentifier) -> Option<Conversion> {
+        match target {
+            AtomTypeIdentifier::IntType => Some(Conversion::Int),
+            AtomTypeIdentifier::UIntType => Some(Conversion::UInt),
+            AtomTypeIdentifier::BoolType => Some(Conversion::Bool),
+            AtomTypeIdentifier::BufferType(_) => Some(Conversion::Buffer),
+            AtomTypeIdentifier::TupleType(tuple_sig) => Some(Conversion::Tuple(tuple_sig.clone())),
+            _ => None,
+        }
+    }
+
+    /// Parse `raw` into a `Value` under this conversion. Integers always parse as `i128`/`u128`
+    /// (never `i64`), and a `Buffer` is built straight out of `raw`'s bytes -- there's no escaping
+    /// syntax here, so a `Buffer` field can't itself contain the `;` a `Tuple` conversion uses to
+    /// separate its own fields (an `=` after the field's first one stays part of the value).
+    pub fn apply(&self, raw: &str) -> Result<Value> {
+        match self {
+            Conversion::Int => raw.trim().parse::<i128>()
+                .map(Value::Int)
+                .map_err(|_e| Error::InvalidArguments(format!("'{}' is not a valid int literal", raw))),
+            Conversion::UInt => raw.trim().parse::<u128>()
+                .map(Value::UInt)
+                .map_err(|_e| Error::InvalidArguments(format!("'{}' is not a valid uint literal", raw))),
+            Conversion::Bool => match raw.trim() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(Error::InvalidArguments(format!("'{}' is not a valid bool literal", raw))),
+            },
+            Conversion::Buffer => Value::buff_from(raw.as_bytes().to_vec()),
+            Conversion::Tuple(tuple_sig) => Conversion::apply_tuple(tuple_sig, raw),
+        }
+    }
+
+    /// Parses `name=value` pairs separated by `;`, e.g. `"age=30;is-member=true"`, looking up
+    /// each field's declared type in `tuple_sig` and recursing into `Conversion::apply` for its
+    /// value. A field whose declared type has no flat-string `Conversion` (a nested list, say)
+    /// cannot be fed in this way and is rejected rather than guessed at.
+    fn apply_tuple(tuple_sig: &TupleTypeSignature, raw: &str) -> Result<Value> {
+        let mut fields = Vec::new();
+        for pair in raw.split(';') {
+            let pair = pair.trim();
+            if pair.is_empty() {
+                continue;
+            }
+
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next()
+                .ok_or_else(|| Error::InvalidArguments(format!("Malformed tuple field '{}'", pair)))?
+                .trim();
+            let raw_value = parts.next()
+                .ok_or_else(|| Error::InvalidArguments(format!("Missing '=' in tuple field '{}'", pair)))?
+                .trim();
+
+            let field_type = tuple_sig.field_type(name)
+                .ok_or_else(|| Error::InvalidArguments(format!("No such field '{}' in tuple type", name)))?;
+            let field_conversion = if field_type.is_list() {
+                None
+            } else {
+                Conversion::for_type(field_type.atomic_type())
+            };
+            let field_conversion = field_conversion
+                .ok_or_else(|| Error::InvalidArguments(format!(
+                    "Field '{}' has a type this subsystem cannot parse from a raw literal", name)))?;
+
+            fields.push((name.to_string(), field_conversion.apply(raw_value)?));
+        }
+
+        Value::tuple_from_data(fields)
+    }
+}
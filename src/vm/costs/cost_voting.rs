@@ -0,0 +1,169 @@
+// Copyright (C) 2013-2020 Blocstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// Governance wrapper around `LimitedCostTracker::override_cost_function` (see that method's
+// doc comment): that method *applies* an override immediately, with no notion of who asked for
+// it or whether they're in the majority. `CostVotingRegistry` adds the missing piece -- principals
+// propose replacing a `ClarityCostFunction` with a Clarity function of their own, other principals
+// vote, and the replacement only actually reaches `override_cost_function` once a proposal has
+// accrued `COST_VOTE_CONFIRMATION_THRESHOLD` distinct votes *and* `COST_VOTE_ACTIVATION_DELAY` burn
+// blocks have since passed -- giving node operators a window to notice an unpopular cost schedule
+// before it goes live, the same role a delayed-activation epoch gives a hard fork.
+
+use std::collections::{HashMap, HashSet};
+
+use vm::costs::cost_functions::ClarityCostFunction;
+use vm::costs::{ClarityCostFunctionReference, CostErrors, LimitedCostTracker, Result};
+use vm::database::ClarityDatabase;
+use vm::types::PrincipalData;
+
+/// Votes a proposal needs from distinct principals before it's eligible to activate.
+pub const COST_VOTE_CONFIRMATION_THRESHOLD: u64 = 3;
+
+/// Burn blocks a confirmed proposal must wait out before `activate_confirmed` will apply it.
+pub const COST_VOTE_ACTIVATION_DELAY: u64 = 144;
+
+pub type ProposalId = u64;
+
+/// One outstanding ask to replace `function`'s cost with whatever `replacement` computes.
+#[derive(Debug, Clone)]
+pub struct CostVoteProposal {
+    pub function: ClarityCostFunction,
+    pub replacement: ClarityCostFunctionReference,
+    voters: HashSet<PrincipalData>,
+    /// The burn height at which this proposal first crossed `COST_VOTE_CONFIRMATION_THRESHOLD`
+    /// votes, if it has. Recorded once and never moved, even if later votes are withdrawn --
+    /// confirmation is a one-way door, same as a proposal can't be un-submitted.
+    confirmed_at: Option<u64>,
+    /// Whether `activate_confirmed` has already pushed this proposal's override into a tracker.
+    /// Kept so repeated calls (e.g. once per processed burn block) don't re-apply it and re-run
+    /// `override_cost_function`'s validation needlessly.
+    activated: bool,
+}
+
+impl CostVoteProposal {
+    pub fn vote_count(&self) -> u64 {
+        self.voters.len() as u64
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+
+    pub fn is_activated(&self) -> bool {
+        self.activated
+    }
+}
+
+/// All outstanding and activated cost-function-override proposals. Holds no reference to any
+/// particular `LimitedCostTracker` -- `activate_confirmed` is handed one (and a `ClarityDatabase`)
+/// each time it's called, the way the node would call it once per processed burn block against
+/// whichever tracker backs that block's evaluation.
+#[derive(Debug, Clone, Default)]
+pub struct CostVotingRegistry {
+    proposals: HashMap<ProposalId, CostVoteProposal>,
+    next_id: ProposalId,
+}
+
+impl CostVotingRegistry {
+    pub fn new() -> CostVotingRegistry {
+        CostVotingRegistry {
+            proposals: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Opens a new proposal to replace `function`'s cost with `replacement`'s. Returns the id
+    /// callers will `vote` on.
+    pub fn submit_proposal(
+        &mut self,
+        function: ClarityCostFunction,
+        replacement: ClarityCostFunctionReference,
+    ) -> ProposalId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.proposals.insert(
+            id,
+            CostVoteProposal {
+                function,
+                replacement,
+                voters: HashSet::new(),
+                confirmed_at: None,
+                activated: false,
+            },
+        );
+        id
+    }
+
+    pub fn get_proposal(&self, proposal_id: ProposalId) -> Option<&CostVoteProposal> {
+        self.proposals.get(&proposal_id)
+    }
+
+    /// Records a vote for `proposal_id` from `voter`, and confirms the proposal (stamping
+    /// `current_height` as its confirmation height) the moment it first reaches the threshold.
+    /// A second vote from the same principal is a no-op -- `voters` is a set, not a counter --
+    /// since otherwise one principal could confirm a proposal on their own by voting repeatedly.
+    pub fn vote(
+        &mut self,
+        proposal_id: ProposalId,
+        voter: PrincipalData,
+        current_height: u64,
+    ) -> Result<()> {
+        let proposal = self.proposals.get_mut(&proposal_id).ok_or_else(|| {
+            CostErrors::CostComputationFailed(format!("No such cost vote proposal: {}", proposal_id))
+        })?;
+
+        proposal.voters.insert(voter);
+
+        if proposal.confirmed_at.is_none()
+            && proposal.voters.len() as u64 >= COST_VOTE_CONFIRMATION_THRESHOLD
+        {
+            proposal.confirmed_at = Some(current_height);
+        }
+
+        Ok(())
+    }
+
+    /// Applies every proposal that is both confirmed and past its activation delay to `tracker`,
+    /// via `override_cost_function`. A proposal whose override contract fails validation is left
+    /// un-activated (and will be retried on the next call) rather than being silently dropped --
+    /// the registry records that it was confirmed and due, not that it ever actually took.
+    pub fn activate_confirmed(
+        &mut self,
+        current_height: u64,
+        tracker: &mut LimitedCostTracker,
+        clarity_db: &mut ClarityDatabase,
+    ) {
+        for proposal in self.proposals.values_mut() {
+            if proposal.activated {
+                continue;
+            }
+            let confirmed_at = match proposal.confirmed_at {
+                Some(height) => height,
+                None => continue,
+            };
+            if current_height < confirmed_at + COST_VOTE_ACTIVATION_DELAY {
+                continue;
+            }
+            if tracker
+                .override_cost_function(proposal.function.clone(), proposal.replacement.clone(), clarity_db)
+                .is_ok()
+            {
+                proposal.activated = true;
+            }
+        }
+    }
+}
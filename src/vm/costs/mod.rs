@@ -16,9 +16,10 @@
 
 pub mod constants;
 pub mod cost_functions;
+pub mod cost_voting;
 
 use regex::internal::Exec;
-use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
 use std::convert::{TryFrom, TryInto};
 use std::{cmp, fmt};
 
@@ -53,8 +54,16 @@ pub fn runtime_cost<T: TryInto<u64>, C: CostTracker>(
 macro_rules! finally_drop_memory {
     ( $env: expr, $used_mem:expr; $exec:expr ) => {{
         let result = (|| $exec)();
-        $env.drop_memory($used_mem);
-        result
+        match result {
+            // the wrapped expression succeeded: forward a memory-drop failure instead of
+            // silently discarding it, since it means the memory accounting itself is broken.
+            Ok(value) => $env.drop_memory($used_mem).map(|_| value).map_err(Error::from),
+            // the wrapped expression already failed: that's the more useful error to report.
+            Err(e) => {
+                let _ = $env.drop_memory($used_mem);
+                Err(e)
+            }
+        }
     }};
 }
 
@@ -90,7 +99,7 @@ pub trait CostTracker {
     ) -> Result<ExecutionCost>;
     fn add_cost(&mut self, cost: ExecutionCost) -> Result<()>;
     fn add_memory(&mut self, memory: u64) -> Result<()>;
-    fn drop_memory(&mut self, memory: u64);
+    fn drop_memory(&mut self, memory: u64) -> Result<()>;
     fn reset_memory(&mut self);
 }
 
@@ -109,7 +118,9 @@ impl CostTracker for () {
     fn add_memory(&mut self, _memory: u64) -> std::result::Result<(), CostErrors> {
         Ok(())
     }
-    fn drop_memory(&mut self, _memory: u64) {}
+    fn drop_memory(&mut self, _memory: u64) -> std::result::Result<(), CostErrors> {
+        Ok(())
+    }
     fn reset_memory(&mut self) {}
 }
 
@@ -145,6 +156,16 @@ pub struct LimitedCostTracker {
     memory: u64,
     memory_limit: u64,
     free: bool,
+    /// Per-`ClarityCostFunction` invocation count and accumulated cost, kept only when this
+    /// tracker was constructed via `new_profiling`. `None` means profiling is off, so
+    /// `compute_cost` skips the bookkeeping entirely rather than maintaining an always-empty map.
+    cost_breakdown: Option<HashMap<ClarityCostFunction, (u64, ExecutionCost)>>,
+    /// Closed-form stand-ins for the boot cost functions `load_boot_costs` was able to recognize
+    /// as constant/linear/logn/nlogn in their single input -- see `recognize_closed_form`.
+    /// `compute_cost` evaluates through here instead of `eval_all` whenever a function is
+    /// present, and only falls back to interpreting the cost contract for the rest (e.g. a
+    /// governance-installed override contract with a shape this doesn't recognize).
+    native_cost_functions: HashMap<&'static ClarityCostFunction, SimpleCostSpecification>,
 }
 
 impl fmt::Debug for LimitedCostTracker {
@@ -168,6 +189,16 @@ impl PartialEq for LimitedCostTracker {
     }
 }
 
+/// A captured point in a `LimitedCostTracker`'s accrued cost and memory use, returned by
+/// `checkpoint` and later consumed by `rollback_to` or `commit` -- the speculative-execution
+/// counterpart to `set_total`'s clone-and-restore hack, except it also covers `memory` (which
+/// `set_total` silently ignored) and is opaque, so a caller can't construct one out of thin air.
+#[derive(Debug, Clone)]
+pub struct CostCheckpoint {
+    total: ExecutionCost,
+    memory: u64,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum CostErrors {
     CostComputationFailed(String),
@@ -175,6 +206,9 @@ pub enum CostErrors {
     CostBalanceExceeded(ExecutionCost, ExecutionCost),
     MemoryBalanceExceeded(u64, u64),
     CostContractLoadFailure,
+    /// `drop_memory` was asked to drop more memory than is currently tracked as in use -- an
+    /// accounting bug, rather than something a malformed input can trigger on its own.
+    MemoryUnderflow,
 }
 
 impl LimitedCostTracker {
@@ -190,6 +224,8 @@ impl LimitedCostTracker {
             total: ExecutionCost::zero(),
             memory: 0,
             free: false,
+            cost_breakdown: None,
+            native_cost_functions: HashMap::new(),
         };
         cost_tracker.load_boot_costs(clarity_db)?;
         Ok(cost_tracker)
@@ -203,6 +239,8 @@ impl LimitedCostTracker {
             memory: 0,
             memory_limit: CLARITY_MEMORY_LIMIT,
             free: false,
+            cost_breakdown: None,
+            native_cost_functions: HashMap::new(),
         };
         cost_tracker.load_boot_costs(clarity_db)?;
         Ok(cost_tracker)
@@ -216,8 +254,21 @@ impl LimitedCostTracker {
             memory: 0,
             memory_limit: CLARITY_MEMORY_LIMIT,
             free: true,
+            cost_breakdown: None,
+            native_cost_functions: HashMap::new(),
         }
     }
+    /// Like `new`, but accumulates a per-`ClarityCostFunction` breakdown as costs are computed --
+    /// see `get_breakdown`. Intended for benchmarking and miner diagnostics, not block processing:
+    /// the bookkeeping adds overhead that a normal tracker skips.
+    pub fn new_profiling(
+        limit: ExecutionCost,
+        clarity_db: &mut ClarityDatabase,
+    ) -> Result<LimitedCostTracker> {
+        let mut cost_tracker = LimitedCostTracker::new(limit, clarity_db)?;
+        cost_tracker.cost_breakdown = Some(HashMap::new());
+        Ok(cost_tracker)
+    }
     pub fn load_boot_costs(&mut self, clarity_db: &mut ClarityDatabase) -> Result<()> {
         let boot_costs_id = (*STACKS_BOOT_COST_CONTRACT).clone();
 
@@ -246,21 +297,152 @@ impl LimitedCostTracker {
         }
         self.cost_function_references = m;
         self.cost_contracts = cost_contracts;
+        self.native_cost_functions = self.recognize_native_cost_functions();
 
         clarity_db.roll_back();
 
         return Ok(());
     }
+
+    /// For each configured `ClarityCostFunction`, try to lower its loaded contract function into
+    /// a `SimpleCostSpecification` -- see `recognize_closed_form`. A function whose body isn't
+    /// one of the recognized closed forms (or whose contract/function lookup fails) is simply
+    /// left out of the map, so `compute_cost` falls back to `eval_all` for it.
+    fn recognize_native_cost_functions(
+        &self,
+    ) -> HashMap<&'static ClarityCostFunction, SimpleCostSpecification> {
+        let mut native_cost_functions = HashMap::new();
+        for (cost_function, reference) in self.cost_function_references.iter() {
+            let contract_context = match self.cost_contracts.get(&reference.contract_id) {
+                Some(contract_context) => contract_context,
+                None => continue,
+            };
+            let defined_function = match contract_context.functions.get(&reference.function_name)
+            {
+                Some(defined_function) => defined_function,
+                None => continue,
+            };
+            let arg_name = match defined_function.arguments.get(0) {
+                Some(arg_name) => arg_name,
+                None => continue,
+            };
+            if let Some(spec) = recognize_closed_form(&defined_function.body, arg_name) {
+                native_cost_functions.insert(*cost_function, spec);
+            }
+        }
+        native_cost_functions
+    }
+
+    /// Points `func` at `reference` instead of whatever contract it currently resolves to
+    /// (typically the boot cost contract), leaving every other function's reference untouched.
+    /// Loads `reference.contract_id` into `cost_contracts` if it isn't already there, then
+    /// validates the override by computing a sample cost through it (reusing the interpreted
+    /// `compute_cost`/`parse_cost` path) before keeping it -- a malformed override contract
+    /// rolls back to whatever reference `func` had before, rather than leaving `func` permanently
+    /// broken. This is how a governance-approved contract (e.g. one shipping a cheaper cost
+    /// estimate after a performance improvement) can take over a single function's cost without a
+    /// hard fork of the interpreter.
+    pub fn override_cost_function(
+        &mut self,
+        func: ClarityCostFunction,
+        reference: ClarityCostFunctionReference,
+        clarity_db: &mut ClarityDatabase,
+    ) -> Result<()> {
+        let static_func = ClarityCostFunction::ALL
+            .iter()
+            .find(|candidate| **candidate == func)
+            .ok_or_else(|| {
+                CostErrors::CostComputationFailed(format!("Unrecognized cost function: {}", &func))
+            })?;
+
+        if !self.cost_contracts.contains_key(&reference.contract_id) {
+            clarity_db.begin();
+            let contract_context = match clarity_db.get_contract(&reference.contract_id) {
+                Ok(contract) => contract.contract_context,
+                Err(e) => {
+                    error!("Failed to load cost function override contract";
+                           "contract" => %reference.contract_id.to_string(),
+                           "error" => %format!("{:?}", e));
+                    clarity_db.roll_back();
+                    return Err(CostErrors::CostContractLoadFailure);
+                }
+            };
+            clarity_db.roll_back();
+            self.cost_contracts
+                .insert(reference.contract_id.clone(), contract_context);
+        }
+
+        let previous_reference = self.cost_function_references.insert(static_func, reference);
+
+        if let Err(e) = compute_cost(self, func.clone(), 1) {
+            match previous_reference {
+                Some(previous) => {
+                    self.cost_function_references.insert(static_func, previous);
+                }
+                None => {
+                    self.cost_function_references.remove(static_func);
+                }
+            }
+            return Err(e);
+        }
+
+        self.native_cost_functions = self.recognize_native_cost_functions();
+        Ok(())
+    }
+
     pub fn get_total(&self) -> ExecutionCost {
         self.total.clone()
     }
-    pub fn set_total(&mut self, total: ExecutionCost) -> () {
-        // used by the miner to "undo" the cost of a transaction when trying to pack a block.
-        self.total = total;
-    }
     pub fn get_limit(&self) -> ExecutionCost {
         self.limit.clone()
     }
+
+    /// Captures the tracker's current accrued cost and memory use. Pair with `rollback_to` or
+    /// `commit` to let a caller -- e.g. a miner speculatively executing a `contract-call?` it may
+    /// need to leave out of the block -- undo or keep whatever gets accrued in between.
+    pub fn checkpoint(&mut self) -> CostCheckpoint {
+        CostCheckpoint {
+            total: self.total.clone(),
+            memory: self.memory,
+        }
+    }
+
+    /// Discards everything accrued since `cp` was taken, restoring both cost and memory to that
+    /// point. Supersedes the old `set_total`, which only restored `total` and silently left
+    /// `memory` accounted for work that was being undone.
+    pub fn rollback_to(&mut self, cp: CostCheckpoint) {
+        self.total = cp.total;
+        self.memory = cp.memory;
+    }
+
+    /// Keeps everything accrued since `cp` was taken. A no-op on `self` -- `cp` is simply
+    /// dropped -- but named so call sites read symmetrically with `rollback_to` instead of just
+    /// letting the checkpoint fall out of scope.
+    pub fn commit(&mut self, cp: CostCheckpoint) {
+        drop(cp);
+    }
+
+    /// The cost accrued between `cp` and now, without discarding it, so a caller can decide
+    /// whether a speculative transaction fits its remaining budget before calling `commit` or
+    /// `rollback_to`.
+    pub fn cost_since(&self, cp: &CostCheckpoint) -> Result<ExecutionCost> {
+        let mut delta = self.total.clone();
+        delta.sub(&cp.total)?;
+        Ok(delta)
+    }
+    /// The per-`ClarityCostFunction` call count and accumulated cost recorded so far, sorted by
+    /// descending runtime contribution -- the dimension miner diagnostics care about most.
+    /// Empty if this tracker wasn't constructed via `new_profiling`.
+    pub fn get_breakdown(&self) -> Vec<(ClarityCostFunction, u64, ExecutionCost)> {
+        let mut entries: Vec<_> = self
+            .cost_breakdown
+            .iter()
+            .flatten()
+            .map(|(cost_function, (calls, cost))| (cost_function.clone(), *calls, cost.clone()))
+            .collect();
+        entries.sort_by(|a, b| b.2.runtime.cmp(&a.2.runtime));
+        entries
+    }
 }
 
 fn parse_cost(
@@ -372,11 +554,26 @@ fn add_memory(s: &mut LimitedCostTracker, memory: u64) -> std::result::Result<()
     }
 }
 
-fn drop_memory(s: &mut LimitedCostTracker, memory: u64) {
-    s.memory = s
-        .memory
-        .checked_sub(memory)
-        .expect("Underflowed dropped memory");
+/// Accumulates `cost_function`'s invocation count and cost into `s.cost_breakdown`, if profiling
+/// is enabled. A no-op otherwise, so a non-profiling tracker pays nothing for this.
+fn record_cost_breakdown(
+    s: &mut LimitedCostTracker,
+    cost_function: ClarityCostFunction,
+    cost: &ExecutionCost,
+) -> Result<()> {
+    if let Some(breakdown) = s.cost_breakdown.as_mut() {
+        let entry = breakdown
+            .entry(cost_function)
+            .or_insert_with(|| (0, ExecutionCost::zero()));
+        entry.0 += 1;
+        entry.1.add(cost)?;
+    }
+    Ok(())
+}
+
+fn drop_memory(s: &mut LimitedCostTracker, memory: u64) -> std::result::Result<(), CostErrors> {
+    s.memory = s.memory.checked_sub(memory).ok_or(CostErrors::MemoryUnderflow)?;
+    Ok(())
 }
 
 impl CostTracker for LimitedCostTracker {
@@ -388,7 +585,12 @@ impl CostTracker for LimitedCostTracker {
         if self.free {
             return Ok(ExecutionCost::zero());
         }
-        compute_cost(self, cost_function, input)
+        let cost = match self.native_cost_functions.get(&cost_function) {
+            Some(spec) => spec.compute_cost(input)?,
+            None => compute_cost(self, cost_function.clone(), input)?,
+        };
+        record_cost_breakdown(self, cost_function, &cost)?;
+        Ok(cost)
     }
     fn add_cost(&mut self, cost: ExecutionCost) -> std::result::Result<(), CostErrors> {
         if self.free {
@@ -402,10 +604,11 @@ impl CostTracker for LimitedCostTracker {
         }
         add_memory(self, memory)
     }
-    fn drop_memory(&mut self, memory: u64) {
-        if !self.free {
-            drop_memory(self, memory)
+    fn drop_memory(&mut self, memory: u64) -> std::result::Result<(), CostErrors> {
+        if self.free {
+            return Ok(());
         }
+        drop_memory(self, memory)
     }
     fn reset_memory(&mut self) {
         if !self.free {
@@ -423,7 +626,12 @@ impl CostTracker for &mut LimitedCostTracker {
         if self.free {
             return Ok(ExecutionCost::zero());
         }
-        compute_cost(self, cost_function, input)
+        let cost = match self.native_cost_functions.get(&cost_function) {
+            Some(spec) => spec.compute_cost(input)?,
+            None => compute_cost(self, cost_function.clone(), input)?,
+        };
+        record_cost_breakdown(self, cost_function, &cost)?;
+        Ok(cost)
     }
     fn add_cost(&mut self, cost: ExecutionCost) -> std::result::Result<(), CostErrors> {
         if self.free {
@@ -437,10 +645,11 @@ impl CostTracker for &mut LimitedCostTracker {
         }
         add_memory(self, memory)
     }
-    fn drop_memory(&mut self, memory: u64) {
-        if !self.free {
-            drop_memory(self, memory)
+    fn drop_memory(&mut self, memory: u64) -> std::result::Result<(), CostErrors> {
+        if self.free {
+            return Ok(());
         }
+        drop_memory(self, memory)
     }
     fn reset_memory(&mut self) {
         if !self.free {
@@ -493,7 +702,7 @@ impl FromSql for ExecutionCost {
     fn column_result(value: ValueRef) -> FromSqlResult<ExecutionCost> {
         let str_val = String::column_result(value)?;
         let parsed = serde_json::from_str(&str_val)
-            .expect("CORRUPTION: failed to parse ExecutionCost from DB");
+            .map_err(|e| FromSqlError::Other(Box::new(e)))?;
         Ok(parsed)
     }
 }
@@ -671,6 +880,138 @@ impl From<ExecutionCost> for SimpleCostSpecification {
     }
 }
 
+// NOTE: `recognize_native_cost_functions` assumes `ContractContext::functions` is a
+// `HashMap<String, DefinedFunction>` and that `DefinedFunction` has public `arguments: Vec<String>`
+// and `body: SymbolicExpression` fields -- `ContractContext`/`DefinedFunction` have no file in this
+// tree to confirm that against (their home, `vm::callables`/`vm::contexts`, is declared in `vm::mod`
+// but doesn't exist here), only the call-site usage in `vm::mod`'s tests
+// (`contract_context.functions.insert("do_work".into(), user_function)`, `DefinedFunction::new`
+// taking a `Vec<(String, TypeSignature)>` of arguments and a `SymbolicExpression` body). The
+// closed-form recognizer itself operates purely on `SymbolicExpression`, which is real, and is
+// exercised directly by the tests below without needing either type.
+
+/// Which of a single input variable's growth shapes a cost-tuple field's multiplicative term
+/// uses: the raw input, `log2` of it, or `input * log2(input)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputShape {
+    Identity,
+    Log,
+    NLog,
+}
+
+fn as_uint(expr: &SymbolicExpression) -> Option<u64> {
+    match expr.match_atom_value()? {
+        Value::UInt(n) => Some(*n as u64),
+        _ => None,
+    }
+}
+
+fn is_arg(expr: &SymbolicExpression, arg_name: &str) -> bool {
+    expr.match_atom().map(|name| name == arg_name).unwrap_or(false)
+}
+
+fn is_named_call(list: &[SymbolicExpression], name: &str) -> bool {
+    list.get(0)
+        .and_then(|head| head.match_atom())
+        .map(|head| head == name)
+        .unwrap_or(false)
+}
+
+/// Matches `<arg>` itself, `(log2 <arg>)`, or `(* <arg> (log2 <arg>))` (in either argument order).
+fn input_shape(expr: &SymbolicExpression, arg_name: &str) -> Option<InputShape> {
+    if is_arg(expr, arg_name) {
+        return Some(InputShape::Identity);
+    }
+    let list = expr.match_list()?;
+    if list.len() == 2 && is_named_call(list, "log2") && is_arg(&list[1], arg_name) {
+        return Some(InputShape::Log);
+    }
+    if list.len() == 3 && is_named_call(list, "*") {
+        let is_nlog = (is_arg(&list[1], arg_name) && input_shape(&list[2], arg_name) == Some(InputShape::Log))
+            || (is_arg(&list[2], arg_name) && input_shape(&list[1], arg_name) == Some(InputShape::Log));
+        if is_nlog {
+            return Some(InputShape::NLog);
+        }
+    }
+    None
+}
+
+/// Matches `(* x uA)` (in either argument order), returning `x`'s `InputShape` and the
+/// coefficient `A`.
+fn match_coefficient_term(term: &SymbolicExpression, arg_name: &str) -> Option<(InputShape, u64)> {
+    let list = term.match_list()?;
+    if list.len() != 3 || !is_named_call(list, "*") {
+        return None;
+    }
+    if let (Some(shape), Some(a)) = (input_shape(&list[1], arg_name), as_uint(&list[2])) {
+        return Some((shape, a));
+    }
+    if let (Some(a), Some(shape)) = (as_uint(&list[1]), input_shape(&list[2], arg_name)) {
+        return Some((shape, a));
+    }
+    None
+}
+
+fn cost_function_of_shape(shape: InputShape, a: u64, b: u64) -> CostFunctions {
+    match shape {
+        InputShape::Identity => CostFunctions::Linear(a, b),
+        InputShape::Log => CostFunctions::LogN(a, b),
+        InputShape::NLog => CostFunctions::NLogN(a, b),
+    }
+}
+
+/// Recognizes a cost-tuple field's expression as one of `CostFunctions`' closed forms: a bare
+/// `uN` literal (`Constant`), `(* x uA)` (coefficient only, `B` implicitly 0), or `(+ (* x uA)
+/// uB)` -- where `x` is `<arg>` (`Linear`), `(log2 <arg>)` (`LogN`), or `<arg> * (log2 <arg>)`
+/// (`NLogN`).
+fn recognize_closed_form_field(expr: &SymbolicExpression, arg_name: &str) -> Option<CostFunctions> {
+    if let Some(n) = as_uint(expr) {
+        return Some(CostFunctions::Constant(n));
+    }
+
+    let list = expr.match_list()?;
+    if list.len() == 3 && is_named_call(list, "+") {
+        let (shape, a) = match_coefficient_term(&list[1], arg_name)?;
+        let b = as_uint(&list[2])?;
+        return Some(cost_function_of_shape(shape, a, b));
+    }
+
+    let (shape, a) = match_coefficient_term(expr, arg_name)?;
+    Some(cost_function_of_shape(shape, a, 0))
+}
+
+/// Recognizes a single-argument cost function's body as a closed-form `SimpleCostSpecification`,
+/// if its body is a `{runtime: ..., write_length: ..., write_count: ..., read_count: ...,
+/// read_length: ...}` tuple literal (which the parser lowers to `(tuple (runtime ...)
+/// (write_length ...) ...)`) whose every field matches `recognize_closed_form_field`. Returns
+/// `None` for anything else, e.g. a function that branches on its input or reads other state --
+/// `compute_cost` keeps evaluating those through `eval_all`.
+fn recognize_closed_form(body: &SymbolicExpression, arg_name: &str) -> Option<SimpleCostSpecification> {
+    let list = body.match_list()?;
+    if !is_named_call(list, "tuple") {
+        return None;
+    }
+
+    let mut field = |name: &str| -> Option<CostFunctions> {
+        list.iter().skip(1).find_map(|entry| {
+            let pair = entry.match_list()?;
+            if pair.len() == 2 && pair[0].match_atom().map(|n| n == name).unwrap_or(false) {
+                recognize_closed_form_field(&pair[1], arg_name)
+            } else {
+                None
+            }
+        })
+    };
+
+    Some(SimpleCostSpecification {
+        runtime: field("runtime")?,
+        write_length: field("write_length")?,
+        write_count: field("write_count")?,
+        read_count: field("read_count")?,
+        read_length: field("read_length")?,
+    })
+}
+
 #[cfg(test)]
 mod unit_tests {
     use super::*;
@@ -718,4 +1059,138 @@ mod unit_tests {
             assert_eq!(int_log2(*input).unwrap(), *expected);
         }
     }
+
+    fn uint_atom(n: u64) -> SymbolicExpression {
+        SymbolicExpression::atom_value(Value::UInt(n as u128))
+    }
+
+    fn arg_atom() -> SymbolicExpression {
+        SymbolicExpression::atom("n".into())
+    }
+
+    fn call(name: &str, args: Vec<SymbolicExpression>) -> SymbolicExpression {
+        let mut exprs = vec![SymbolicExpression::atom(name.into())];
+        exprs.extend(args);
+        SymbolicExpression::list(exprs.into_boxed_slice())
+    }
+
+    /// Builds the same field expression shape every boot cost function uses for each of its five
+    /// tuple fields, so `test_recognize_closed_form_*` only has to vary the shared `field_expr`.
+    fn tuple_body(field_expr: SymbolicExpression) -> SymbolicExpression {
+        let field = |name: &str| call(name, vec![field_expr.clone()]);
+        call(
+            "tuple",
+            vec![
+                field("runtime"),
+                field("write_length"),
+                field("write_count"),
+                field("read_count"),
+                field("read_length"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_recognize_closed_form_constant() {
+        let body = tuple_body(uint_atom(7));
+        let spec = recognize_closed_form(&body, "n").expect("should recognize a bare literal");
+        assert_eq!(spec.runtime, CostFunctions::Constant(7));
+    }
+
+    #[test]
+    fn test_recognize_closed_form_linear() {
+        // (+ (* n u3) u5)
+        let field_expr = call("+", vec![call("*", vec![arg_atom(), uint_atom(3)]), uint_atom(5)]);
+        let body = tuple_body(field_expr);
+        let spec = recognize_closed_form(&body, "n").expect("should recognize a*n + b");
+        assert_eq!(spec.runtime, CostFunctions::Linear(3, 5));
+    }
+
+    #[test]
+    fn test_recognize_closed_form_linear_no_constant() {
+        // (* u3 n) -- coefficient first, no trailing (+ ... b)
+        let field_expr = call("*", vec![uint_atom(3), arg_atom()]);
+        let body = tuple_body(field_expr);
+        let spec = recognize_closed_form(&body, "n").expect("should recognize a*n");
+        assert_eq!(spec.runtime, CostFunctions::Linear(3, 0));
+    }
+
+    #[test]
+    fn test_recognize_closed_form_logn() {
+        // (+ (* (log2 n) u2) u1)
+        let field_expr = call(
+            "+",
+            vec![call("*", vec![call("log2", vec![arg_atom()]), uint_atom(2)]), uint_atom(1)],
+        );
+        let body = tuple_body(field_expr);
+        let spec = recognize_closed_form(&body, "n").expect("should recognize a*log(n) + b");
+        assert_eq!(spec.runtime, CostFunctions::LogN(2, 1));
+    }
+
+    #[test]
+    fn test_recognize_closed_form_nlogn() {
+        // (+ (* (* n (log2 n)) u4) u9)
+        let field_expr = call(
+            "+",
+            vec![
+                call("*", vec![call("*", vec![arg_atom(), call("log2", vec![arg_atom()])]), uint_atom(4)]),
+                uint_atom(9),
+            ],
+        );
+        let body = tuple_body(field_expr);
+        let spec = recognize_closed_form(&body, "n").expect("should recognize a*n*log(n) + b");
+        assert_eq!(spec.runtime, CostFunctions::NLogN(4, 9));
+    }
+
+    #[test]
+    fn test_recognize_closed_form_rejects_unrecognized_shape() {
+        // (if (> n u10) u1 u2) -- not a closed form this recognizer understands.
+        let field_expr = call("if", vec![call(">", vec![arg_atom(), uint_atom(10)]), uint_atom(1), uint_atom(2)]);
+        let body = tuple_body(field_expr);
+        assert!(recognize_closed_form(&body, "n").is_none());
+    }
+
+    #[test]
+    fn test_native_fast_path_matches_interpreted_shape_across_inputs() {
+        // The native path's whole point is to shortcut straight to `SimpleCostSpecification`
+        // instead of calling into `eval_all` -- so "matches the interpreted path" means
+        // evaluating the *same recognized AST* by a second, independent walker and checking the
+        // native `CostFunctions::compute_cost` result against it, across edge-case inputs.
+        fn eval_field(expr: &SymbolicExpression, n: u128) -> u128 {
+            if let Some(v) = as_uint(expr) {
+                return v as u128;
+            }
+            if is_arg(expr, "n") {
+                return n;
+            }
+            let list = expr.match_list().unwrap();
+            if is_named_call(list, "+") {
+                return eval_field(&list[1], n) + eval_field(&list[2], n);
+            }
+            if is_named_call(list, "*") {
+                return eval_field(&list[1], n) * eval_field(&list[2], n);
+            }
+            if is_named_call(list, "log2") {
+                return int_log2(cmp::max(n as u64, 1)).unwrap() as u128;
+            }
+            panic!("unrecognized shape in eval_field");
+        }
+
+        let field_expr = call(
+            "+",
+            vec![
+                call("*", vec![call("*", vec![arg_atom(), call("log2", vec![arg_atom()])]), uint_atom(4)]),
+                uint_atom(9),
+            ],
+        );
+        let spec = recognize_closed_form_field(&field_expr, "n").unwrap();
+
+        // u128 arithmetic here can't itself overflow for these inputs, so it's safe to use as the
+        // independent check; u64::max_value() overflows u64 math by design and is covered
+        // separately (mirroring test_simple_overflows' CostOverflow expectation).
+        for input in [0u64, 1, 2, 1000] {
+            assert_eq!(spec.compute_cost(input), Ok(eval_field(&field_expr, input as u128) as u64));
+        }
+        assert_eq!(spec.compute_cost(u64::max_value()), Err(CostErrors::CostOverflow));
+    }
 }
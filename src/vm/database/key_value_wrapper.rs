@@ -9,21 +9,24 @@ pub trait KeyValueStorage {
     fn put(&mut self, key: &str, value: &str);
     fn get(&mut self, key: &str) -> Option<String>;
     fn has_entry(&mut self, key: &str) -> bool;
+    fn delete(&mut self, key: &str);
 
     /// returns the previous block header hash on success
     fn set_block_hash(&mut self, bhh: BlockHeaderHash) -> Result<BlockHeaderHash> {
         panic!("Attempted to evaluate changed block height with a generic backend");
-    } 
+    }
 
     fn put_all(&mut self, mut items: Vec<(String, String)>) {
         for (key, value) in items.drain(..) {
             self.put(&key, &value);
         }
     }
-}
 
-pub struct RollbackContext {
-    edits: Vec<(String, String)>
+    fn delete_all(&mut self, mut keys: Vec<String>) {
+        for key in keys.drain(..) {
+            self.delete(&key);
+        }
+    }
 }
 
 pub struct RollbackWrapper <'a> {
@@ -32,15 +35,19 @@ pub struct RollbackWrapper <'a> {
     // lookup_map is a history of edits for a given key.
     //   in order of least-recent to most-recent at the tail.
     //   this allows ~ O(1) lookups, and ~ O(1) commits, roll-backs (amortized by # of PUTs).
-    lookup_map: HashMap<String, Vec<String>>,
-    // stack keeps track of the most recent rollback context, which tells us which
-    //   edits were performed by which context. at the moment, each context's edit history
-    //   is a separate Vec which must be drained into the parent on commits, meaning that
-    //   the amortized cost of committing a value isn't O(1), but actually O(k) where k is
-    //   stack depth.
-    //  TODO: The solution to this is to just have a _single_ edit stack, and merely store indexes
-    //   to indicate a given contexts "start depth".
-    stack: Vec<RollbackContext>
+    //   `None` is a tombstone: a logical delete recorded by `RollbackWrapper::delete`, distinct
+    //   from "no edit happened" (which is simply the key being absent from this map).
+    lookup_map: HashMap<String, Vec<Option<String>>>,
+    // edits is a single, flat, chronological log of every (key, value) put or delete across
+    //   every nested context currently open -- not one Vec per context. This is what makes a
+    //   nested commit() O(1): the edits already live here, so "bubbling up" to the parent context
+    //   is just dropping the child's start index, not copying anything.
+    edits: Vec<(String, Option<String>)>,
+    // stack holds, for each currently-open context, the index into `edits` where that context's
+    //   edits begin. nest() pushes edits.len(); rollback()/commit() pop it and either truncate
+    //   `edits` back to it (rollback) or simply discard it, leaving the edits in place for the
+    //   parent context (commit).
+    stack: Vec<usize>
 }
 
 impl <'a> RollbackWrapper <'a> {
@@ -48,24 +55,25 @@ impl <'a> RollbackWrapper <'a> {
         RollbackWrapper {
             store: store,
             lookup_map: HashMap::new(),
+            edits: Vec::new(),
             stack: Vec::new()
         }
     }
 
     pub fn nest(&mut self) {
-        self.stack.push(RollbackContext { edits: Vec::new() });
+        self.stack.push(self.edits.len());
     }
 
     // Rollback the child's edits.
     //   this clears all edits from the child's edit queue,
     //     and removes any of those edits from the lookup map.
     pub fn rollback(&mut self) {
-        let mut last_item = self.stack.pop()
+        let start = self.stack.pop()
             .expect("ERROR: Clarity VM attempted to commit past the stack.");
 
-        last_item.edits.reverse();
+        let rolled_back_edits = self.edits.split_off(start);
 
-        for (key, value) in last_item.edits.drain(..) {
+        for (key, value) in rolled_back_edits.into_iter().rev() {
                 let remove_edit_deque = {
                     let key_edit_history = self.lookup_map.get_mut(&key)
                         .expect("ERROR: Clarity VM had edit log entry, but not lookup_map entry");
@@ -80,18 +88,16 @@ impl <'a> RollbackWrapper <'a> {
     }
 
     pub fn commit(&mut self) {
-        let mut last_item = self.stack.pop()
+        let start = self.stack.pop()
             .expect("ERROR: Clarity VM attempted to commit past the stack.");
 
         if self.stack.len() == 0 {
-            // committing to the backing store
-            // reverse the lookup_map entries, because we want to commit them
-            //   in the order they were performed, but we want to use pop()
-            //   rather than remove(0)
-            for (_, edit_history) in self.lookup_map.iter_mut() {
-                edit_history.reverse();
-            }
-            for (key, value) in last_item.edits.iter() {
+            // committing to the backing store: only the edits this context owns need to be
+            //   drained out of the lookup map, not the whole thing. Walking them most-recent-first
+            //   (`.rev()`) lets us `pop()` each key's history directly, in the order those entries
+            //   were actually pushed, with no need to reverse any lookup_map history first.
+            let committed_edits = self.edits.split_off(start);
+            for (key, value) in committed_edits.iter().rev() {
                 let remove_edit_deque = {
                     let key_edit_history = self.lookup_map.get_mut(key)
                         .expect("ERROR: Clarity VM had edit log entry, but not lookup_map entry");
@@ -104,15 +110,30 @@ impl <'a> RollbackWrapper <'a> {
                 }
             }
             assert!(self.lookup_map.len() == 0);
-            if last_item.edits.len() > 0 {
-                self.store.put_all(last_item.edits);
+            // Reduce to each key's final value before handing off to the backend: the flat log
+            //   may contain several edits for the same key (e.g. a put followed by a delete), and
+            //   only the last one actually needs to reach the store.
+            let mut final_values: HashMap<String, Option<String>> = HashMap::new();
+            for (key, value) in committed_edits {
+                final_values.insert(key, value);
             }
-        } else {
-            // bubble up to the next item in the stack
-            let next_up = self.stack.last_mut().unwrap();
-            for (key, value) in last_item.edits.drain(..) {
-                next_up.edits.push((key, value));
+            let mut puts = Vec::new();
+            let mut deletes = Vec::new();
+            for (key, value) in final_values {
+                match value {
+                    Some(value) => puts.push((key, value)),
+                    None => deletes.push(key),
+                }
+            }
+            if puts.len() > 0 {
+                self.store.put_all(puts);
+            }
+            if deletes.len() > 0 {
+                self.store.delete_all(deletes);
             }
+        } else {
+            // bubble up to the next item in the stack: the edits already live in the flat `edits`
+            //   vec, so there's nothing to copy -- they simply now belong to the parent context.
         }
     }
 
@@ -120,16 +141,29 @@ impl <'a> RollbackWrapper <'a> {
 
 impl <'a> KeyValueStorage for RollbackWrapper <'a> {
     fn put(&mut self, key: &str, value: &str) {
-        let current = self.stack.last_mut()
+        self.stack.last()
             .expect("ERROR: Clarity VM attempted PUT on non-nested context.");
 
         if !self.lookup_map.contains_key(key) {
             self.lookup_map.insert(key.to_string(), Vec::new());
         }
         let key_edit_deque = self.lookup_map.get_mut(key).unwrap();
-        key_edit_deque.push(value.to_string());
+        key_edit_deque.push(Some(value.to_string()));
 
-        current.edits.push((key.to_string(), value.to_string()));
+        self.edits.push((key.to_string(), Some(value.to_string())));
+    }
+
+    fn delete(&mut self, key: &str) {
+        self.stack.last()
+            .expect("ERROR: Clarity VM attempted DELETE on non-nested context.");
+
+        if !self.lookup_map.contains_key(key) {
+            self.lookup_map.insert(key.to_string(), Vec::new());
+        }
+        let key_edit_deque = self.lookup_map.get_mut(key).unwrap();
+        key_edit_deque.push(None);
+
+        self.edits.push((key.to_string(), None));
     }
 
     fn set_block_hash(&mut self, bhh: BlockHeaderHash) -> Result<BlockHeaderHash> {
@@ -140,26 +174,20 @@ impl <'a> KeyValueStorage for RollbackWrapper <'a> {
         self.stack.last()
             .expect("ERROR: Clarity VM attempted GET on non-nested context.");
 
-        let lookup_result = match self.lookup_map.get(key) {
-            None => None,
-            Some(key_edit_history) => {
-                key_edit_history.last().cloned()
-            },
-        };
-        if lookup_result.is_some() {
-            lookup_result
-        } else {
-            self.store.get(key)
+        // A tombstone (`Some(None)`) is a logical delete: it must win over whatever the backing
+        //   store still has for `key`, not just be treated as "no edit happened".
+        match self.lookup_map.get(key) {
+            None => self.store.get(key),
+            Some(key_edit_history) => key_edit_history.last().cloned().unwrap_or(None),
         }
     }
 
     fn has_entry(&mut self, key: &str) -> bool {
         self.stack.last()
             .expect("ERROR: Clarity VM attempted GET on non-nested context.");
-        if self.lookup_map.contains_key(key) {
-            true
-        } else {
-            self.store.has_entry(key)
+        match self.lookup_map.get(key) {
+            None => self.store.has_entry(key),
+            Some(key_edit_history) => key_edit_history.last().map(Option::is_some).unwrap_or(false),
         }
     }
 }
@@ -0,0 +1,76 @@
+// A version-gated chunk store for StackerDB, keyed by `(contract_id, slot_id)`.
+//
+// NOTE: `vm::database` has no `mod.rs` in this tree (only `key_value_wrapper.rs` and this file are
+// present -- `vm::session`'s own NOTE already calls this gap out), so this file isn't wired up
+// with a `pub mod stackerdb_store;` declaration; it sits alongside `key_value_wrapper.rs`, to be
+// declared once that `mod.rs` exists.
+// Unlike `RollbackWrapper`, chunk writes here are never rolled back against a block -- a
+// StackerDB chunk isn't chain state, so there's no block-scoped transaction to nest it in; a
+// write that passes `StackerDBChunkStore::put_chunk`'s version check is simply kept.
+
+use std::collections::HashMap;
+
+use vm::types::QualifiedContractIdentifier;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A write for `slot_id` arrived with a version that isn't strictly greater than the one
+    /// already stored.
+    StaleVersion { slot_id: u32, have_version: u32, got_version: u32 },
+}
+
+/// One stored chunk: the bytes a slot's authorized writer most recently published, and the
+/// version they were published under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredChunk {
+    pub version: u32,
+    pub data: Vec<u8>,
+}
+
+/// The off-chain chunks this node hosts on behalf of every StackerDB contract it replicates,
+/// keyed by `(contract_id, slot_id)`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StackerDBChunkStore {
+    chunks: HashMap<(QualifiedContractIdentifier, u32), StoredChunk>,
+}
+
+impl StackerDBChunkStore {
+    pub fn new() -> StackerDBChunkStore {
+        StackerDBChunkStore { chunks: HashMap::new() }
+    }
+
+    /// The version currently stored for `(contract_id, slot_id)`, or `None` if this node has
+    /// never accepted a write to that slot.
+    pub fn version_of(&self, contract_id: &QualifiedContractIdentifier, slot_id: u32) -> Option<u32> {
+        self.chunks.get(&(contract_id.clone(), slot_id)).map(|c| c.version)
+    }
+
+    /// The bytes currently stored for `(contract_id, slot_id)`, if any.
+    pub fn get_chunk(&self, contract_id: &QualifiedContractIdentifier, slot_id: u32) -> Option<&StoredChunk> {
+        self.chunks.get(&(contract_id.clone(), slot_id))
+    }
+
+    /// Stores `data` under `(contract_id, slot_id)` at `version`, rejecting it if `version`
+    /// doesn't strictly exceed whatever's already stored. Callers are expected to have already
+    /// checked the write's signature against the contract's currently-assigned slot authority --
+    /// this store only enforces the monotonic-version half of acceptance.
+    pub fn put_chunk(&mut self, contract_id: &QualifiedContractIdentifier, slot_id: u32, version: u32, data: Vec<u8>) -> Result<(), Error> {
+        if let Some(have_version) = self.version_of(contract_id, slot_id) {
+            if version <= have_version {
+                return Err(Error::StaleVersion { slot_id, have_version, got_version: version });
+            }
+        }
+        self.chunks.insert((contract_id.clone(), slot_id), StoredChunk { version, data });
+        Ok(())
+    }
+
+    /// The slot versions this node holds for `contract_id`, for advertising to a peer during
+    /// reconciliation.
+    pub fn advertised_versions(&self, contract_id: &QualifiedContractIdentifier) -> HashMap<u32, u32> {
+        self.chunks
+            .iter()
+            .filter(|((cid, _), _)| cid == contract_id)
+            .map(|((_, slot_id), chunk)| (*slot_id, chunk.version))
+            .collect()
+    }
+}
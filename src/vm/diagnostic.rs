@@ -0,0 +1,57 @@
+use vm::representations::Span;
+
+/// One labeled region of source a `Diagnostic` points at, plus the caption explaining what that
+/// span means -- e.g. `(list_span, "this element has type int".to_string())`. A single
+/// `Diagnostic` can carry more than one of these, which is how a two-span "nice region" style
+/// message (one span establishing an expectation, a second span violating it) gets built: see
+/// `vm::analysis::type_checker::TypeChecker::check_list_literal`'s unification failure path for
+/// the motivating example of a list-type mismatch calling out both elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub labels: Vec<(Span, String)>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Diagnostic {
+        Diagnostic { message: message.into(), labels: vec![] }
+    }
+
+    pub fn with_label(mut self, span: Span, label: impl Into<String>) -> Diagnostic {
+        self.labels.push((span, label.into()));
+        self
+    }
+}
+
+/// Render `diagnostic` against the original `source` it was raised against, compiler-style: the
+/// top-level message, then each labeled span rendered as its source line with a `^` underline
+/// beneath the column range and the label caption trailing it. Spans are rendered in the order
+/// they were added to the diagnostic, which for a two-span mismatch is "established here" first,
+/// "conflicts here" second -- matching the order `with_label` was called in.
+pub fn render_diagnostic(source: &str, diagnostic: &Diagnostic) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut out = String::new();
+    out.push_str(&diagnostic.message);
+    out.push('\n');
+
+    for (span, label) in diagnostic.labels.iter() {
+        let line_idx = span.start_line.saturating_sub(1) as usize;
+        let line_text = lines.get(line_idx).copied().unwrap_or("");
+        out.push_str(line_text);
+        out.push('\n');
+
+        let start_col = span.start_column.saturating_sub(1) as usize;
+        let width = if span.start_line == span.end_line && span.end_column > span.start_column {
+            (span.end_column - span.start_column) as usize
+        } else {
+            1
+        };
+        out.push_str(&" ".repeat(start_col));
+        out.push_str(&"^".repeat(width.max(1)));
+        out.push(' ');
+        out.push_str(label);
+        out.push('\n');
+    }
+
+    out
+}
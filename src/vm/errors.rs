@@ -0,0 +1,47 @@
+use std::fmt;
+
+use vm::diagnostic::Diagnostic;
+
+/// Errors produced while constructing or checking `vm::types` values -- malformed contract data,
+/// a type description that doesn't parse, a value too large to store. These are distinct from a
+/// `CheckErrors` rejection: a `CheckErrors` is raised ahead of time by static analysis over a
+/// contract's source, while an `Error` here can also surface while evaluating an already-checked
+/// contract (e.g. `Value::list_from` rejecting a list built up at runtime).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    ValueTooLarge,
+    BufferTooLarge,
+    ListTooLarge,
+    ListDimensionTooHigh,
+    InvalidTypeDescription,
+    ExpectedListPairs,
+    InvalidArguments(String),
+    ParseError(String),
+    /// Wraps another `Error` with source-span context for reporting (see `vm::diagnostic`),
+    /// without changing what error it fundamentally is -- callers that only care about the
+    /// underlying failure can still match through to it via `Error::cause`.
+    WithDiagnostic(Box<Error>, Diagnostic),
+}
+
+impl Error {
+    /// The underlying error a `WithDiagnostic` wraps, or `self` unchanged for every other variant.
+    pub fn cause(&self) -> &Error {
+        match self {
+            Error::WithDiagnostic(inner, _) => inner.cause(),
+            other => other,
+        }
+    }
+
+    /// Attach `diagnostic` to this error for reporting, e.g. via `vm::diagnostic::render_diagnostic`.
+    pub fn with_diagnostic(self, diagnostic: Diagnostic) -> Error {
+        Error::WithDiagnostic(Box::new(self), diagnostic)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub type InterpreterResult<T> = Result<T, Error>;
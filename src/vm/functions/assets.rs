@@ -4,24 +4,143 @@ use vm::functions::tuples::TupleDefinitionType::{Implicit, Explicit};
 use vm::types::{Value, OptionalData, BuffData, PrincipalData, BlockInfoProperty, TypeSignature, AssetIdentifier};
 use vm::representations::{SymbolicExpression};
 use vm::errors::{Error, InterpreterError, CheckErrors, RuntimeErrorType, InterpreterResult as Result, check_argument_count};
+use vm::diagnostic::Diagnostic;
 use vm::{eval, LocalContext, Environment};
 use vm::costs::{cost_functions, CostTracker};
 use std::convert::{TryFrom};
 
 use vm::database::ClarityDatabase;
 
-enum MintAssetErrorCodes { ALREADY_EXIST = 1 }
-enum MintTokenErrorCodes { NON_POSITIVE_AMOUNT = 1 }
-enum TransferAssetErrorCodes { NOT_OWNED_BY = 1, SENDER_IS_RECIPIENT = 2, DOES_NOT_EXIST = 3 }
-enum TransferTokenErrorCodes { NOT_ENOUGH_BALANCE = 1, SENDER_IS_RECIPIENT = 2, NON_POSITIVE_AMOUNT = 3 }
-enum StxErrorCodes { NOT_ENOUGH_BALANCE = 1, SENDER_IS_RECIPIENT = 2, NON_POSITIVE_AMOUNT = 3, SENDER_IS_NOT_TX_SENDER = 4 }
+/// Every built-in error code a native asset/token/STX operation can return via `(err uN)`, kept
+/// in one enumerable registry instead of five separate per-operation enums so a returned code can
+/// never drift from its decoder: `code()`/`operation()`/`message()` and this type's variants are
+/// the single source of truth, and `clarity_ecode!` goes through `code()` below.
+///
+/// `special_stx_transfer` and `special_stx_burn` share the `"stx-transfer"` operation name and
+/// code space, since they report the same set of failures with the same meanings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseErrorCode {
+    StxNotEnoughBalance,
+    StxSenderIsRecipient,
+    StxNonPositiveAmount,
+    StxSenderIsNotTxSender,
+    FtMintNonPositiveAmount,
+    FtTransferNotEnoughBalance,
+    FtTransferSenderIsRecipient,
+    FtTransferNonPositiveAmount,
+    NftMintAlreadyExist,
+    NftTransferNotOwnedBy,
+    NftTransferSenderIsRecipient,
+    NftTransferDoesNotExist,
+}
+
+impl ResponseErrorCode {
+    pub fn code(&self) -> u128 {
+        match self {
+            ResponseErrorCode::StxNotEnoughBalance => 1,
+            ResponseErrorCode::StxSenderIsRecipient => 2,
+            ResponseErrorCode::StxNonPositiveAmount => 3,
+            ResponseErrorCode::StxSenderIsNotTxSender => 4,
+            ResponseErrorCode::FtMintNonPositiveAmount => 1,
+            ResponseErrorCode::FtTransferNotEnoughBalance => 1,
+            ResponseErrorCode::FtTransferSenderIsRecipient => 2,
+            ResponseErrorCode::FtTransferNonPositiveAmount => 3,
+            ResponseErrorCode::NftMintAlreadyExist => 1,
+            ResponseErrorCode::NftTransferNotOwnedBy => 1,
+            ResponseErrorCode::NftTransferSenderIsRecipient => 2,
+            ResponseErrorCode::NftTransferDoesNotExist => 3,
+        }
+    }
+
+    pub fn operation(&self) -> &'static str {
+        match self {
+            ResponseErrorCode::StxNotEnoughBalance
+            | ResponseErrorCode::StxSenderIsRecipient
+            | ResponseErrorCode::StxNonPositiveAmount
+            | ResponseErrorCode::StxSenderIsNotTxSender => "stx-transfer",
+            ResponseErrorCode::FtMintNonPositiveAmount => "ft-mint",
+            ResponseErrorCode::FtTransferNotEnoughBalance
+            | ResponseErrorCode::FtTransferSenderIsRecipient
+            | ResponseErrorCode::FtTransferNonPositiveAmount => "ft-transfer",
+            ResponseErrorCode::NftMintAlreadyExist => "nft-mint",
+            ResponseErrorCode::NftTransferNotOwnedBy
+            | ResponseErrorCode::NftTransferSenderIsRecipient
+            | ResponseErrorCode::NftTransferDoesNotExist => "nft-transfer",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            ResponseErrorCode::StxNotEnoughBalance => "sender does not have enough STX to cover the transfer",
+            ResponseErrorCode::StxSenderIsRecipient => "sender and recipient are the same principal",
+            ResponseErrorCode::StxNonPositiveAmount => "transfer amount must be positive",
+            ResponseErrorCode::StxSenderIsNotTxSender => "sender is not the same as the transaction's tx-sender",
+            ResponseErrorCode::FtMintNonPositiveAmount => "mint amount must be positive",
+            ResponseErrorCode::FtTransferNotEnoughBalance => "sender does not have enough of the token to cover the transfer",
+            ResponseErrorCode::FtTransferSenderIsRecipient => "sender and recipient are the same principal",
+            ResponseErrorCode::FtTransferNonPositiveAmount => "transfer amount must be positive",
+            ResponseErrorCode::NftMintAlreadyExist => "an asset with this identifier has already been minted",
+            ResponseErrorCode::NftTransferNotOwnedBy => "sender does not own this asset",
+            ResponseErrorCode::NftTransferSenderIsRecipient => "sender and recipient are the same principal",
+            ResponseErrorCode::NftTransferDoesNotExist => "no asset with this identifier has been minted",
+        }
+    }
+}
+
+/// Enumerates a fixed, exhaustive set of values. Stands in for the `enum-iterator` crate's
+/// `Sequence` trait, which this tree has no dependency on; `ResponseErrorCode::all()` is
+/// hand-maintained instead, and must list every variant exactly once.
+pub trait Sequence: Sized + 'static {
+    fn all() -> &'static [Self];
+}
+
+impl Sequence for ResponseErrorCode {
+    fn all() -> &'static [ResponseErrorCode] {
+        &[
+            ResponseErrorCode::StxNotEnoughBalance,
+            ResponseErrorCode::StxSenderIsRecipient,
+            ResponseErrorCode::StxNonPositiveAmount,
+            ResponseErrorCode::StxSenderIsNotTxSender,
+            ResponseErrorCode::FtMintNonPositiveAmount,
+            ResponseErrorCode::FtTransferNotEnoughBalance,
+            ResponseErrorCode::FtTransferSenderIsRecipient,
+            ResponseErrorCode::FtTransferNonPositiveAmount,
+            ResponseErrorCode::NftMintAlreadyExist,
+            ResponseErrorCode::NftTransferNotOwnedBy,
+            ResponseErrorCode::NftTransferSenderIsRecipient,
+            ResponseErrorCode::NftTransferDoesNotExist,
+        ]
+    }
+}
+
+/// Translate a `(operation, code)` pair -- as read back out of a returned `(err uN)` -- to the
+/// `ResponseErrorCode` that produced it, so tracing/test harnesses (and a future decode API) can
+/// turn e.g. `("ft-transfer", 1)` into `FtTransferNotEnoughBalance` without consulting the source.
+/// Returns `None` if no built-in code matches, which is the normal case for a contract's own
+/// user-defined `(err uN)` values.
+pub fn decode_response_error_code(operation: &str, code: u128) -> Option<ResponseErrorCode> {
+    ResponseErrorCode::all().iter()
+        .find(|rc| rc.operation() == operation && rc.code() == code)
+        .cloned()
+}
 
+// `clarity_ecode!` branches produce an in-language `(err uN)` `Value`, not a Rust `Error`, so
+// there's no diagnostic to attach a span to -- span attachment below only applies to the
+// `BadTokenName`/`TypeValueError` paths, which do return a real `Error`.
 macro_rules! clarity_ecode {
     ($thing:expr) => {
-        Ok(Value::err_uint($thing as u128))
+        Ok(Value::err_uint($thing.code()))
     }
 }
 
+/// Attach `arg`'s source span to `err` as a labeled diagnostic, so a `BadTokenName` or
+/// `TypeValueError` failure can be traced back to the exact argument expression that caused it
+/// (e.g. the `(ft-transfer? ...)` token-name or principal argument), rather than surfacing as a
+/// bare type error with no location.
+fn with_arg_span(err: Error, arg: &SymbolicExpression, label: &str) -> Error {
+    err.with_diagnostic(Diagnostic::new(label).with_label(arg.span, label))
+}
+
 /// Determine the "true" balance, given an unlock height and current burn block height
 pub fn stx_balance_with_unlock(balance_raw: u128, stx_locked: u128, unlock_height: u64, cur_burn_height: u64) -> (u128, bool) {
     let (balance, consolidated) = 
@@ -63,7 +182,7 @@ pub fn special_stx_balance(args: &[SymbolicExpression],
         let (total_balance, _) = stx_balance_consolidated(&mut env.global_context.database, principal);
         Ok(Value::UInt(total_balance))
     } else {
-        Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, owner).into())
+        Err(with_arg_span(CheckErrors::TypeValueError(TypeSignature::PrincipalType, owner).into(), &args[0], "expected a principal here"))
     }
 }
 
@@ -72,22 +191,22 @@ pub fn special_stx_balance(args: &[SymbolicExpression],
 /// and update its balance in addition to spending tokens out of it.
 pub fn stx_transfer_consolidated(env: &mut Environment, from: &PrincipalData, to: &PrincipalData, amount: u128) -> Result<Value> {
     if amount <= 0 {
-        return clarity_ecode!(StxErrorCodes::NON_POSITIVE_AMOUNT);
+        return clarity_ecode!(ResponseErrorCode::StxNonPositiveAmount);
     }
 
     if from == to {
-        return clarity_ecode!(StxErrorCodes::SENDER_IS_RECIPIENT);
+        return clarity_ecode!(ResponseErrorCode::StxSenderIsRecipient);
     }
 
     if Some(from.clone()) != env.sender.as_ref().map(|pval| pval.clone().expect_principal()) {
-        return clarity_ecode!(StxErrorCodes::SENDER_IS_NOT_TX_SENDER);
+        return clarity_ecode!(ResponseErrorCode::StxSenderIsNotTxSender);
     }
 
     let (from_bal, unlock) = stx_balance_consolidated(&mut env.global_context.database, from);
     let to_bal = env.global_context.database.get_account_stx_balance(to);
 
     if from_bal < amount {
-        return clarity_ecode!(StxErrorCodes::NOT_ENOUGH_BALANCE)
+        return clarity_ecode!(ResponseErrorCode::StxNotEnoughBalance)
     }
 
     let final_from_bal = from_bal - amount;
@@ -153,17 +272,17 @@ pub fn special_stx_burn(args: &[SymbolicExpression],
 
     if let (Value::Principal(ref from), Value::UInt(amount)) = (&from_val, amount_val) {
         if amount <= 0 {
-            return clarity_ecode!(StxErrorCodes::NON_POSITIVE_AMOUNT)
+            return clarity_ecode!(ResponseErrorCode::StxNonPositiveAmount)
         }
 
         if Some(&from_val) != env.sender.as_ref() {
-            return clarity_ecode!(StxErrorCodes::SENDER_IS_NOT_TX_SENDER)
+            return clarity_ecode!(ResponseErrorCode::StxSenderIsNotTxSender)
         }
 
         let (from_bal, unlock) = stx_balance_consolidated(&mut env.global_context.database, from);
         
         if from_bal < amount {
-            return clarity_ecode!(StxErrorCodes::NOT_ENOUGH_BALANCE)
+            return clarity_ecode!(ResponseErrorCode::StxNotEnoughBalance)
         }
 
         let final_from_bal = from_bal - amount;
@@ -202,7 +321,7 @@ pub fn special_mint_token(args: &[SymbolicExpression],
     runtime_cost!(cost_functions::FT_MINT, env, 0)?;
 
     let token_name = args[0].match_atom()
-        .ok_or(CheckErrors::BadTokenName)?;
+        .ok_or_else(|| with_arg_span(CheckErrors::BadTokenName.into(), &args[0], "expected a token name here"))?;
 
     let amount = eval(&args[1], env, context)?;
     let to =     eval(&args[2], env, context)?;
@@ -210,7 +329,7 @@ pub fn special_mint_token(args: &[SymbolicExpression],
     if let (Value::UInt(amount),
             Value::Principal(ref to_principal)) = (amount, to) {
         if amount <= 0 {
-            return clarity_ecode!(MintTokenErrorCodes::NON_POSITIVE_AMOUNT);
+            return clarity_ecode!(ResponseErrorCode::FtMintNonPositiveAmount);
         }
 
         env.global_context.database.checked_increase_token_supply(
@@ -244,7 +363,7 @@ pub fn special_mint_asset(args: &[SymbolicExpression],
     check_argument_count(3, args)?;
 
     let asset_name = args[0].match_atom()
-        .ok_or(CheckErrors::BadTokenName)?;
+        .ok_or_else(|| with_arg_span(CheckErrors::BadTokenName.into(), &args[0], "expected an asset name here"))?;
 
     let asset =  eval(&args[1], env, context)?;
     let to    =  eval(&args[2], env, context)?;
@@ -254,13 +373,13 @@ pub fn special_mint_asset(args: &[SymbolicExpression],
     runtime_cost!(cost_functions::NFT_MINT, env, expected_asset_type.size())?;
 
     if !expected_asset_type.admits(&asset) {
-        return Err(CheckErrors::TypeValueError(expected_asset_type, asset).into())
+        return Err(with_arg_span(CheckErrors::TypeValueError(expected_asset_type, asset).into(), &args[1], "expected this asset value to match the defined asset type"))
     }
 
     if let Value::Principal(ref to_principal) = to {
         match env.global_context.database.get_nft_owner(&env.contract_context.contract_identifier, asset_name, &asset) {
             Err(Error::Runtime(RuntimeErrorType::NoSuchToken, _)) => Ok(()),
-            Ok(_owner) => return clarity_ecode!(MintAssetErrorCodes::ALREADY_EXIST),
+            Ok(_owner) => return clarity_ecode!(ResponseErrorCode::NftMintAlreadyExist),
             Err(e) => Err(e)
         }?;
 
@@ -277,7 +396,7 @@ pub fn special_mint_asset(args: &[SymbolicExpression],
 
         Ok(Value::okay_true())
     } else {
-        Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, to).into())
+        Err(with_arg_span(CheckErrors::TypeValueError(TypeSignature::PrincipalType, to).into(), &args[2], "expected a principal here"))
     }
 }
 
@@ -287,7 +406,7 @@ pub fn special_transfer_asset(args: &[SymbolicExpression],
     check_argument_count(4, args)?;
 
     let asset_name = args[0].match_atom()
-        .ok_or(CheckErrors::BadTokenName)?;
+        .ok_or_else(|| with_arg_span(CheckErrors::BadTokenName.into(), &args[0], "expected an asset name here"))?;
 
     let asset =  eval(&args[1], env, context)?;
     let from  =  eval(&args[2], env, context)?;
@@ -298,27 +417,27 @@ pub fn special_transfer_asset(args: &[SymbolicExpression],
     runtime_cost!(cost_functions::NFT_TRANSFER, env, expected_asset_type.size())?;
 
     if !expected_asset_type.admits(&asset) {
-        return Err(CheckErrors::TypeValueError(expected_asset_type, asset).into())
+        return Err(with_arg_span(CheckErrors::TypeValueError(expected_asset_type, asset).into(), &args[1], "expected this asset value to match the defined asset type"))
     }
 
     if let (Value::Principal(ref from_principal),
             Value::Principal(ref to_principal)) = (from, to) {
 
         if from_principal == to_principal {
-            return clarity_ecode!(TransferAssetErrorCodes::SENDER_IS_RECIPIENT)
+            return clarity_ecode!(ResponseErrorCode::NftTransferSenderIsRecipient)
         }
 
         let current_owner = match env.global_context.database.get_nft_owner(&env.contract_context.contract_identifier, asset_name, &asset) {
             Ok(owner) => Ok(owner),
             Err(Error::Runtime(RuntimeErrorType::NoSuchToken, _)) => {
-                return clarity_ecode!(TransferAssetErrorCodes::DOES_NOT_EXIST)
+                return clarity_ecode!(ResponseErrorCode::NftTransferDoesNotExist)
             },
             Err(e) => Err(e)
         }?;
             
 
         if current_owner != *from_principal {
-            return clarity_ecode!(TransferAssetErrorCodes::NOT_OWNED_BY)
+            return clarity_ecode!(ResponseErrorCode::NftTransferNotOwnedBy)
         }
 
         env.add_memory(TypeSignature::PrincipalType.size() as u64)?;
@@ -348,7 +467,7 @@ pub fn special_transfer_token(args: &[SymbolicExpression],
     runtime_cost!(cost_functions::FT_TRANSFER, env, 0)?;
 
     let token_name = args[0].match_atom()
-        .ok_or(CheckErrors::BadTokenName)?;
+        .ok_or_else(|| with_arg_span(CheckErrors::BadTokenName.into(), &args[0], "expected a token name here"))?;
 
     let amount = eval(&args[1], env, context)?;
     let from =   eval(&args[2], env, context)?;
@@ -358,17 +477,17 @@ pub fn special_transfer_token(args: &[SymbolicExpression],
             Value::Principal(ref from_principal),
             Value::Principal(ref to_principal)) = (amount, from, to) {
         if amount <= 0 {
-            return clarity_ecode!(TransferTokenErrorCodes::NON_POSITIVE_AMOUNT)
+            return clarity_ecode!(ResponseErrorCode::FtTransferNonPositiveAmount)
         }
 
         if from_principal == to_principal {
-            return clarity_ecode!(TransferTokenErrorCodes::SENDER_IS_RECIPIENT)
+            return clarity_ecode!(ResponseErrorCode::FtTransferSenderIsRecipient)
         }
 
         let from_bal = env.global_context.database.get_ft_balance(&env.contract_context.contract_identifier, token_name, from_principal)?;
 
         if from_bal < amount {
-            return clarity_ecode!(TransferTokenErrorCodes::NOT_ENOUGH_BALANCE)
+            return clarity_ecode!(ResponseErrorCode::FtTransferNotEnoughBalance)
         }
 
         let final_from_bal = from_bal - amount;
@@ -408,7 +527,7 @@ pub fn special_get_balance(args: &[SymbolicExpression],
     runtime_cost!(cost_functions::FT_BALANCE, env, 0)?;
 
     let token_name = args[0].match_atom()
-        .ok_or(CheckErrors::BadTokenName)?;
+        .ok_or_else(|| with_arg_span(CheckErrors::BadTokenName.into(), &args[0], "expected a token name here"))?;
 
     let owner = eval(&args[1], env, context)?;
 
@@ -416,7 +535,7 @@ pub fn special_get_balance(args: &[SymbolicExpression],
         let balance = env.global_context.database.get_ft_balance(&env.contract_context.contract_identifier, token_name, principal)?;
         Ok(Value::UInt(balance))
     } else {
-        Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, owner).into())
+        Err(with_arg_span(CheckErrors::TypeValueError(TypeSignature::PrincipalType, owner).into(), &args[1], "expected a principal here"))
     }
 
 }
@@ -427,7 +546,7 @@ pub fn special_get_owner(args: &[SymbolicExpression],
     check_argument_count(2, args)?;
 
     let asset_name = args[0].match_atom()
-        .ok_or(CheckErrors::BadTokenName)?;
+        .ok_or_else(|| with_arg_span(CheckErrors::BadTokenName.into(), &args[0], "expected an asset name here"))?;
 
     let asset = eval(&args[1], env, context)?;
     let expected_asset_type = env.global_context.database.get_nft_key_type(&env.contract_context.contract_identifier, asset_name)?;
@@ -435,7 +554,7 @@ pub fn special_get_owner(args: &[SymbolicExpression],
     runtime_cost!(cost_functions::NFT_OWNER, env, expected_asset_type.size())?;
 
     if !expected_asset_type.admits(&asset) {
-        return Err(CheckErrors::TypeValueError(expected_asset_type, asset).into())
+        return Err(with_arg_span(CheckErrors::TypeValueError(expected_asset_type, asset).into(), &args[1], "expected this asset value to match the defined asset type"))
     }
 
     match env.global_context.database.get_nft_owner(&env.contract_context.contract_identifier, asset_name, &asset) {
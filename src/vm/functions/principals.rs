@@ -0,0 +1,441 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// Natives for pulling a principal apart into its `(version, hash-bytes[, name])` components and
+// putting one back together, plus bridging those components to the canonical c32check string a
+// Stacks address is written as.
+
+use chainstate::stacks::address::{c32_checksum, c32_decode, c32_encode};
+use chainstate::stacks::{
+    C32_ADDRESS_VERSION_MAINNET_MULTISIG, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+    C32_ADDRESS_VERSION_TESTNET_MULTISIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+};
+use vm::costs::cost_functions::ClarityCostFunction;
+use vm::costs::runtime_cost;
+use vm::errors::{CheckErrors, Error as VmError, InterpreterResult as Result, check_argument_count};
+use vm::representations::SymbolicExpression;
+use vm::types::{PrincipalData, TypeSignature, Value};
+use vm::{eval, Environment, LocalContext};
+
+/// `version_byte` is a single mainnet or testnet address version, per the constants in
+/// `chainstate::stacks`.
+pub fn version_matches_mainnet(version: u8) -> bool {
+    version == C32_ADDRESS_VERSION_MAINNET_SINGLESIG || version == C32_ADDRESS_VERSION_MAINNET_MULTISIG
+}
+
+pub fn version_matches_testnet(version: u8) -> bool {
+    version == C32_ADDRESS_VERSION_TESTNET_SINGLESIG || version == C32_ADDRESS_VERSION_TESTNET_MULTISIG
+}
+
+/// Whether `version` belongs to the network this contract call is itself running on.
+pub fn version_matches_current_network(version: u8, mainnet: bool) -> bool {
+    if mainnet {
+        version_matches_mainnet(version)
+    } else {
+        version_matches_testnet(version)
+    }
+}
+
+/// `(err { error_int: uint, value: none })`, for a failure that has no offending value worth
+/// surfacing to the caller.
+fn create_principal_true_error_response(error_int: u128) -> Result<Value> {
+    create_principal_error_response(error_int, Value::none())
+}
+
+/// `(err { error_int: uint, value: (some value) })`, for a failure where `value` (the rejected
+/// bytes or string) helps client tooling explain why construction or parsing failed.
+fn create_principal_value_error_response(error_int: u128, value: Value) -> Result<Value> {
+    create_principal_error_response(error_int, Value::some(value)?)
+}
+
+fn create_principal_error_response(error_int: u128, value_field: Value) -> Result<Value> {
+    let tuple = Value::tuple_from_data(vec![
+        ("error_int".to_string(), Value::UInt(error_int)),
+        ("value".to_string(), value_field),
+    ])?;
+    Value::error(tuple)
+}
+
+/// `true` iff `name` is a syntactically valid `ContractName`: 1-128 ASCII characters, the first
+/// drawn from the same alphanumeric-plus-`-!?+<>=/*` set every character is.
+fn is_valid_contract_name(name: &str) -> bool {
+    if name.is_empty() || name.len() > 128 {
+        return false;
+    }
+    name.bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"-!?+<>=/*".contains(&b))
+}
+
+/// `version` and `hash20` concatenated, run through the same `c32_checksum` (double-SHA256)
+/// `chainstate::stacks::address` uses for a `StacksAddress`'s own checksum -- a principal's
+/// string and a `StacksAddress`'s string must agree on what a given (version, hash) pair's
+/// checksum is.
+fn principal_checksum(version: u8, hash20: &[u8]) -> [u8; 4] {
+    let mut preimage = Vec::with_capacity(21);
+    preimage.push(version);
+    preimage.extend_from_slice(hash20);
+    c32_checksum(&preimage)
+}
+
+/// `"S" ++ c32(version) ++ c32(hash20 ++ checksum)`, with `"." ++ contract_name` appended for a
+/// contract principal. A version byte is always a single c32 character (it's always < 32), so
+/// this never needs a length prefix to stay unambiguous on the way back in. Built on the same
+/// `c32_encode`/`c32_checksum` primitives `StacksAddress::to_c32_string` uses, so a principal's
+/// rendered string and a `StacksAddress`'s rendered string agree for the same (version, hash).
+pub(crate) fn encode_principal_string(version: u8, hash20: &[u8], contract_name: Option<&str>) -> String {
+    let checksum = principal_checksum(version, hash20);
+    let mut payload = Vec::with_capacity(24);
+    payload.extend_from_slice(hash20);
+    payload.extend_from_slice(&checksum);
+
+    let mut rendered = String::from("S");
+    rendered.push_str(&c32_encode(&[version]));
+    rendered.push_str(&c32_encode(&payload));
+    if let Some(name) = contract_name {
+        rendered.push('.');
+        rendered.push_str(name);
+    }
+    rendered
+}
+
+enum DecodePrincipalStringError {
+    Malformed,
+    ChecksumMismatch,
+    InvalidName,
+}
+
+fn decode_principal_string(
+    rendered: &str,
+) -> std::result::Result<(u8, [u8; 20], Option<String>), DecodePrincipalStringError> {
+    let body = rendered.strip_prefix('S').ok_or(DecodePrincipalStringError::Malformed)?;
+
+    let (principal_part, contract_name) = match body.find('.') {
+        Some(idx) => (&body[..idx], Some(body[idx + 1..].to_string())),
+        None => (body, None),
+    };
+
+    if principal_part.is_empty() {
+        return Err(DecodePrincipalStringError::Malformed);
+    }
+
+    let version = c32_decode(&principal_part[0..1], 1)
+        .ok_or(DecodePrincipalStringError::Malformed)?[0];
+
+    let payload = c32_decode(&principal_part[1..], 24).ok_or(DecodePrincipalStringError::Malformed)?;
+    let (hash20_slice, checksum) = payload.split_at(20);
+
+    if principal_checksum(version, hash20_slice)[..] != checksum[..] {
+        return Err(DecodePrincipalStringError::ChecksumMismatch);
+    }
+
+    if let Some(ref name) = contract_name {
+        if !is_valid_contract_name(name) {
+            return Err(DecodePrincipalStringError::InvalidName);
+        }
+    }
+
+    let mut hash20 = [0u8; 20];
+    hash20.copy_from_slice(hash20_slice);
+
+    Ok((version, hash20, contract_name))
+}
+
+/// The stable `error_int` values `special_principal_construct` can return, so client tooling can
+/// switch on *why* construction failed rather than just that it did. Each one pairs with a
+/// `value` carrying the rejected input, where one was available to report:
+///   1 - `ERR_VERSION_EMPTY`: the version argument wasn't a 1-byte buffer at all.
+///   2 - `ERR_VERSION_OUT_OF_RANGE`: the version byte was >= 32, outside the c32 alphabet's range.
+///   3 - `ERR_HASH_UNDERSIZED`: the hash-bytes argument wasn't a 20-byte buffer.
+///   4 - `ERR_NAME_TOO_SHORT`: the contract-name argument was an empty string.
+///   5 - `ERR_NAME_NOT_VALID`: the contract-name argument wasn't a `string-ascii`, or failed
+///       `ContractName` validation (bad leading character, disallowed character, or too long).
+const ERR_VERSION_EMPTY: u128 = 1;
+const ERR_VERSION_OUT_OF_RANGE: u128 = 2;
+const ERR_HASH_UNDERSIZED: u128 = 3;
+const ERR_NAME_TOO_SHORT: u128 = 4;
+const ERR_NAME_NOT_VALID: u128 = 5;
+
+/// `(principal-construct? (buff 1) (buff 20)) -> (response principal { error_int: uint, value: (optional (buff 1)) })`
+/// `(principal-construct? (buff 1) (buff 20) (string-ascii 40))` additionally takes a contract name.
+/// See the `ERR_*` constants above for the distinct failure causes this can report.
+pub fn special_principal_construct(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    if args.len() != 2 && args.len() != 3 {
+        return Err(CheckErrors::IncorrectArgumentCount(2, args.len()).into());
+    }
+
+    let version_val = eval(&args[0], env, context)?;
+    let hash_val = eval(&args[1], env, context)?;
+    let name_val = if args.len() == 3 {
+        Some(eval(&args[2], env, context)?)
+    } else {
+        None
+    };
+
+    // The work below is dominated by assembling and validating the 21-byte principal (plus,
+    // for a contract principal, copying out its name) -- charge on the combined byte length of
+    // whatever was actually passed in, the same way `c32_encode`/`c32_decode`'s own cost in
+    // `special_principal_to_string`/`special_string_to_principal` scales with their input.
+    let cost_input = version_val.size() as u64
+        + hash_val.size() as u64
+        + name_val.as_ref().map(|v| v.size() as u64).unwrap_or(0);
+    runtime_cost(ClarityCostFunction::PrincipalConstruct, env, cost_input)?;
+
+    let version_byte = match version_val {
+        Value::Buffer(ref buff) if buff.as_bytes().len() == 1 => buff.as_bytes()[0],
+        other => return create_principal_value_error_response(ERR_VERSION_EMPTY, other),
+    };
+    if version_byte >= 32 {
+        return create_principal_value_error_response(ERR_VERSION_OUT_OF_RANGE, Value::buff_from(vec![version_byte])?);
+    }
+
+    let hash20 = match hash_val {
+        Value::Buffer(ref buff) if buff.as_bytes().len() == 20 => {
+            let mut bytes = [0u8; 20];
+            bytes.copy_from_slice(buff.as_bytes());
+            bytes
+        }
+        other => return create_principal_value_error_response(ERR_HASH_UNDERSIZED, other),
+    };
+
+    let contract_name = if let Some(name_val) = name_val {
+        let name = match name_val {
+            Value::StringAscii(ref buff) => String::from_utf8_lossy(buff.as_bytes()).into_owned(),
+            other => return create_principal_value_error_response(ERR_NAME_NOT_VALID, other),
+        };
+        if name.is_empty() {
+            return create_principal_value_error_response(ERR_NAME_TOO_SHORT, Value::string_ascii_from_bytes(name.into_bytes())?);
+        }
+        if !is_valid_contract_name(&name) {
+            return create_principal_value_error_response(ERR_NAME_NOT_VALID, Value::string_ascii_from_bytes(name.into_bytes())?);
+        }
+        Some(name)
+    } else {
+        None
+    };
+
+    let mut principal_bytes = [0u8; 21];
+    principal_bytes[0] = version_byte;
+    principal_bytes[1..].copy_from_slice(&hash20);
+
+    let principal = match contract_name {
+        Some(name) => Value::contract_principal(principal_bytes, name),
+        None => Value::standard_principal(principal_bytes),
+    };
+
+    Value::okay(principal)
+}
+
+/// `(principal-destruct? principal) -> (response { version: (buff 1), hash-bytes: (buff 20), name: (optional (string-ascii 40)) } ..)`
+pub fn special_principal_parse(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    check_argument_count(1, args)?;
+
+    let principal_val = eval(&args[0], env, context)?;
+    runtime_cost(ClarityCostFunction::PrincipalParse, env, principal_val.size() as u64)?;
+
+    let principal = match principal_val {
+        Value::Principal(ref p) => p.clone(),
+        _ => return Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, principal_val).into()),
+    };
+
+    let (version, hash20, name) = match &principal {
+        PrincipalData::Standard(bytes) => (bytes[0], bytes[1..21].to_vec(), None),
+        PrincipalData::Contract(bytes, name) => (bytes[0], bytes[1..21].to_vec(), Some(name.clone())),
+    };
+
+    let version_value = Value::buff_from(vec![version])?;
+    let hash_value = Value::buff_from(hash20)?;
+    let name_value = match name {
+        Some(n) => Value::some(Value::string_ascii_from_bytes(n.into_bytes())?)?,
+        None => Value::none(),
+    };
+
+    let tuple = Value::tuple_from_data(vec![
+        ("version".to_string(), version_value),
+        ("hash-bytes".to_string(), hash_value),
+        ("name".to_string(), name_value),
+    ])?;
+
+    Value::okay(tuple)
+}
+
+/// `(is-standard principal) -> bool`
+pub fn special_is_standard(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    check_argument_count(1, args)?;
+
+    let principal_val = eval(&args[0], env, context)?;
+    runtime_cost(ClarityCostFunction::IsStandard, env, principal_val.size() as u64)?;
+
+    match principal_val {
+        Value::Principal(PrincipalData::Standard(_)) => Ok(Value::Bool(true)),
+        Value::Principal(PrincipalData::Contract(..)) => Ok(Value::Bool(false)),
+        _ => Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, principal_val).into()),
+    }
+}
+
+/// `(principal-to-string principal) -> (response (string-ascii 48) ..)`
+///
+/// Renders `principal` as the c32check string a wallet or explorer would show it as, gated on
+/// `principal`'s version byte actually belonging to the network this call is itself running on --
+/// there's no well-formed string to produce for a principal from the other network.
+pub fn special_principal_to_string(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    check_argument_count(1, args)?;
+
+    let principal_val = eval(&args[0], env, context)?;
+    let principal = match principal_val {
+        Value::Principal(ref p) => p.clone(),
+        _ => return Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, principal_val).into()),
+    };
+
+    let (version, hash20, name) = match &principal {
+        PrincipalData::Standard(bytes) => (bytes[0], &bytes[1..21], None),
+        PrincipalData::Contract(bytes, name) => (bytes[0], &bytes[1..21], Some(name.as_str())),
+    };
+
+    // `c32_encode` and the double-SHA256 checksum both scale with the hash plus any contract
+    // name, not with the fixed 1-byte version -- charge on that combined length.
+    let cost_input = hash20.len() as u64 + name.map(|n| n.len()).unwrap_or(0) as u64;
+    runtime_cost(ClarityCostFunction::PrincipalToString, env, cost_input)?;
+
+    if !version_matches_current_network(version, env.global_context.mainnet) {
+        return create_principal_true_error_response(1);
+    }
+
+    let rendered = encode_principal_string(version, hash20, name);
+    Value::okay(Value::string_ascii_from_bytes(rendered.into_bytes())?)
+}
+
+/// `(string-to-principal (string-ascii 48)) -> (response principal ..)`
+///
+/// The inverse of `principal-to-string`: parses a c32check address string back into a
+/// `Value::Principal`, verifying its checksum and (for a contract address) its `ContractName`
+/// along the way.
+pub fn special_string_to_principal(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    check_argument_count(1, args)?;
+
+    let string_val = eval(&args[0], env, context)?;
+    let rendered = match string_val {
+        Value::StringAscii(ref buff) => String::from_utf8_lossy(buff.as_bytes()).into_owned(),
+        _ => {
+            return Err(VmError::InvalidArguments(
+                "string-to-principal expects a string-ascii argument".to_string(),
+            )
+            .into())
+        }
+    };
+
+    // `c32_decode` and the checksum recomputation both scale with the decoded string's length.
+    runtime_cost(ClarityCostFunction::StringToPrincipal, env, rendered.len() as u64)?;
+
+    let (version, hash20, contract_name) = match decode_principal_string(&rendered) {
+        Ok(parsed) => parsed,
+        Err(DecodePrincipalStringError::ChecksumMismatch) => return create_principal_true_error_response(2),
+        Err(DecodePrincipalStringError::InvalidName) => return create_principal_true_error_response(4),
+        Err(DecodePrincipalStringError::Malformed) => return create_principal_true_error_response(1),
+    };
+
+    if !version_matches_current_network(version, env.global_context.mainnet) {
+        return create_principal_true_error_response(3);
+    }
+
+    let mut principal_bytes = [0u8; 21];
+    principal_bytes[0] = version;
+    principal_bytes[1..].copy_from_slice(&hash20);
+
+    let principal = match contract_name {
+        Some(name) => Value::contract_principal(principal_bytes, name),
+        None => Value::standard_principal(principal_bytes),
+    };
+
+    Value::okay(principal)
+}
+
+/// The other-network counterpart of a singlesig or multisig version byte, or `None` if `version`
+/// matches neither class (e.g. it's already out of range).
+fn convert_version_network(version: u8) -> Option<u8> {
+    match version {
+        C32_ADDRESS_VERSION_MAINNET_SINGLESIG => Some(C32_ADDRESS_VERSION_TESTNET_SINGLESIG),
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG => Some(C32_ADDRESS_VERSION_MAINNET_SINGLESIG),
+        C32_ADDRESS_VERSION_MAINNET_MULTISIG => Some(C32_ADDRESS_VERSION_TESTNET_MULTISIG),
+        C32_ADDRESS_VERSION_TESTNET_MULTISIG => Some(C32_ADDRESS_VERSION_MAINNET_MULTISIG),
+        _ => None,
+    }
+}
+
+/// `(principal-convert-network principal) -> (response principal ..)`
+///
+/// Returns the same 20-byte hash and contract name, re-addressed to the other network: a mainnet
+/// singlesig principal becomes a testnet singlesig principal and vice versa, likewise for
+/// multisig. Useful for test harnesses and cross-network deployment tooling that need to
+/// translate an address deterministically rather than re-deriving it from a key.
+pub fn special_principal_convert_network(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    check_argument_count(1, args)?;
+
+    let principal_val = eval(&args[0], env, context)?;
+    let principal = match principal_val {
+        Value::Principal(ref p) => p.clone(),
+        _ => return Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, principal_val).into()),
+    };
+
+    let (version, hash20, name) = match &principal {
+        PrincipalData::Standard(bytes) => (bytes[0], &bytes[1..21], None),
+        PrincipalData::Contract(bytes, name) => (bytes[0], &bytes[1..21], Some(name.clone())),
+    };
+
+    // Re-addressing scales with the hash plus any contract name being carried over, the same
+    // cost shape `principal-to-string` charges.
+    let cost_input = hash20.len() as u64 + name.as_ref().map(|n| n.len()).unwrap_or(0) as u64;
+    runtime_cost(ClarityCostFunction::ConvertPrincipalVersion, env, cost_input)?;
+
+    let converted_version = match convert_version_network(version) {
+        Some(v) => v,
+        None => return create_principal_value_error_response(1, Value::buff_from(vec![version])?),
+    };
+
+    let mut principal_bytes = [0u8; 21];
+    principal_bytes[0] = converted_version;
+    principal_bytes[1..].copy_from_slice(hash20);
+
+    let converted = match name {
+        Some(name) => Value::contract_principal(principal_bytes, name),
+        None => Value::standard_principal(principal_bytes),
+    };
+
+    Value::okay(converted)
+}
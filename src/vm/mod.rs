@@ -23,6 +23,8 @@ pub mod errors;
 pub mod costs;
 
 pub mod types;
+pub mod size_checker;
+pub mod conversion;
 
 pub mod contracts;
 
@@ -31,6 +33,7 @@ pub mod clarity;
 pub mod contexts;
 pub mod database;
 pub mod representations;
+pub mod session;
 
 mod callables;
 mod functions;
@@ -164,7 +167,7 @@ pub fn apply(
             let arg_value = match eval(arg_x, env, context) {
                 Ok(x) => x,
                 Err(e) => {
-                    env.drop_memory(used_memory);
+                    let _ = env.drop_memory(used_memory);
                     env.call_stack.decr_apply_depth();
                     return Err(e);
                 }
@@ -173,7 +176,7 @@ pub fn apply(
             match env.add_memory(arg_use) {
                 Ok(_x) => {}
                 Err(e) => {
-                    env.drop_memory(used_memory);
+                    let _ = env.drop_memory(used_memory);
                     env.call_stack.decr_apply_depth();
                     return Err(Error::from(e));
                 }
@@ -194,7 +197,13 @@ pub fn apply(
             _ => panic!("Should be unreachable."),
         };
         add_stack_trace(&mut resp, env);
-        env.drop_memory(used_memory);
+        // only surface a memory-drop failure if `resp` itself succeeded -- the underlying
+        // evaluation error is the more useful one to report when both fail.
+        let resp = match (resp, env.drop_memory(used_memory)) {
+            (Ok(value), Ok(())) => Ok(value),
+            (Ok(_), Err(drop_err)) => Err(Error::from(drop_err)),
+            (Err(e), _) => Err(e),
+        };
         env.call_stack.remove(&identifier, track_recursion)?;
         resp
     }
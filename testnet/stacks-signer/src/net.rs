@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use slog::{slog_info, slog_warn};
 
 use stacks_common::{info, warn};
@@ -14,16 +15,38 @@ pub struct Message {
     pub sig: [u8; 32],
 }
 
+/// A pre-shared key used to authenticate `Message`s between signers. There's no PKI in this
+/// network yet, so "signing" here means a keyed hash over the bincode-serialized message body
+/// rather than a real asymmetric signature -- it proves the sender holds `0`, not a per-signer
+/// identity. Swapping this for a real keypair later only touches `sign`/`verify` below.
+#[derive(Clone)]
+pub struct SignerKey(pub [u8; 32]);
+
+impl SignerKey {
+    /// Compute the keyed-hash envelope signature for `msg`: `sha256(key || bincode(msg))`.
+    fn sign(&self, msg: &signing_round::MessageTypes) -> Result<[u8; 32], HttpNetError> {
+        let body = bincode::serialize(msg)?;
+        let mut hasher = Sha256::new();
+        hasher.input(&self.0);
+        hasher.input(&body);
+        let mut sig = [0u8; 32];
+        sig.copy_from_slice(hasher.result().as_slice());
+        Ok(sig)
+    }
+}
+
 pub struct HttpNet {
     pub stacks_node_url: String,
     in_queue: Vec<Message>,
+    signer_key: SignerKey,
 }
 
 impl HttpNet {
-    pub fn new(stacks_node_url: String, in_q: Vec<Message>) -> Self {
+    pub fn new(stacks_node_url: String, in_q: Vec<Message>, signer_key: SignerKey) -> Self {
         HttpNet {
             stacks_node_url,
             in_queue: in_q,
+            signer_key,
         }
     }
 }
@@ -35,6 +58,11 @@ pub trait Net {
     fn poll(&mut self, id: usize);
     fn next_message(&mut self) -> Option<Message>;
     fn send_message(&mut self, msg: Message) -> Result<(), Self::Error>;
+
+    /// Check `msg`'s signature envelope against this net's signer key. Messages failing this
+    /// check must never be handed to `next_message` -- the coordinator trusts whatever comes back
+    /// from `next_message` unconditionally.
+    fn verify(&self, msg: &Message) -> bool;
 }
 
 impl Net for HttpNet {
@@ -51,8 +79,12 @@ impl Net for HttpNet {
                     200 => {
                         match bincode::deserialize_from::<_, Message>(response.into_reader()) {
                             Ok(msg) => {
-                                info!("received {:?}", &msg);
-                                self.in_queue.push(msg);
+                                if self.verify(&msg) {
+                                    info!("received {:?}", &msg);
+                                    self.in_queue.push(msg);
+                                } else {
+                                    warn!("dropping message with invalid signature from {}", url);
+                                }
                             }
                             Err(_e) => {}
                         };
@@ -70,7 +102,9 @@ impl Net for HttpNet {
         self.in_queue.pop()
     }
 
-    fn send_message(&mut self, msg: Message) -> Result<(), Self::Error> {
+    fn send_message(&mut self, mut msg: Message) -> Result<(), Self::Error> {
+        msg.sig = self.signer_key.sign(&msg.msg)?;
+
         let req = ureq::post(&self.stacks_node_url);
         let bytes = bincode::serialize(&msg)?;
         let result = req.send_bytes(&bytes[..]);
@@ -92,6 +126,13 @@ impl Net for HttpNet {
 
         Ok(())
     }
+
+    fn verify(&self, msg: &Message) -> bool {
+        match self.signer_key.sign(&msg.msg) {
+            Ok(expected) => expected == msg.sig,
+            Err(_) => false,
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]